@@ -0,0 +1,338 @@
+//! Golden-image regression testing for rendered frames.
+//!
+//! Rendered frames are compared against reference PNG files with a per-channel tolerance. When a
+//! frame does not match, the actual image and a diff image are written next to the reference to
+//! help track down the regression.
+//!
+//! Set the `PIXELS_BLESS` environment variable to write the rendered frames as the new reference
+//! images instead of comparing them.
+
+use pixels::wgpu::{PowerPreference, RequestAdapterOptions, TextureFormat};
+use pixels::{Headless, Pixels, PixelsBuilder, RgbaImage};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// All the ways in which comparing against a reference image can fail.
+#[derive(Error, Debug)]
+pub enum GoldenError {
+    /// The reference image could not be read.
+    #[error("Unable to read reference image `{0}`: {1}")]
+    Read(PathBuf, #[source] png::DecodingError),
+    /// An image could not be written.
+    #[error("Unable to write image `{0}`: {1}")]
+    Write(PathBuf, #[source] png::EncodingError),
+    /// The rendered frame and reference image have different dimensions.
+    #[error("Size mismatch: rendered {actual:?}, reference {expected:?}")]
+    SizeMismatch {
+        actual: (u32, u32),
+        expected: (u32, u32),
+    },
+    /// Some pixels differ by more than the tolerance.
+    #[error("{count} pixels differ by up to {max_difference}; see `{diff}`")]
+    Mismatch {
+        count: usize,
+        max_difference: u8,
+        diff: PathBuf,
+    },
+}
+
+/// Build a headless pixel buffer on a software adapter.
+///
+/// Software rendering keeps the output stable across machines with different GPUs.
+///
+/// # Errors
+///
+/// Returns [`pixels::Error::AdapterNotFound`] when the host has no software adapter.
+pub fn build_headless(
+    width: u32,
+    height: u32,
+    surface_width: u32,
+    surface_height: u32,
+) -> Result<Pixels<'static>, pixels::Error> {
+    headless_builder(width, height, surface_width, surface_height).build()
+}
+
+/// Create a builder for a headless pixel buffer on a software adapter, for further
+/// customization.
+///
+/// See [`build_headless`].
+pub fn headless_builder<'req, 'dev>(
+    width: u32,
+    height: u32,
+    surface_width: u32,
+    surface_height: u32,
+) -> PixelsBuilder<'req, 'dev, 'static, Headless> {
+    PixelsBuilder::new_headless(width, height, surface_width, surface_height)
+        .request_adapter_options(RequestAdapterOptions {
+            power_preference: PowerPreference::None,
+            force_fallback_adapter: true,
+            compatible_surface: None,
+        })
+}
+
+/// Build a headless pixel buffer on a software adapter, or `None` to skip the test when the host
+/// has no software adapter.
+///
+/// See [`build_headless`].
+///
+/// # Panics
+///
+/// Panics when the pixel buffer cannot be created for any other reason.
+#[track_caller]
+pub fn try_headless(
+    width: u32,
+    height: u32,
+    surface_width: u32,
+    surface_height: u32,
+) -> Option<Pixels<'static>> {
+    try_build(headless_builder(
+        width,
+        height,
+        surface_width,
+        surface_height,
+    ))
+}
+
+/// Build a customized headless pixel buffer, or `None` to skip the test when the host has no
+/// software adapter.
+///
+/// See [`headless_builder`].
+///
+/// # Panics
+///
+/// Panics when the pixel buffer cannot be created for any other reason.
+#[track_caller]
+pub fn try_build(builder: PixelsBuilder<'_, '_, 'static, Headless>) -> Option<Pixels<'static>> {
+    skip_without_adapter(builder.build())
+}
+
+/// Build a headless pixel buffer filled with the [`test_pattern`], or `None` to skip the test
+/// when the host has no software adapter.
+///
+/// The pixel buffer, the render target, and the surface all use `format`, so that the rendered
+/// frame has no color space conversion. Indexed pixel buffers with the `R8Uint` format render
+/// with the default formats instead, and every pixel gets a distinct palette index.
+///
+/// # Panics
+///
+/// Panics when the pixel buffer cannot be created for any other reason.
+#[track_caller]
+pub fn pattern_buffer(
+    width: u32,
+    height: u32,
+    surface_width: u32,
+    surface_height: u32,
+    format: TextureFormat,
+) -> Option<Pixels<'static>> {
+    let indexed = format == TextureFormat::R8Uint;
+    let mut builder =
+        headless_builder(width, height, surface_width, surface_height).texture_format(format);
+    if !indexed {
+        builder = builder
+            .render_texture_format(format)
+            .surface_texture_format(format);
+    }
+    let mut pixels = try_build(builder)?;
+
+    if indexed {
+        for (i, index) in pixels.frame_mut().iter_mut().enumerate() {
+            *index = i as u8;
+        }
+    } else {
+        test_pattern(pixels.frame_mut(), width, height);
+    }
+
+    Some(pixels)
+}
+
+/// Unwrap the result of creating something on the GPU, or `None` to skip the test when the host
+/// has no adapter.
+///
+/// # Panics
+///
+/// Panics on any error other than [`pixels::Error::AdapterNotFound`].
+#[track_caller]
+pub fn skip_without_adapter<T>(result: Result<T, pixels::Error>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(pixels::Error::AdapterNotFound) => {
+            let thread = std::thread::current();
+            let test = thread.name().unwrap_or("test");
+            eprintln!("Skipping `{test}`: no software adapter available");
+            None
+        }
+        Err(err) => panic!("Unable to initialize the GPU: {err}"),
+    }
+}
+
+/// Fill an `RGBA` frame with a test pattern.
+///
+/// The pattern has a distinct color for every pixel, and hard edges in both directions, which
+/// makes scaling and offset errors easy to spot.
+pub fn test_pattern(frame: &mut [u8], width: u32, height: u32) {
+    for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+        let x = (i % width as usize) as u32;
+        let y = (i / width as usize) as u32;
+        let checker = if (x ^ y) & 1 == 0 { 0xff } else { 0x40 };
+
+        pixel.copy_from_slice(&[
+            (x * 255 / (width - 1).max(1)) as u8,
+            (y * 255 / (height - 1).max(1)) as u8,
+            checker,
+            0xff,
+        ]);
+    }
+}
+
+/// Compare an image against the reference PNG at `reference`.
+///
+/// Each color channel may differ by at most `tolerance`. On a mismatch, the rendered image is
+/// written to `<name>.actual.png` and a diff image to `<name>.diff.png` beside the reference. The
+/// diff image shows mismatched pixels in red over a faded copy of the rendered image.
+///
+/// When the `PIXELS_BLESS` environment variable is set, the image is written to `reference`
+/// instead.
+///
+/// # Errors
+///
+/// Returns an error when the images differ, or when reading or writing files fails.
+pub fn compare(
+    image: &RgbaImage,
+    reference: impl AsRef<Path>,
+    tolerance: u8,
+) -> Result<(), GoldenError> {
+    let reference = reference.as_ref();
+    if std::env::var_os("PIXELS_BLESS").is_some() {
+        return write_png(reference, image);
+    }
+
+    let expected = read_png(reference)?;
+    let actual_size = (image.width(), image.height());
+    let expected_size = (expected.width(), expected.height());
+    if actual_size != expected_size {
+        write_png(sibling(reference, "actual"), image)?;
+
+        return Err(GoldenError::SizeMismatch {
+            actual: actual_size,
+            expected: expected_size,
+        });
+    }
+
+    let mut diff = image.clone();
+    let mut count = 0;
+    let mut max_difference = 0;
+    let pixels = image.pixels().chunks_exact(4);
+    let diff_pixels = diff.pixels_mut().chunks_exact_mut(4);
+    for ((actual, expected), diff) in pixels
+        .zip(expected.pixels().chunks_exact(4))
+        .zip(diff_pixels)
+    {
+        let difference = actual
+            .iter()
+            .zip(expected)
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap_or_default();
+        max_difference = max_difference.max(difference);
+
+        if difference > tolerance {
+            count += 1;
+            diff.copy_from_slice(&[0xff, 0, 0, 0xff]);
+        } else {
+            let luma = (actual[0] as u32 + actual[1] as u32 + actual[2] as u32) / 3;
+            let faded = (luma / 4) as u8;
+            diff.copy_from_slice(&[faded, faded, faded, 0xff]);
+        }
+    }
+
+    if count == 0 {
+        return Ok(());
+    }
+
+    let diff_path = sibling(reference, "diff");
+    write_png(sibling(reference, "actual"), image)?;
+    write_png(&diff_path, &diff)?;
+
+    Err(GoldenError::Mismatch {
+        count,
+        max_difference,
+        diff: diff_path,
+    })
+}
+
+/// Compare an image against a reference PNG, panicking with a descriptive message on failure.
+///
+/// See [`compare`] for details.
+///
+/// # Panics
+///
+/// Panics when the images differ, or when reading or writing files fails.
+#[track_caller]
+pub fn assert_golden(image: &RgbaImage, reference: impl AsRef<Path>, tolerance: u8) {
+    let reference = reference.as_ref();
+    if let Err(err) = compare(image, reference, tolerance) {
+        panic!("Golden image `{}` failed: {err}", reference.display());
+    }
+}
+
+/// Read a PNG file, converting it to 8-bit `RGBA`.
+///
+/// # Errors
+///
+/// Returns an error when the file cannot be read or decoded.
+pub fn read_png(path: impl AsRef<Path>) -> Result<RgbaImage, GoldenError> {
+    let path = path.as_ref();
+    let error = |err| GoldenError::Read(path.to_path_buf(), err);
+
+    let file = File::open(path).map_err(|err| error(err.into()))?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(error)?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(error)?;
+    pixels.truncate(info.buffer_size());
+
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => pixels,
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xff])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|&g| [g, g, g, 0xff]).collect(),
+        png::ColorType::Indexed => unreachable!("Palettes are expanded by the decoder"),
+    };
+
+    Ok(RgbaImage::new(info.width, info.height, pixels))
+}
+
+/// Write an image to an 8-bit `RGBA` PNG file.
+///
+/// # Errors
+///
+/// Returns an error when the file cannot be written.
+pub fn write_png(path: impl AsRef<Path>, image: &RgbaImage) -> Result<(), GoldenError> {
+    let path = path.as_ref();
+    let error = |err| GoldenError::Write(path.to_path_buf(), err);
+
+    let file = File::create(path).map_err(|err| error(err.into()))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(error)?;
+    writer.write_image_data(image.pixels()).map_err(error)?;
+
+    writer.finish().map_err(error)
+}
+
+/// Get the path for an output file beside a reference image, e.g. `name.diff.png`.
+fn sibling(reference: &Path, kind: &str) -> PathBuf {
+    let stem = reference.file_stem().unwrap_or_default().to_string_lossy();
+
+    reference.with_file_name(format!("{stem}.{kind}.png"))
+}
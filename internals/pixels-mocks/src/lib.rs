@@ -1,6 +1,8 @@
 #![deny(clippy::all)]
 #![forbid(unsafe_code)]
 
+pub mod golden;
+
 pub struct Window;
 
 impl raw_window_handle::HasWindowHandle for Window {
@@ -0,0 +1,122 @@
+use pixels::wgpu::TextureFormat;
+use pixels::{CrtMask, CrtOptions, Pixels, RgbaImage};
+use pixels_mocks::golden::pattern_buffer;
+
+const WIDTH: u32 = 4;
+const HEIGHT: u32 = 3;
+
+/// Build a `4x3` pixel buffer with the test pattern, rendered at 10x, or `None` without a
+/// software adapter.
+fn build() -> Option<Pixels<'static>> {
+    pattern_buffer(
+        WIDTH,
+        HEIGHT,
+        WIDTH * 10,
+        HEIGHT * 10,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+/// Options with every effect disabled.
+fn neutral() -> CrtOptions {
+    let mut options = CrtOptions::default();
+    options.scanlines = 0.0;
+    options.mask = CrtMask::None;
+    options.curvature = 0.0;
+    options.vignette = 0.0;
+    options.bloom = 0.0;
+
+    options
+}
+
+/// Get a single pixel from an image.
+fn pixel(image: &RgbaImage, x: u32, y: u32) -> [u8; 4] {
+    let i = ((y * image.width() + x) * 4) as usize;
+    image.pixels()[i..i + 4].try_into().unwrap()
+}
+
+/// The sum of the color channels of a pixel.
+fn brightness(image: &RgbaImage, x: u32, y: u32) -> u32 {
+    pixel(image, x, y)[..3].iter().map(|&c| u32::from(c)).sum()
+}
+
+/// Assert that two images are equal, allowing each color channel to differ by one.
+#[track_caller]
+fn assert_similar(actual: &RgbaImage, expected: &RgbaImage) {
+    let pixels = actual.pixels().iter().zip(expected.pixels());
+    for (i, (actual, expected)) in pixels.enumerate() {
+        assert!(
+            actual.abs_diff(*expected) <= 1,
+            "Byte {i}: expected {expected}, got {actual}"
+        );
+    }
+}
+
+#[test]
+fn neutral_options() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let expected = pixels.read_rendered_frame().unwrap();
+
+    pixels.set_crt(Some(neutral()));
+    assert_eq!(pixels.crt(), Some(&neutral()));
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_similar(&image, &expected);
+
+    pixels.set_crt(None);
+    assert_eq!(pixels.crt(), None);
+    assert!(pixels.context().crt_renderer().is_none());
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), expected.pixels());
+}
+
+#[test]
+fn scanlines() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let expected = pixels.read_rendered_frame().unwrap();
+
+    let mut options = neutral();
+    options.scanlines = 1.0;
+    pixels.set_crt(Some(options));
+    let image = pixels.read_rendered_frame().unwrap();
+
+    // The first buffer row covers surface rows 0 to 9; its center is the brightest
+    for x in [5, 15] {
+        let edge = brightness(&image, x, 0);
+        let center = brightness(&image, x, 5);
+        assert!(edge < center, "{edge} < {center}");
+        assert!(center.abs_diff(brightness(&expected, x, 5)) <= 3);
+    }
+
+    // Adjusting the options at runtime takes effect on the next frame
+    options.scanlines = 0.0;
+    pixels.set_crt(Some(options));
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_similar(&image, &expected);
+}
+
+#[test]
+fn curvature() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    assert_eq!(pixels.window_pos_to_pixel((1.0, 1.0)), Ok((0, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((10.0, 15.0)), Ok((1, 1)));
+
+    let mut options = neutral();
+    options.curvature = 0.5;
+    pixels.set_crt(Some(options));
+
+    // The center is not distorted, while the corners are pushed out of the screen
+    assert_eq!(pixels.window_pos_to_pixel((20.0, 15.0)), Ok((2, 1)));
+    assert_eq!(pixels.window_pos_to_pixel((10.0, 15.0)), Ok((0, 1)));
+    assert!(pixels.window_pos_to_pixel((1.0, 1.0)).is_err());
+
+    // The corners show the clear color
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(pixel(&image, 0, 0), [0, 0, 0, 255]);
+    assert_eq!(pixel(&image, 39, 29), [0, 0, 0, 255]);
+}
@@ -0,0 +1,234 @@
+use pixels::wgpu::{self, TextureFormat};
+use pixels::{CrtOptions, Pixels, PixelsDevice, RgbaImage};
+use pixels_mocks::golden::{headless_builder, pattern_buffer, skip_without_adapter, test_pattern};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const WIDTH: u32 = 4;
+const HEIGHT: u32 = 3;
+
+/// Build a `4x3` pixel buffer with the test pattern and a layer, or `None` without a software
+/// adapter.
+fn build() -> Option<Pixels<'static>> {
+    let mut pixels = pattern_buffer(
+        WIDTH,
+        HEIGHT,
+        WIDTH * 2,
+        HEIGHT * 2,
+        TextureFormat::Rgba8Unorm,
+    )?;
+    let layer = pixels
+        .add_layer("cursor", 1, 1, TextureFormat::Rgba8Unorm)
+        .unwrap();
+    layer.frame_view_mut().fill(&[0xff, 0x00, 0xff, 0xff]);
+    layer.set_offset(1, 1);
+
+    Some(pixels)
+}
+
+/// Destroy the device and wait for the loss to be reported.
+fn lose_device(pixels: &Pixels) {
+    pixels.device().destroy();
+    pixels.device().poll(wgpu::Maintain::Wait);
+}
+
+/// Count the device losses reported to the callback.
+fn count_losses(pixels: &Pixels) -> Arc<AtomicUsize> {
+    let losses = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&losses);
+    pixels.set_device_lost_callback(move |reason, _| {
+        assert!(matches!(reason, wgpu::DeviceLostReason::Destroyed));
+        counter.fetch_add(1, Ordering::Relaxed);
+    });
+
+    losses
+}
+
+#[test]
+fn recreate_device() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let losses = count_losses(&pixels);
+    pixels.set_crt(Some(CrtOptions::default()));
+    let expected: RgbaImage = pixels.read_rendered_frame().unwrap();
+    assert!(!pixels.is_device_lost());
+
+    lose_device(&pixels);
+    assert!(pixels.is_device_lost());
+    assert!(pixels.pixels_device().is_lost());
+    assert_eq!(losses.load(Ordering::Relaxed), 1);
+    assert!(matches!(pixels.render(), Err(pixels::Error::DeviceLost)));
+    assert!(matches!(
+        pixels.read_rendered_frame(),
+        Err(pixels::Error::DeviceLost)
+    ));
+
+    // The pixel buffer, layers, and CRT renderer are recreated on the new device
+    let old_device = Arc::clone(&pixels.context().device);
+    pixels.recreate_device().unwrap();
+    assert!(!pixels.is_device_lost());
+    assert!(!Arc::ptr_eq(&pixels.context().device, &old_device));
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), expected.pixels());
+
+    // Later changes still work, and the callback is kept
+    pixels.frame_mut().fill(0xff);
+    pixels.resize_buffer(2, 2).unwrap();
+    pixels.render().unwrap();
+    lose_device(&pixels);
+    assert_eq!(losses.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn dropped_device() {
+    let Some(pixels) = build() else {
+        return;
+    };
+    let losses = count_losses(&pixels);
+
+    // Dropping the last pixel buffer on a device is not a device loss
+    drop(pixels);
+    assert_eq!(losses.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn external_device() {
+    let Some(gpu) = skip_without_adapter(PixelsDevice::new()) else {
+        return;
+    };
+    let external = PixelsDevice::from_wgpu(
+        Arc::clone(gpu.instance()),
+        Arc::clone(gpu.adapter()),
+        Arc::clone(gpu.device()),
+        Arc::clone(gpu.queue()),
+    );
+    let mut pixels: Pixels = headless_builder(WIDTH, HEIGHT, WIDTH, HEIGHT)
+        .with_pixels_device(&external)
+        .build()
+        .unwrap();
+    let losses = count_losses(&pixels);
+
+    // Pixels does not replace the device lost callback of the application
+    lose_device(&pixels);
+    assert!(gpu.is_lost());
+    assert!(!pixels.is_device_lost());
+
+    // The application reports the loss instead
+    pixels.notify_device_lost(wgpu::DeviceLostReason::Dropped, String::new());
+    assert!(!pixels.is_device_lost());
+    external.notify_device_lost(wgpu::DeviceLostReason::Destroyed, String::new());
+    assert!(pixels.is_device_lost());
+    pixels.notify_device_lost(wgpu::DeviceLostReason::Destroyed, String::new());
+    assert_eq!(losses.load(Ordering::Relaxed), 1);
+
+    // Pixels does not replace the device of the application either
+    assert!(matches!(
+        pixels.recreate_device(),
+        Err(pixels::Error::ExternalDevice)
+    ));
+    assert!(matches!(
+        external.recreate(),
+        Err(pixels::Error::ExternalDevice)
+    ));
+
+    // The application moves the pixel buffer to its new device
+    let (device, queue) =
+        pollster::block_on(gpu.adapter().request_device(&Default::default(), None)).unwrap();
+    let replacement = PixelsDevice::from_wgpu(
+        Arc::clone(gpu.instance()),
+        Arc::clone(gpu.adapter()),
+        Arc::new(device),
+        Arc::new(queue),
+    );
+    pixels.recreate_device_with(&replacement).unwrap();
+    assert!(!pixels.is_device_lost());
+    pixels.render().unwrap();
+}
+
+#[test]
+fn recreate_device_failure() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    pixels
+        .add_layer("overlay", 16, 16, TextureFormat::Rgba8Unorm)
+        .unwrap();
+    let expected: RgbaImage = pixels.read_rendered_frame().unwrap();
+
+    // The new device supports the pixel buffer and the surface, but not the overlay
+    let limits = wgpu::Limits {
+        max_texture_dimension_2d: 8,
+        ..wgpu::Limits::downlevel_webgl2_defaults()
+    };
+    let descriptor = wgpu::DeviceDescriptor {
+        required_limits: limits,
+        ..Default::default()
+    };
+    let adapter = Arc::clone(&pixels.context().adapter);
+    let (device, queue) = pollster::block_on(adapter.request_device(&descriptor, None)).unwrap();
+    let small = PixelsDevice::from_wgpu(
+        Arc::clone(&pixels.context().instance),
+        adapter,
+        Arc::new(device),
+        Arc::new(queue),
+    );
+    assert!(matches!(
+        pixels.recreate_device_with(&small),
+        Err(pixels::Error::InvalidTexture(_))
+    ));
+
+    // The pixel buffer keeps its device
+    assert!(!Arc::ptr_eq(&pixels.context().device, small.device()));
+    assert_eq!(
+        pixels.read_rendered_frame().unwrap().pixels(),
+        expected.pixels()
+    );
+}
+
+#[test]
+fn recreate_shared_device() {
+    let Some(gpu) = skip_without_adapter(PixelsDevice::new()) else {
+        return;
+    };
+    let build = || -> Pixels {
+        let mut pixels = headless_builder(WIDTH, HEIGHT, WIDTH, HEIGHT)
+            .texture_format(TextureFormat::Rgba8Unorm)
+            .render_texture_format(TextureFormat::Rgba8Unorm)
+            .surface_texture_format(TextureFormat::Rgba8Unorm)
+            .with_pixels_device(&gpu)
+            .build()
+            .unwrap();
+        test_pattern(pixels.frame_mut(), WIDTH, HEIGHT);
+        pixels
+    };
+    let mut game = build();
+    let mut viewer = build();
+    let losses = count_losses(&game);
+    let expected = game.read_rendered_frame().unwrap();
+
+    lose_device(&game);
+    assert!(viewer.is_device_lost());
+    assert_eq!(losses.load(Ordering::Relaxed), 1);
+
+    // Both pixel buffers move to the same new device, and keep the callback
+    let new_gpu = gpu.recreate().unwrap();
+    assert!(!new_gpu.is_lost());
+    game.recreate_device_with(&new_gpu).unwrap();
+    viewer.recreate_device_with(&new_gpu).unwrap();
+    assert!(Arc::ptr_eq(&game.context().device, new_gpu.device()));
+    assert!(Arc::ptr_eq(&viewer.context().device, new_gpu.device()));
+    assert!(!viewer.is_device_lost());
+    assert_eq!(
+        game.read_rendered_frame().unwrap().pixels(),
+        expected.pixels()
+    );
+    assert_eq!(
+        viewer.read_rendered_frame().unwrap().pixels(),
+        expected.pixels()
+    );
+
+    lose_device(&viewer);
+    assert!(game.is_device_lost());
+    assert_eq!(losses.load(Ordering::Relaxed), 2);
+}
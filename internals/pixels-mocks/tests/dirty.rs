@@ -0,0 +1,136 @@
+use pixels::wgpu::TextureFormat;
+use pixels::Pixels;
+use pixels_mocks::golden::pattern_buffer;
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 6;
+
+/// Build an `8x6` pixel buffer with the test pattern, rendered at 1x, or `None` without a
+/// software adapter.
+fn build() -> Option<Pixels<'static>> {
+    pattern_buffer(WIDTH, HEIGHT, WIDTH, HEIGHT, TextureFormat::Rgba8UnormSrgb)
+}
+
+/// Fill the frame with opaque black.
+fn clear(frame: &mut [u8]) {
+    for pixel in frame.chunks_exact_mut(4) {
+        pixel.copy_from_slice(&[0, 0, 0, 0xff]);
+    }
+}
+
+/// Get the indices of pixels in the rendered frame that are white.
+fn white_pixels(pixels: &Pixels) -> Vec<usize> {
+    let image = pixels.read_rendered_frame().unwrap();
+
+    image
+        .pixels()
+        .chunks_exact(4)
+        .enumerate()
+        .filter(|(_, pixel)| pixel == &[0xff; 4])
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn region(x: u32, y: u32, width: u32, height: u32) -> Vec<usize> {
+    (y..y + height)
+        .flat_map(|y| (x..x + width).map(move |x| (y * WIDTH + x) as usize))
+        .collect()
+}
+
+#[test]
+fn only_marked_regions_are_uploaded() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    clear(pixels.frame_mut());
+    assert_eq!(white_pixels(&pixels), vec![]);
+
+    // Write the whole frame, but only mark a region
+    pixels.frame_mut_region(2, 1, 3, 2).fill(0xff);
+    assert_eq!(white_pixels(&pixels), region(2, 1, 3, 2));
+
+    // Nothing is uploaded without changes
+    assert_eq!(white_pixels(&pixels), region(2, 1, 3, 2));
+
+    // Separate regions, one of them clipped to the buffer
+    pixels.mark_dirty(0, 4, 1, 1);
+    pixels.mark_dirty(6, 4, 10, 10);
+    let mut expected = region(2, 1, 3, 2);
+    expected.extend(region(0, 4, 1, 1));
+    expected.extend(region(6, 4, 2, 2));
+    expected.sort_unstable();
+    assert_eq!(white_pixels(&pixels), expected);
+
+    // Everything is uploaded after `frame_mut()`
+    pixels.frame_mut();
+    assert_eq!(white_pixels(&pixels), region(0, 0, WIDTH, HEIGHT));
+}
+
+#[test]
+fn many_regions_are_merged() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    clear(pixels.frame_mut());
+    assert_eq!(white_pixels(&pixels), vec![]);
+
+    // Mark more separate pixels than are tracked individually
+    pixels.frame_mut_region(0, 0, 0, 0).fill(0xff);
+    for y in [0, 2, 4] {
+        for x in [0, 2, 4, 6] {
+            pixels.mark_dirty(x, y, 1, 1);
+        }
+    }
+
+    // Everything within the bounding box of the regions is uploaded
+    assert_eq!(white_pixels(&pixels), region(0, 0, 7, 5));
+}
+
+#[test]
+fn render_if_dirty() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+
+    // The first frame is always rendered
+    assert!(pixels.needs_redraw());
+    assert!(pixels.render_if_dirty().unwrap());
+    assert!(!pixels.needs_redraw());
+    assert!(!pixels.render_if_dirty().unwrap());
+
+    // Reading the frame does not count as a redraw
+    pixels.mark_dirty(1, 1, 1, 1);
+    pixels.read_rendered_frame().unwrap();
+    assert!(pixels.needs_redraw());
+    pixels.render().unwrap();
+    assert!(!pixels.needs_redraw());
+
+    let changes: [fn(&mut Pixels); 5] = [
+        |pixels| pixels.frame_mut()[0] = 0xff,
+        |pixels| pixels.set_scaling_mode(pixels::ScalingMode::Fill),
+        |pixels| pixels.clear_color(pixels::wgpu::Color::WHITE),
+        |pixels| pixels.resize_surface(16, 12).unwrap(),
+        |pixels| pixels.resize_buffer(4, 3).unwrap(),
+    ];
+    for change in changes {
+        change(&mut pixels);
+        assert!(pixels.render_if_dirty().unwrap());
+        assert!(!pixels.render_if_dirty().unwrap());
+    }
+}
+
+#[test]
+fn frame_view_region() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    clear(pixels.frame_mut());
+    assert_eq!(white_pixels(&pixels), vec![]);
+
+    // Only the view is marked, and it covers exactly the region
+    let mut view = pixels.frame_view_mut_region(5, 3, 2, 3);
+    view.fill(&[0xff; 4]);
+    assert_eq!(white_pixels(&pixels), region(5, 3, 2, 3));
+    assert_eq!(pixels.frame_view().pixel_rgba(5, 3), Some([0xff; 4]));
+    assert_eq!(pixels.frame_view().pixel_rgba(4, 3), Some([0, 0, 0, 0xff]));
+}
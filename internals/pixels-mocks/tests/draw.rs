@@ -0,0 +1,195 @@
+use pixels::wgpu::TextureFormat;
+use pixels::{draw, Frame, FrameMut};
+
+const WHITE: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+const RED: [u8; 4] = [0xff, 0x00, 0x00, 0xff];
+
+/// Draw into a blank `width` by `height` frame and render it as ASCII art.
+///
+/// Blank pixels are `.`, white pixels are `#`, and any other color is `?`.
+fn ascii<F>(width: u32, height: u32, draw: F) -> Vec<String>
+where
+    F: FnOnce(&mut FrameMut),
+{
+    let mut data = vec![0; (width * height * 4) as usize];
+    draw(&mut FrameMut::new(
+        &mut data,
+        width,
+        height,
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+
+    let frame = Frame::new(&data, width, height, TextureFormat::Rgba8UnormSrgb);
+    frame
+        .rows_rgba()
+        .map(|row| {
+            row.iter()
+                .map(|&pixel| match pixel {
+                    [0, 0, 0, 0] => '.',
+                    WHITE => '#',
+                    _ => '?',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn fill_rect_clipped() {
+    let art = ascii(6, 4, |frame| {
+        draw::fill_rect(frame, 4, -1, 10, 3, WHITE);
+        draw::fill_rect(frame, -100, 3, 101, 1, WHITE);
+        draw::fill_rect(frame, 6, 0, 1, 1, WHITE);
+    });
+
+    assert_eq!(art, ["....##", "....##", "......", "#....."]);
+}
+
+#[test]
+fn rect_and_lines() {
+    let art = ascii(7, 6, |frame| {
+        draw::rect(frame, 1, 1, 5, 4, WHITE);
+        draw::hline(frame, i32::MAX, i32::MIN, 5, WHITE);
+        draw::vline(frame, 0, 3, -3, WHITE);
+    });
+
+    assert_eq!(
+        art,
+        [
+            "#......", //
+            "######.", "##...#.", "##...#.", ".#####.", "#######",
+        ]
+    );
+}
+
+#[test]
+fn line_octants() {
+    let art = ascii(7, 5, |frame| {
+        draw::line(frame, 0, 0, 6, 2, WHITE);
+        draw::line(frame, 6, 4, 5, 0, WHITE);
+    });
+
+    assert_eq!(
+        art,
+        [
+            "##...#.", //
+            "..####.", ".....##", "......#", "......#",
+        ]
+    );
+}
+
+#[test]
+fn line_clipping_is_exact() {
+    // A line drawn in a large frame, and the same line clipped by a small frame
+    let (x0, y0, x1, y1) = (-15, 20, 25, -12);
+    let large = ascii(120, 120, |frame| {
+        draw::line(frame, x0 + 50, y0 + 50, x1 + 50, y1 + 50, WHITE)
+    });
+    let small = ascii(10, 8, |frame| draw::line(frame, x0, y0, x1, y1, WHITE));
+
+    let expected: Vec<String> = large[50..58]
+        .iter()
+        .map(|row| row[50..60].to_string())
+        .collect();
+    assert_eq!(small, expected);
+    assert!(small.iter().any(|row| row.contains('#')));
+
+    // Far away endpoints
+    let art = ascii(3, 3, |frame| {
+        draw::line(frame, i32::MIN, i32::MIN, i32::MAX, i32::MAX, WHITE)
+    });
+    assert_eq!(art, ["#..", ".#.", "..#"]);
+}
+
+#[test]
+fn circles() {
+    let outline = ascii(7, 7, |frame| draw::circle(frame, 3, 3, 3, WHITE));
+    assert_eq!(
+        outline,
+        [
+            "..###..", //
+            ".#...#.", "#.....#", "#.....#", "#.....#", ".#...#.", "..###..",
+        ]
+    );
+
+    let filled = ascii(7, 7, |frame| draw::fill_circle(frame, 3, 3, 3, WHITE));
+    assert_eq!(
+        filled,
+        [
+            "..###..", //
+            ".#####.", "#######", "#######", "#######", ".#####.", "..###..",
+        ]
+    );
+
+    let clipped = ascii(4, 3, |frame| draw::fill_circle(frame, 0, 0, 2, WHITE));
+    assert_eq!(clipped, ["###.", "###.", "##.."]);
+
+    let nothing = ascii(3, 3, |frame| draw::circle(frame, 1, 1, -1, WHITE));
+    assert_eq!(nothing, ["...", "...", "..."]);
+}
+
+#[test]
+fn flood_fill() {
+    let art = ascii(7, 5, |frame| {
+        draw::rect(frame, 0, 0, 7, 5, WHITE);
+        draw::vline(frame, 3, 0, 2, WHITE);
+        draw::flood_fill(frame, 1, 1, RED);
+    });
+
+    assert_eq!(
+        art,
+        [
+            "#######", //
+            "#??#??#", "#??#??#", "#?????#", "#######",
+        ]
+    );
+
+    // Filling outside of the frame, or with the same color, does nothing
+    let art = ascii(2, 1, |frame| {
+        draw::flood_fill(frame, -1, 0, WHITE);
+        draw::flood_fill(frame, 0, 0, [0; 4]);
+    });
+    assert_eq!(art, [".."]);
+}
+
+#[test]
+fn blit_blends_and_converts() {
+    // A 2x2 RGBA sprite with opaque, transparent, and half transparent pixels
+    let sprite_data = [
+        [0xff, 0x00, 0x00, 0xff],
+        [0x00, 0xff, 0x00, 0x00],
+        [0x00, 0x00, 0xff, 0x80],
+        [0xff, 0xff, 0xff, 0xff],
+    ]
+    .concat();
+    let sprite = Frame::new(&sprite_data, 2, 2, TextureFormat::Rgba8UnormSrgb);
+
+    let mut data = [0x10, 0x20, 0x30, 0xff].repeat(3 * 2);
+    let mut frame = FrameMut::new(&mut data, 3, 2, TextureFormat::Bgra8UnormSrgb);
+    draw::blit(&mut frame, 1, -1, &sprite);
+    draw::blit(&mut frame, -1, 1, &sprite);
+
+    let pixels: Vec<[u8; 4]> = frame.as_frame().rows_rgba().flatten().copied().collect();
+    assert_eq!(
+        pixels,
+        [
+            // Untouched, blue blended over the background, and white (all in BGRA order)
+            [0x10, 0x20, 0x30, 0xff],
+            [0x88, 0x10, 0x18, 0xff],
+            [0xff, 0xff, 0xff, 0xff],
+            // Transparent, untouched, untouched
+            [0x10, 0x20, 0x30, 0xff],
+            [0x10, 0x20, 0x30, 0xff],
+            [0x10, 0x20, 0x30, 0xff],
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "not supported for drawing")]
+fn unsupported_format() {
+    let mut data = vec![0; 4];
+    let mut frame = FrameMut::new(&mut data, 1, 1, TextureFormat::Rgb10a2Unorm);
+
+    draw::clear(&mut frame, WHITE);
+}
@@ -0,0 +1,148 @@
+use pixels::wgpu::TextureFormat;
+use pixels::{Pixels, RgbaImage, ScalingFilter};
+use pixels_mocks::golden::pattern_buffer;
+
+const FILTERS: [ScalingFilter; 8] = [
+    ScalingFilter::Nearest,
+    ScalingFilter::SharpBilinear,
+    ScalingFilter::Scale2x,
+    ScalingFilter::Scale3x,
+    ScalingFilter::Xbr,
+    ScalingFilter::Hqx,
+    ScalingFilter::Bicubic,
+    ScalingFilter::Lanczos,
+];
+
+const BLACK: [u8; 4] = [0x00, 0x00, 0x00, 0xff];
+const WHITE: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
+/// Build a `3x3` pixel buffer scaled by `scale`, or `None` without a software adapter.
+///
+/// The buffer is white with a black staircase in the top left corner.
+fn build(scale: u32) -> Option<Pixels<'static>> {
+    let mut pixels = pattern_buffer(3, 3, 3 * scale, 3 * scale, TextureFormat::Rgba8UnormSrgb)?;
+    for (i, pixel) in pixels.frame_mut().chunks_exact_mut(4).enumerate() {
+        let color = if matches!(i, 0 | 1 | 3) { BLACK } else { WHITE };
+        pixel.copy_from_slice(&color);
+    }
+
+    Some(pixels)
+}
+
+/// Get a single pixel from an image.
+fn pixel(image: &RgbaImage, x: u32, y: u32) -> [u8; 4] {
+    let i = ((y * image.width() + x) * 4) as usize;
+    image.pixels()[i..i + 4].try_into().unwrap()
+}
+
+/// Render with the given filter.
+fn render(pixels: &mut Pixels, filter: ScalingFilter) -> RgbaImage {
+    pixels.set_scaling_filter(filter);
+    assert_eq!(pixels.scaling_filter(), filter);
+
+    pixels.read_rendered_frame().unwrap()
+}
+
+#[test]
+fn scale2x() {
+    let Some(mut pixels) = build(2) else {
+        return;
+    };
+
+    // The top left quadrant of the center pixel fills in the staircase
+    let image = render(&mut pixels, ScalingFilter::Nearest);
+    assert_eq!(pixel(&image, 2, 2), WHITE);
+    let image = render(&mut pixels, ScalingFilter::Scale2x);
+    assert_eq!(pixel(&image, 2, 2), BLACK);
+    assert_eq!(pixel(&image, 3, 2), WHITE);
+    assert_eq!(pixel(&image, 2, 3), WHITE);
+    assert_eq!(pixel(&image, 3, 3), WHITE);
+}
+
+#[test]
+fn scale3x() {
+    let Some(mut pixels) = build(3) else {
+        return;
+    };
+
+    let image = render(&mut pixels, ScalingFilter::Scale3x);
+    assert_eq!(pixel(&image, 3, 3), BLACK);
+    assert_eq!(pixel(&image, 4, 3), WHITE);
+    assert_eq!(pixel(&image, 3, 4), WHITE);
+    assert_eq!(pixel(&image, 4, 4), WHITE);
+
+    // The filter is kept when the buffer is resized
+    pixels.resize_buffer(6, 6).unwrap();
+    assert_eq!(pixels.scaling_filter(), ScalingFilter::Scale3x);
+}
+
+#[test]
+fn sharp_bilinear() {
+    let Some(mut pixels) = build(3) else {
+        return;
+    };
+
+    // Identical to nearest at integer scales, away from the pixel edges
+    let expected = render(&mut pixels, ScalingFilter::Nearest);
+    let image = render(&mut pixels, ScalingFilter::SharpBilinear);
+    for y in [1, 4, 7] {
+        for x in [1, 4, 7] {
+            assert_eq!(pixel(&image, x, y), pixel(&expected, x, y), "({x}, {y})");
+        }
+    }
+
+    // Smooth at fractional scales, but only at the pixel edges
+    pixels.resize_surface(8, 8).unwrap();
+    pixels.set_scaling_mode(pixels::ScalingMode::Fill);
+    let image = render(&mut pixels, ScalingFilter::SharpBilinear);
+    assert_eq!(pixel(&image, 0, 0), BLACK);
+    assert_eq!(pixel(&image, 7, 7), WHITE);
+}
+
+#[test]
+fn uniform_colors() {
+    let Some(mut pixels) = build(4) else {
+        return;
+    };
+    pixels.frame_mut().copy_from_slice(&[0x40; 3 * 3 * 4]);
+    let expected = render(&mut pixels, ScalingFilter::Nearest);
+
+    // Every filter leaves an image of a single color unchanged
+    for filter in FILTERS {
+        let image = render(&mut pixels, filter);
+        let similar = image
+            .pixels()
+            .iter()
+            .zip(expected.pixels())
+            .all(|(a, b)| a.abs_diff(*b) <= 1);
+        assert!(similar, "{filter:?}");
+    }
+}
+
+#[test]
+fn smooth_edges() {
+    let Some(mut pixels) = build(4) else {
+        return;
+    };
+
+    // The interpolating filters blend colors across the staircase, while the pixel art filters
+    // keep solid black and white pixels
+    let filters = FILTERS
+        .into_iter()
+        .filter(|&filter| filter != ScalingFilter::SharpBilinear);
+    for filter in filters {
+        let image = render(&mut pixels, filter);
+        let blended = image
+            .pixels()
+            .chunks_exact(4)
+            .any(|p| p != BLACK && p != WHITE);
+        let expected = matches!(
+            filter,
+            ScalingFilter::Xbr
+                | ScalingFilter::Hqx
+                | ScalingFilter::Bicubic
+                | ScalingFilter::Lanczos
+        );
+        assert_eq!(blended, expected, "{filter:?}");
+    }
+}
@@ -0,0 +1,138 @@
+use pixels::wgpu::TextureFormat;
+use pixels::{Frame, FrameMut};
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 6;
+
+fn frame_data() -> Vec<u8> {
+    vec![0; (WIDTH * HEIGHT * 4) as usize]
+}
+
+#[test]
+fn pixel_access() {
+    let mut data = frame_data();
+    let mut frame = FrameMut::new(&mut data, WIDTH, HEIGHT, TextureFormat::Rgba8UnormSrgb);
+    assert_eq!((frame.width(), frame.height()), (WIDTH, HEIGHT));
+    assert_eq!((frame.stride(), frame.bytes_per_pixel()), (32, 4));
+
+    frame.set_pixel_rgba(2, 1, [1, 2, 3, 4]);
+    frame.set_pixel_u32(7, 5, 0x0403_0201);
+    assert_eq!(frame.pixel(2, 1), Some(&[1, 2, 3, 4][..]));
+    assert_eq!(frame.pixel_rgba(7, 5), Some([1, 2, 3, 4]));
+    assert_eq!(frame.pixel_u32(2, 1), Some(0x0403_0201));
+    assert_eq!(frame.pixel(8, 0), None);
+    assert_eq!(frame.pixel(0, 6), None);
+
+    assert_eq!(&data[(8 + 2) * 4..(8 + 3) * 4], &[1, 2, 3, 4]);
+    assert_eq!(&data[data.len() - 4..], &[1, 2, 3, 4]);
+}
+
+#[test]
+fn indexed_pixels() {
+    let mut data = vec![0; (WIDTH * HEIGHT) as usize];
+    let mut frame = FrameMut::new(&mut data, WIDTH, HEIGHT, TextureFormat::R8Uint);
+    assert_eq!(frame.bytes_per_pixel(), 1);
+
+    frame.set_pixel(3, 2, &[42]);
+    assert_eq!(data[2 * 8 + 3], 42);
+}
+
+#[test]
+#[should_panic(expected = "not a 4 byte format")]
+fn typed_access_requires_4_byte_format() {
+    let data = vec![0; (WIDTH * HEIGHT) as usize];
+    let frame = Frame::new(&data, WIDTH, HEIGHT, TextureFormat::R8Uint);
+
+    frame.pixel_rgba(0, 0);
+}
+
+#[test]
+fn sub_rectangle_views() {
+    let mut data = frame_data();
+    let mut frame = FrameMut::new(&mut data, WIDTH, HEIGHT, TextureFormat::Rgba8UnormSrgb);
+
+    let mut view = frame.view_mut(2, 1, 3, 4);
+    assert_eq!((view.width(), view.height(), view.stride()), (3, 4, 32));
+    view.fill(&[0xff; 4]);
+    view.set_pixel_rgba(0, 0, [1, 2, 3, 4]);
+    assert_eq!(view.pixel(3, 0), None);
+
+    let frame = frame.as_frame();
+    assert_eq!(frame.pixel_rgba(2, 1), Some([1, 2, 3, 4]));
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let inside = (2..5).contains(&x) && (1..5).contains(&y);
+            let expected = match (x, y) {
+                (2, 1) => [1, 2, 3, 4],
+                _ if inside => [0xff; 4],
+                _ => [0; 4],
+            };
+            assert_eq!(frame.pixel_rgba(x, y), Some(expected), "Pixel {:?}", (x, y));
+        }
+    }
+
+    // Views of views, and the rows of a view
+    let view = frame.view(1, 1, 4, 4).view(1, 0, 2, 2);
+    let rows: Vec<_> = view.rows_rgba().collect();
+    assert_eq!(rows, [[[1, 2, 3, 4], [0xff; 4]], [[0xff; 4], [0xff; 4]]]);
+
+    // Empty views
+    let empty = frame.view(WIDTH, HEIGHT, 0, 0);
+    assert_eq!(empty.rows().count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "outside of the 8x6 frame")]
+fn sub_rectangle_out_of_bounds() {
+    let data = frame_data();
+    let frame = Frame::new(&data, WIDTH, HEIGHT, TextureFormat::Rgba8UnormSrgb);
+
+    frame.view(6, 0, 3, 1);
+}
+
+#[test]
+fn split_into_bands() {
+    let mut data = frame_data();
+    let frame = FrameMut::new(&mut data, WIDTH, HEIGHT, TextureFormat::Rgba8UnormSrgb);
+
+    // Split a view narrower than the frame, so the bands have padding between rows
+    let view = frame.into_view_mut(1, 0, 6, HEIGHT);
+    let bands: Vec<_> = view.into_row_bands(4).collect();
+    assert_eq!(
+        bands.iter().map(FrameMut::height).collect::<Vec<_>>(),
+        [4, 2]
+    );
+
+    std::thread::scope(|scope| {
+        for (i, mut band) in bands.into_iter().enumerate() {
+            scope.spawn(move || {
+                for row in band.rows_rgba_mut() {
+                    row.fill([i as u8 + 1; 4]);
+                }
+            });
+        }
+    });
+
+    let frame = Frame::new(&data, WIDTH, HEIGHT, TextureFormat::Rgba8UnormSrgb);
+    for (y, row) in frame.rows_rgba().enumerate() {
+        let band = if y < 4 { 1 } else { 2 };
+        let mut expected = [[band; 4]; 8];
+        expected[0] = [0; 4];
+        expected[7] = [0; 4];
+        assert_eq!(row, expected, "Row {y}");
+    }
+}
+
+#[test]
+fn split_at_row() {
+    let mut data = frame_data();
+    let frame = FrameMut::new(&mut data, WIDTH, HEIGHT, TextureFormat::Rgba8UnormSrgb);
+
+    let (top, bottom) = frame.split_at_row_mut(HEIGHT);
+    assert_eq!((top.height(), bottom.height()), (HEIGHT, 0));
+
+    let (top, mut bottom) = top.split_at_row_mut(0);
+    assert_eq!((top.height(), bottom.height()), (0, HEIGHT));
+    bottom.set_pixel_rgba(0, 5, [9; 4]);
+    assert_eq!(&data[(5 * 8) * 4..(5 * 8 + 1) * 4], &[9; 4]);
+}
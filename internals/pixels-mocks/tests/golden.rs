@@ -0,0 +1,193 @@
+use pixels::wgpu::TextureFormat;
+use pixels::{Anchor, Pixels, RgbaImage, ScalingMode};
+use pixels_mocks::golden::{assert_golden, pattern_buffer};
+use std::path::PathBuf;
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 6;
+const TOLERANCE: u8 = 2;
+
+/// Render the test pattern after applying `configure`, or `None` without a software adapter.
+fn render<F>(surface_width: u32, surface_height: u32, configure: F) -> Option<RgbaImage>
+where
+    F: FnOnce(&mut Pixels),
+{
+    let mut pixels = pattern_buffer(
+        WIDTH,
+        HEIGHT,
+        surface_width,
+        surface_height,
+        TextureFormat::Rgba8UnormSrgb,
+    )?;
+    configure(&mut pixels);
+
+    Some(pixels.read_rendered_frame().unwrap())
+}
+
+fn scaling_mode(scaling_mode: ScalingMode) -> impl FnOnce(&mut Pixels) {
+    move |pixels| pixels.set_scaling_mode(scaling_mode)
+}
+
+fn reference(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect()
+}
+
+#[test]
+fn pixel_perfect_integer_scale() {
+    if let Some(image) = render(32, 24, scaling_mode(ScalingMode::PixelPerfect)) {
+        assert_golden(&image, reference("pixel_perfect_integer.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn pixel_perfect_border() {
+    if let Some(image) = render(30, 28, scaling_mode(ScalingMode::PixelPerfect)) {
+        assert_golden(&image, reference("pixel_perfect_border.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn pixel_perfect_smaller_surface() {
+    if let Some(image) = render(6, 4, scaling_mode(ScalingMode::PixelPerfect)) {
+        assert_golden(&image, reference("pixel_perfect_smaller.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn fill() {
+    if let Some(image) = render(30, 28, scaling_mode(ScalingMode::Fill)) {
+        assert_golden(&image, reference("fill.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn fill_smaller_surface() {
+    if let Some(image) = render(6, 4, scaling_mode(ScalingMode::Fill)) {
+        assert_golden(&image, reference("fill_smaller.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn pixel_aspect_ratio_pixel_perfect() {
+    let image = render(40, 28, |pixels| pixels.set_pixel_aspect_ratio(8.0 / 7.0));
+    if let Some(image) = image {
+        assert_golden(&image, reference("par_pixel_perfect.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn pixel_aspect_ratio_fill() {
+    let image = render(40, 28, |pixels| {
+        pixels.set_scaling_mode(ScalingMode::Fill);
+        pixels.set_pixel_aspect_ratio(5.0 / 6.0);
+    });
+    if let Some(image) = image {
+        assert_golden(&image, reference("par_fill.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn stretch() {
+    if let Some(image) = render(30, 28, scaling_mode(ScalingMode::Stretch)) {
+        assert_golden(&image, reference("stretch.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn fit_integer_or_down_larger_surface() {
+    // Identical to `PixelPerfect` when the buffer fits
+    if let Some(image) = render(30, 28, scaling_mode(ScalingMode::FitIntegerOrDown)) {
+        assert_golden(&image, reference("pixel_perfect_border.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn fit_integer_or_down_smaller_surface() {
+    // Identical to `Fill` when the buffer does not fit
+    if let Some(image) = render(6, 4, scaling_mode(ScalingMode::FitIntegerOrDown)) {
+        assert_golden(&image, reference("fill_smaller.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn fixed() {
+    if let Some(image) = render(30, 28, scaling_mode(ScalingMode::Fixed(2))) {
+        assert_golden(&image, reference("fixed.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn fixed_cropped() {
+    if let Some(image) = render(30, 28, scaling_mode(ScalingMode::Fixed(5))) {
+        assert_golden(&image, reference("fixed_cropped.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn cover() {
+    if let Some(image) = render(30, 28, scaling_mode(ScalingMode::Cover)) {
+        assert_golden(&image, reference("cover.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn anchor_top_left() {
+    if let Some(image) = render(30, 28, |pixels| pixels.set_anchor(Anchor::TopLeft)) {
+        assert_golden(&image, reference("anchor_top_left.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn anchor_bottom_right_offset() {
+    let image = render(30, 28, |pixels| {
+        pixels.set_anchor(Anchor::BottomRight);
+        pixels.set_anchor_offset(-2, -1);
+    });
+    if let Some(image) = image {
+        assert_golden(
+            &image,
+            reference("anchor_bottom_right_offset.png"),
+            TOLERANCE,
+        );
+    }
+}
+
+#[test]
+fn anchor_cropped() {
+    let image = render(30, 28, |pixels| {
+        pixels.set_scaling_mode(ScalingMode::Fixed(5));
+        pixels.set_anchor(Anchor::Right);
+    });
+    if let Some(image) = image {
+        assert_golden(&image, reference("anchor_cropped.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn viewport() {
+    let image = render(40, 30, |pixels| {
+        pixels.clear_color(pixels::wgpu::Color {
+            r: 1.0,
+            g: 0.0,
+            b: 1.0,
+            a: 1.0,
+        });
+        pixels.set_viewport(4, 2, 20, 16);
+    });
+    if let Some(image) = image {
+        assert_golden(&image, reference("viewport.png"), TOLERANCE);
+    }
+}
+
+#[test]
+fn viewport_clipped_by_resize() {
+    let image = render(40, 30, |pixels| {
+        pixels.set_viewport(16, 6, 32, 24);
+        pixels.resize_surface(32, 24).unwrap();
+    });
+    if let Some(image) = image {
+        assert_golden(&image, reference("viewport_clipped.png"), TOLERANCE);
+    }
+}
@@ -0,0 +1,173 @@
+use pixels::shader::{ShaderError, ShaderWatcher};
+use pixels::wgpu;
+use pixels::{PassContext, Pixels, RenderPass, RgbaImage};
+use pixels_mocks::golden::pattern_buffer;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A scaling shader that fills the scaled buffer with a solid color.
+fn solid_shader(color: &str) -> String {
+    format!(
+        "
+struct Locals {{
+    transform: mat4x4<f32>,
+    input_size: vec4<f32>,
+}}
+@group(0) @binding(2) var<uniform> r_locals: Locals;
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>) -> @builtin(position) vec4<f32> {{
+    return r_locals.transform * vec4<f32>(position, 0.0, 1.0);
+}}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {{
+    return vec4<f32>({color}, 1.0);
+}}
+"
+    )
+}
+
+/// A path in the temporary directory that is unique to the test.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pixels-{}-{name}.wgsl", std::process::id()))
+}
+
+/// Write a file, making sure that its modification time changes.
+fn write(path: &Path, contents: &str) {
+    let before = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    loop {
+        std::fs::write(path, contents).unwrap();
+        let after = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if after != before {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+/// Build a `2x2` pixel buffer with the test pattern, or `None` without a software adapter.
+fn build() -> Option<Pixels<'static>> {
+    pattern_buffer(2, 2, 2, 2, wgpu::TextureFormat::Rgba8UnormSrgb)
+}
+
+/// The color of the first pixel.
+fn first_pixel(image: &RgbaImage) -> &[u8] {
+    &image.pixels()[..4]
+}
+
+/// Counts how often its shaders were reloaded.
+struct Counter(Arc<AtomicUsize>);
+
+impl RenderPass for Counter {
+    fn render(&self, _: &mut wgpu::CommandEncoder, _: &wgpu::TextureView, _: &PassContext<'_>) {}
+
+    fn reload_shaders(&mut self, _device: &wgpu::Device) -> Result<bool, ShaderError> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Ok(false)
+    }
+}
+
+#[test]
+fn watcher() {
+    let Some(pixels) = build() else {
+        return;
+    };
+    let device = pixels.device();
+    let path = temp_path("watcher");
+    write(&path, &solid_shader("1.0, 0.0, 0.0"));
+
+    let mut watcher = ShaderWatcher::new(device, &path).unwrap();
+    assert_eq!(watcher.path(), path);
+    assert!(!watcher.poll(device).unwrap());
+
+    // Compile errors carry the diagnostic, and are only reported once
+    write(
+        &path,
+        &solid_shader("1.0, 0.0, 0.0").replace("return", "retrun"),
+    );
+    let err = watcher.poll(device).unwrap_err();
+    assert!(matches!(err, ShaderError::Compile(..)), "{err}");
+    assert!(err.to_string().contains("retrun"), "{err}");
+    assert!(!watcher.poll(device).unwrap());
+
+    // Pipeline validation errors are caught as well
+    let err = watcher
+        .build(device, |module| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: None,
+                layout: None,
+                vertex: wgpu::VertexState {
+                    module,
+                    entry_point: "missing",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: None,
+                multiview: None,
+            })
+        })
+        .unwrap_err();
+    assert!(matches!(err, ShaderError::Compile(..)), "{err}");
+
+    write(&path, &solid_shader("0.0, 1.0, 0.0"));
+    assert!(watcher.poll(device).unwrap());
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(watcher.poll(device), Err(ShaderError::Io(..))));
+    assert!(ShaderWatcher::new(device, &path).is_err());
+}
+
+#[test]
+fn scaling_shader() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let path = temp_path("scaling");
+    let expected = pixels.read_rendered_frame().unwrap();
+
+    // Missing files are reported without changing the scaling shader
+    assert!(matches!(
+        pixels.set_scaling_shader(&path),
+        Err(ShaderError::Io(..))
+    ));
+
+    write(&path, &solid_shader("1.0, 0.0, 0.0"));
+    pixels.set_scaling_shader(&path).unwrap();
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(first_pixel(&image), [0xff, 0x00, 0x00, 0xff]);
+    assert!(!pixels.reload_shaders().unwrap());
+
+    write(&path, &solid_shader("0.0, 1.0, 0.0"));
+    assert!(pixels.reload_shaders().unwrap());
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(first_pixel(&image), [0x00, 0xff, 0x00, 0xff]);
+
+    // The previous pipeline is kept on errors
+    write(&path, "fn fs_main(");
+    assert!(pixels.reload_shaders().is_err());
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(first_pixel(&image), [0x00, 0xff, 0x00, 0xff]);
+
+    pixels.reset_scaling_shader();
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), expected.pixels());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn passes() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let reloads = Arc::new(AtomicUsize::new(0));
+    pixels.add_pass(Box::new(Counter(Arc::clone(&reloads))));
+    pixels.add_pass(Box::new(Counter(Arc::clone(&reloads))));
+
+    assert!(!pixels.reload_shaders().unwrap());
+    assert_eq!(reloads.load(Ordering::Relaxed), 2);
+}
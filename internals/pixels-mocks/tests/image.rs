@@ -0,0 +1,327 @@
+use pixels::image::{self, Fit, ImageError, ImageFormat};
+use pixels::wgpu::TextureFormat;
+use pixels::{FrameMut, Pixels, RgbaImage};
+use pixels_mocks::golden::{headless_builder, try_build};
+
+const RED: [u8; 4] = [0xff, 0x00, 0x00, 0xff];
+const GREEN: [u8; 4] = [0x00, 0xff, 0x00, 0xff];
+const BLUE: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+const WHITE: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+const BLACK: [u8; 4] = [0x00, 0x00, 0x00, 0xff];
+
+/// Split image pixels into rows of `RGBA` colors.
+fn rows(image: &RgbaImage) -> Vec<Vec<[u8; 4]>> {
+    image
+        .pixels()
+        .chunks_exact(image.width() as usize * 4)
+        .map(|row| {
+            row.chunks_exact(4)
+                .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+                .collect()
+        })
+        .collect()
+}
+
+/// Encode a PNG image in memory.
+fn encode_png(width: u32, height: u32, color: png::ColorType, data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(color);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(data)
+        .unwrap();
+
+    bytes
+}
+
+/// Build a BMP file from a header after the `BITMAPINFOHEADER` size field, extra data like masks
+/// or a palette, and the pixel rows.
+fn encode_bmp(info: &[u8], extra: &[u8], data: &[u8]) -> Vec<u8> {
+    let data_offset = 14 + 4 + info.len() + extra.len();
+    let size = data_offset + data.len();
+
+    let mut bytes = b"BM".to_vec();
+    bytes.extend((size as u32).to_le_bytes());
+    bytes.extend([0; 4]);
+    bytes.extend((data_offset as u32).to_le_bytes());
+    bytes.extend((4 + info.len() as u32).to_le_bytes());
+    bytes.extend(info);
+    bytes.extend(extra);
+    bytes.extend(data);
+
+    bytes
+}
+
+/// The fields of a 40 byte `BITMAPINFOHEADER`, after the size field.
+fn bmp_info(width: i32, height: i32, bits: u16, compression: u32, colors: u32) -> Vec<u8> {
+    let mut info = Vec::new();
+    info.extend(width.to_le_bytes());
+    info.extend(height.to_le_bytes());
+    info.extend(1_u16.to_le_bytes());
+    info.extend(bits.to_le_bytes());
+    info.extend(compression.to_le_bytes());
+    info.extend([0; 12]);
+    info.extend(colors.to_le_bytes());
+    info.extend([0; 4]);
+
+    info
+}
+
+#[test]
+fn detect_format() {
+    assert_eq!(ImageFormat::detect(b"\x89PNG\r\n"), Some(ImageFormat::Png));
+    assert_eq!(ImageFormat::detect(b"BM\0\0"), Some(ImageFormat::Bmp));
+    assert_eq!(ImageFormat::detect(b"qoif"), Some(ImageFormat::Qoi));
+    assert_eq!(
+        ImageFormat::detect(&[0x0a, 5, 1, 8]),
+        Some(ImageFormat::Pcx)
+    );
+    assert_eq!(ImageFormat::detect(b"GIF89a"), None);
+
+    assert!(matches!(
+        image::decode(b"GIF89a"),
+        Err(ImageError::UnknownFormat)
+    ));
+    assert!(matches!(
+        image::open("does-not-exist.png"),
+        Err(ImageError::Io(_))
+    ));
+}
+
+#[test]
+fn png() {
+    let data = [RED, GREEN, BLUE, [1, 2, 3, 4]].concat();
+    let image = image::decode(&encode_png(2, 2, png::ColorType::Rgba, &data)).unwrap();
+    assert_eq!(rows(&image), [[RED, GREEN], [BLUE, [1, 2, 3, 4]]]);
+
+    let image =
+        image::decode(&encode_png(3, 1, png::ColorType::Grayscale, &[0, 128, 255])).unwrap();
+    assert_eq!(rows(&image), [[BLACK, [128, 128, 128, 255], WHITE]]);
+
+    let bytes = encode_png(2, 2, png::ColorType::Rgba, &data);
+    assert!(matches!(
+        image::decode(&bytes[..bytes.len() / 2]),
+        Err(ImageError::Png(_))
+    ));
+}
+
+#[test]
+fn bmp_24_bit() {
+    // Bottom-up rows of BGR pixels, padded to 4 bytes
+    let data = [
+        [0xff, 0x00, 0x00, 0xff, 0xff, 0xff, 0, 0],
+        [0x00, 0x00, 0xff, 0x00, 0xff, 0x00, 0, 0],
+    ]
+    .concat();
+    let bytes = encode_bmp(&bmp_info(2, 2, 24, 0, 0), &[], &data);
+    let image = image::decode(&bytes).unwrap();
+    assert_eq!(rows(&image), [[RED, GREEN], [BLUE, WHITE]]);
+
+    // Top-down rows
+    let bytes = encode_bmp(&bmp_info(2, -2, 24, 0, 0), &[], &data);
+    let image = image::decode(&bytes).unwrap();
+    assert_eq!(rows(&image), [[BLUE, WHITE], [RED, GREEN]]);
+
+    assert!(matches!(
+        image::decode(&bytes[..bytes.len() - 1]),
+        Err(ImageError::Bmp(_))
+    ));
+}
+
+#[test]
+fn bmp_paletted() {
+    // Two palette entries in BGRx order, and one byte per row padded to 4 bytes
+    let palette = [[0xff, 0x00, 0x00, 0x00], [0x00, 0xff, 0x00, 0x00]].concat();
+    let data = [[0b1010_0000, 0, 0, 0], [0b0110_0000, 0, 0, 0]].concat();
+    let bytes = encode_bmp(&bmp_info(4, 2, 1, 0, 2), &palette, &data);
+    let image = image::decode(&bytes).unwrap();
+    assert_eq!(
+        rows(&image),
+        [[BLUE, GREEN, GREEN, BLUE], [GREEN, BLUE, GREEN, BLUE]]
+    );
+
+    // Indices beyond the palette are an error
+    let data = [[0x00, 0x20, 0, 0], [0x00, 0x00, 0, 0]].concat();
+    let bytes = encode_bmp(&bmp_info(4, 2, 4, 0, 2), &palette, &data);
+    assert!(matches!(image::decode(&bytes), Err(ImageError::Bmp(_))));
+}
+
+#[test]
+fn bmp_bit_fields() {
+    // 16-bit 5-6-5 pixels
+    let masks: Vec<u8> = [0xf800_u32, 0x07e0, 0x001f]
+        .iter()
+        .flat_map(|mask| mask.to_le_bytes())
+        .collect();
+    let data = [0x00, 0xf8, 0xe0, 0x07, 0x1f, 0x00, 0x00, 0x00];
+    let bytes = encode_bmp(&bmp_info(3, 1, 16, 3, 0), &masks, &data);
+    let image = image::decode(&bytes).unwrap();
+    assert_eq!(rows(&image), [[RED, GREEN, BLUE]]);
+
+    // 32-bit pixels with an alpha mask
+    let masks: Vec<u8> = [0x0000_00ff_u32, 0x0000_ff00, 0x00ff_0000, 0xff00_0000]
+        .iter()
+        .flat_map(|mask| mask.to_le_bytes())
+        .collect();
+    let data = [1, 2, 3, 4];
+    let bytes = encode_bmp(&bmp_info(1, 1, 32, 6, 0), &masks, &data);
+    let image = image::decode(&bytes).unwrap();
+    assert_eq!(image.pixels(), [1, 2, 3, 4]);
+
+    // 32-bit pixels without bit fields ignore the unused byte
+    let bytes = encode_bmp(&bmp_info(1, 1, 32, 0, 0), &[], &data);
+    let image = image::decode(&bytes).unwrap();
+    assert_eq!(image.pixels(), [3, 2, 1, 255]);
+
+    // Run-length encoding is not supported
+    let bytes = encode_bmp(&bmp_info(1, 1, 8, 1, 0), &[], &data);
+    assert!(matches!(image::decode(&bytes), Err(ImageError::Bmp(_))));
+}
+
+#[test]
+fn pcx() {
+    let mut bytes = Vec::new();
+    let mut writer = pcx::WriterPaletted::new(&mut bytes, (3, 1), (72, 72)).unwrap();
+    writer.write_row(&[2, 0, 1]).unwrap();
+    writer
+        .write_palette(&[0xff, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff])
+        .unwrap();
+    let image = image::decode(&bytes).unwrap();
+    assert_eq!(rows(&image), [[BLUE, RED, GREEN]]);
+
+    let mut bytes = Vec::new();
+    let mut writer = pcx::WriterRgb::new(&mut bytes, (2, 2), (72, 72)).unwrap();
+    writer
+        .write_row(&[0xff, 0x00, 0x00, 0x00, 0xff, 0x00])
+        .unwrap();
+    writer
+        .write_row(&[0x00, 0x00, 0xff, 0xff, 0xff, 0xff])
+        .unwrap();
+    writer.finish().unwrap();
+    let image = image::decode(&bytes).unwrap();
+    assert_eq!(rows(&image), [[RED, GREEN], [BLUE, WHITE]]);
+
+    assert!(matches!(
+        image::decode(&bytes[..100]),
+        Err(ImageError::Pcx(_))
+    ));
+
+    // Dimensions are checked before allocating
+    bytes[8..12].copy_from_slice(&[0xfe, 0xff, 0xfe, 0xff]);
+    bytes[66..68].copy_from_slice(&[0xff, 0xff]);
+    assert!(matches!(
+        image::decode(&bytes),
+        Err(ImageError::TooLarge(0xffff, 0xffff))
+    ));
+}
+
+#[test]
+fn qoi() {
+    let mut bytes = b"qoif".to_vec();
+    bytes.extend(3_u32.to_be_bytes());
+    bytes.extend(2_u32.to_be_bytes());
+    bytes.extend([4, 0]);
+    bytes.extend([
+        0xff, 10, 20, 30, 128,  // RGBA
+        0xc0, // Run of 1
+        0x76, // Diff: r + 1, g - 1
+        0xa5, 0x96, // Luma: g + 5, r + 6, b + 3
+        0x14, // Index of the first pixel
+        0xfe, 1, 2, 3, // RGB
+    ]);
+    bytes.extend([0, 0, 0, 0, 0, 0, 0, 1]);
+
+    let image = image::decode(&bytes).unwrap();
+    assert_eq!(
+        rows(&image),
+        [
+            [[10, 20, 30, 128], [10, 20, 30, 128], [11, 19, 30, 128]],
+            [[17, 24, 33, 128], [10, 20, 30, 128], [1, 2, 3, 128]],
+        ]
+    );
+
+    assert!(matches!(
+        image::decode(&bytes[..20]),
+        Err(ImageError::Qoi(_))
+    ));
+
+    // Dimensions are checked before allocating
+    bytes[4..12].copy_from_slice(&[0xff; 8]);
+    assert!(matches!(
+        image::decode(&bytes),
+        Err(ImageError::TooLarge(u32::MAX, u32::MAX))
+    ));
+}
+
+#[test]
+fn copy_to_frame() {
+    let image = RgbaImage::new(2, 1, [RED, GREEN].concat());
+    let mut data = vec![0; 4 * 4 * 4];
+
+    let mut frame = FrameMut::new(&mut data, 4, 4, TextureFormat::Rgba8UnormSrgb);
+    image::copy_to_frame(&image, &mut frame, Fit::Letterbox);
+    let expected = [
+        [BLACK; 4],
+        [RED, RED, GREEN, GREEN],
+        [RED, RED, GREEN, GREEN],
+        [BLACK; 4],
+    ];
+    assert_eq!(data, expected.concat().concat());
+
+    let mut frame = FrameMut::new(&mut data, 4, 4, TextureFormat::Bgra8UnormSrgb);
+    image::copy_to_frame(&image, &mut frame, Fit::Stretch);
+    let expected = [[BLUE, BLUE, GREEN, GREEN]; 4];
+    assert_eq!(data, expected.concat().concat());
+
+    // Pillarbox a tall image
+    let image = RgbaImage::new(1, 2, [RED, GREEN].concat());
+    let mut data = vec![0; 4 * 2 * 4];
+    let mut frame = FrameMut::new(&mut data, 4, 2, TextureFormat::Rgba8UnormSrgb);
+    image::copy_to_frame(&image, &mut frame, Fit::Letterbox);
+    let expected = [[BLACK, RED, BLACK, BLACK], [BLACK, GREEN, BLACK, BLACK]];
+    assert_eq!(data, expected.concat().concat());
+}
+
+/// Build a `4x2` pixel buffer, or `None` without a software adapter.
+fn build(format: TextureFormat) -> Option<Pixels<'static>> {
+    try_build(headless_builder(4, 2, 4, 2).texture_format(format))
+}
+
+#[test]
+fn load_frame_from() {
+    let Some(mut pixels) = build(TextureFormat::Rgba8UnormSrgb) else {
+        return;
+    };
+
+    let png = encode_png(2, 1, png::ColorType::Rgba, &[RED, GREEN].concat());
+    pixels.load_frame_from(&png, Fit::Stretch).unwrap();
+    assert_eq!(
+        pixels.frame(),
+        [[RED, RED, GREEN, GREEN]; 2].concat().concat()
+    );
+    assert!(pixels.needs_redraw());
+
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(rows(&image), [[RED, RED, GREEN, GREEN]; 2]);
+
+    assert!(matches!(
+        pixels.load_frame_from(&png[..8], Fit::Stretch),
+        Err(ImageError::Png(_))
+    ));
+}
+
+#[test]
+fn load_frame_unsupported_format() {
+    let Some(mut pixels) = build(TextureFormat::R8Uint) else {
+        return;
+    };
+
+    let png = encode_png(2, 1, png::ColorType::Rgba, &[RED, GREEN].concat());
+    assert!(matches!(
+        pixels.load_frame_from(&png, Fit::Stretch),
+        Err(ImageError::UnsupportedTextureFormat(TextureFormat::R8Uint))
+    ));
+}
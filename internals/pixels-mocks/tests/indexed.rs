@@ -0,0 +1,102 @@
+use pixels::wgpu::TextureFormat;
+use pixels::Pixels;
+use pixels_mocks::golden::{pattern_buffer, try_headless};
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 6;
+
+/// Build an indexed `8x6` pixel buffer with distinct indices, rendered at 1x, or `None` without
+/// a software adapter.
+fn build() -> Option<Pixels<'static>> {
+    pattern_buffer(WIDTH, HEIGHT, WIDTH, HEIGHT, TextureFormat::R8Uint)
+}
+
+/// Assert that each rendered pixel has the palette color for its index in the frame.
+#[track_caller]
+fn assert_palette_colors(pixels: &Pixels) {
+    let image = pixels.read_rendered_frame().unwrap();
+    let palette = pixels.palette().unwrap();
+    let rendered = image.pixels().chunks_exact(4);
+
+    for (i, (&index, actual)) in pixels.frame().iter().zip(rendered).enumerate() {
+        let expected = palette[index as usize];
+        let matches = actual
+            .iter()
+            .zip(expected)
+            .all(|(actual, expected)| actual.abs_diff(expected) <= 1);
+        assert!(
+            matches,
+            "Pixel {i} with index {index}: expected {expected:?}, got {actual:?}"
+        );
+    }
+}
+
+#[test]
+fn palette_lookup() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    assert_eq!(pixels.frame().len(), (WIDTH * HEIGHT) as usize);
+
+    // The default palette is a grayscale ramp
+    for (i, index) in pixels.frame_mut().iter_mut().enumerate() {
+        *index = (i * 5) as u8;
+    }
+    assert_palette_colors(&pixels);
+
+    let colors: Vec<[u8; 4]> = (0..16)
+        .map(|i| [i * 16, 0xff - i * 16, i * 8, 0xff])
+        .collect();
+    pixels.set_palette(&colors);
+    for (i, index) in pixels.frame_mut().iter_mut().enumerate() {
+        *index = (i % 20) as u8;
+    }
+    assert_palette_colors(&pixels);
+    assert_eq!(pixels.palette().unwrap()[16], [16, 16, 16, 0xff]);
+}
+
+#[test]
+fn palette_cycling() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    for (i, index) in pixels.frame_mut().iter_mut().enumerate() {
+        *index = (i % 4) as u8;
+    }
+    pixels.render().unwrap();
+
+    // Changing the palette requires a redraw, but not an upload
+    let mut colors = [
+        [0xff, 0, 0, 0xff],
+        [0, 0xff, 0, 0xff],
+        [0, 0, 0xff, 0xff],
+        [0xff; 4],
+    ];
+    for _ in 0..4 {
+        colors.rotate_left(1);
+        pixels.set_palette(&colors);
+        assert!(pixels.needs_redraw());
+        assert_palette_colors(&pixels);
+    }
+}
+
+#[test]
+fn palette_survives_resize() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    pixels.set_palette(&[[1, 2, 3, 4]]);
+    pixels.resize_buffer(4, 3).unwrap();
+
+    assert_eq!(pixels.palette().unwrap()[0], [1, 2, 3, 4]);
+    assert_eq!(pixels.frame().len(), 12);
+}
+
+#[test]
+fn not_indexed() {
+    let Some(pixels) = try_headless(WIDTH, HEIGHT, WIDTH, HEIGHT) else {
+        return;
+    };
+
+    assert_eq!(pixels.palette(), None);
+}
@@ -0,0 +1,180 @@
+use pixels::wgpu::TextureFormat;
+use pixels::{BlendMode, Pixels, RgbaImage, TextureError};
+use pixels_mocks::golden::pattern_buffer;
+
+const WIDTH: u32 = 4;
+const HEIGHT: u32 = 3;
+
+/// Build a `4x3` pixel buffer with the test pattern, rendered at 1x without any color space
+/// conversion, or `None` without a software adapter.
+fn build() -> Option<Pixels<'static>> {
+    build_with(TextureFormat::Rgba8Unorm)
+}
+
+/// Build a `4x3` pixel buffer with the test pattern, rendered at 1x, using `format` for the
+/// pixel buffer and the render target.
+fn build_with(format: TextureFormat) -> Option<Pixels<'static>> {
+    pattern_buffer(WIDTH, HEIGHT, WIDTH, HEIGHT, format)
+}
+
+/// Add a layer filled with a single color.
+fn add_solid(pixels: &mut Pixels, name: &str, width: u32, height: u32, color: [u8; 4]) {
+    let layer = pixels
+        .add_layer(name, width, height, TextureFormat::Rgba8Unorm)
+        .unwrap();
+    layer.frame_view_mut().fill(&color);
+}
+
+/// The color of the pixel at `(x, y)`.
+fn pixel(image: &RgbaImage, x: u32, y: u32) -> [u8; 4] {
+    let i = ((y * image.width() + x) * 4) as usize;
+
+    image.pixels()[i..i + 4].try_into().unwrap()
+}
+
+/// Assert that two colors differ by at most one in every channel.
+#[track_caller]
+fn assert_close(actual: [u8; 4], expected: [u8; 4]) {
+    let close = actual.iter().zip(expected).all(|(a, e)| a.abs_diff(e) <= 1);
+    assert!(close, "{actual:?} != {expected:?}");
+}
+
+#[test]
+fn normal() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let base = pixels.read_rendered_frame().unwrap();
+
+    // A transparent layer does not change anything
+    pixels
+        .add_layer("empty", WIDTH, HEIGHT, TextureFormat::Rgba8Unorm)
+        .unwrap();
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), base.pixels());
+
+    // Opaque, offset, and cropped at the bottom right
+    add_solid(&mut pixels, "sprite", 2, 2, [0xff, 0x00, 0x00, 0xff]);
+    pixels.layer_mut("sprite").unwrap().set_offset(3, 2);
+    let image = pixels.read_rendered_frame().unwrap();
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let expected = if x == 3 && y == 2 {
+                [0xff, 0x00, 0x00, 0xff]
+            } else {
+                pixel(&base, x, y)
+            };
+            assert_eq!(pixel(&image, x, y), expected, "({x}, {y})");
+        }
+    }
+
+    // Negative offsets and opacity
+    let sprite = pixels.layer_mut("sprite").unwrap();
+    sprite.set_offset(-1, -1);
+    sprite.set_opacity(0.5);
+    let image = pixels.read_rendered_frame().unwrap();
+    let [r, g, b, a] = pixel(&base, 0, 0);
+    let half = |c: u8, l: u8| ((c as f32 + l as f32) / 2.0).round() as u8;
+    assert_close(
+        pixel(&image, 0, 0),
+        [half(r, 0xff), half(g, 0), half(b, 0), half(a, 0xff)],
+    );
+    assert_eq!(pixel(&image, 1, 0), pixel(&base, 1, 0));
+    assert_eq!(pixel(&image, 0, 1), pixel(&base, 0, 1));
+
+    // Hidden layers are not drawn
+    pixels.layer_mut("sprite").unwrap().set_visible(false);
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), base.pixels());
+
+    // Removing all layers samples the pixel buffer directly again
+    assert!(pixels.remove_layer("sprite").is_some());
+    assert!(pixels.remove_layer("sprite").is_none());
+    assert!(pixels.remove_layer("empty").is_some());
+    pixels.frame_mut()[0] = 0x12;
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels()[0], 0x12);
+}
+
+#[test]
+fn blend_modes() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    pixels.frame_view_mut().fill(&[0x40, 0x80, 0xc0, 0xff]);
+
+    add_solid(&mut pixels, "light", 1, 1, [0x20, 0xff, 0x20, 0xff]);
+    pixels
+        .layer_mut("light")
+        .unwrap()
+        .set_blend_mode(BlendMode::Add);
+    add_solid(&mut pixels, "shadow", 1, 1, [0x80, 0x80, 0x00, 0xff]);
+    let shadow = pixels.layer_mut("shadow").unwrap();
+    shadow.set_blend_mode(BlendMode::Multiply);
+    shadow.set_offset(1, 0);
+
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_close(pixel(&image, 0, 0), [0x60, 0xff, 0xe0, 0xff]);
+    assert_close(pixel(&image, 1, 0), [0x20, 0x40, 0x00, 0xff]);
+    assert_eq!(pixel(&image, 2, 0), [0x40, 0x80, 0xc0, 0xff]);
+
+    // Layers are drawn in order, so the light is multiplied too
+    pixels.layer_mut("shadow").unwrap().set_offset(0, 0);
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_close(pixel(&image, 0, 0), [0x30, 0x80, 0x00, 0xff]);
+
+    // Replacing a layer keeps its place in the order
+    add_solid(&mut pixels, "light", 1, 1, [0x00, 0x00, 0x00, 0x00]);
+    let names: Vec<_> = pixels.layers().map(|layer| layer.name()).collect();
+    assert_eq!(names, ["light", "shadow"]);
+    assert_eq!(
+        pixels.layer("light").unwrap().blend_mode(),
+        BlendMode::Normal
+    );
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_close(pixel(&image, 0, 0), [0x20, 0x40, 0x00, 0xff]);
+}
+
+#[test]
+fn resize_buffer() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    add_solid(&mut pixels, "hud", 1, 1, [0xff, 0xff, 0xff, 0xff]);
+
+    pixels.resize_buffer(2, 2).unwrap();
+    pixels.resize_surface(2, 2).unwrap();
+    pixels.frame_view_mut().fill(&[0x00, 0x00, 0x00, 0xff]);
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(pixel(&image, 0, 0), [0xff, 0xff, 0xff, 0xff]);
+    assert_eq!(pixel(&image, 1, 1), [0x00, 0x00, 0x00, 0xff]);
+
+    // Layers have their own format and size limits
+    assert!(matches!(
+        pixels.add_layer("indexed", 2, 2, TextureFormat::R8Uint),
+        Err(TextureError::TextureFormat(TextureFormat::R8Uint))
+    ));
+    assert!(matches!(
+        pixels.add_layer("empty", 0, 2, TextureFormat::Rgba8Unorm),
+        Err(TextureError::TextureWidth(0))
+    ));
+    assert_eq!(pixels.layers().count(), 1);
+}
+
+#[test]
+fn srgb() {
+    let Some(mut pixels) = build_with(TextureFormat::Rgba8UnormSrgb) else {
+        return;
+    };
+    // Every value survives the round trip through the composited texture
+    for (i, byte) in pixels.frame_mut().iter_mut().enumerate() {
+        *byte = (i * 21) as u8;
+    }
+    let base = pixels.read_rendered_frame().unwrap();
+
+    pixels
+        .add_layer("empty", 1, 1, TextureFormat::Rgba8UnormSrgb)
+        .unwrap();
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), base.pixels());
+}
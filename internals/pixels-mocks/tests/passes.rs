@@ -0,0 +1,282 @@
+use pixels::wgpu::{self, TextureFormat};
+use pixels::{PassContext, Pixels, RenderPass, RgbaImage};
+use pixels_mocks::golden::pattern_buffer;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const WIDTH: u32 = 4;
+const HEIGHT: u32 = 3;
+
+const INVERT_SHADER: &str = "
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@group(0) @binding(0) var input: texture_2d<f32>;
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let color = textureLoad(input, vec2<i32>(position.xy), 0);
+    return vec4<f32>(1.0 - color.rgb, color.a);
+}
+";
+
+/// What the invert pass saw on each call.
+#[derive(Debug, Default)]
+struct Log {
+    resized: Vec<(u32, u32)>,
+    rendered: Vec<Rendered>,
+}
+
+/// The pass context of a single render call.
+#[derive(Debug, Clone, Copy)]
+struct Rendered {
+    clip_rect: (u32, u32, u32, u32),
+    size: (u32, u32),
+    time: Duration,
+    delta_time: Duration,
+}
+
+/// Inverts the color channels of its input.
+struct Invert {
+    layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+    bind_group: Option<wgpu::BindGroup>,
+    log: Arc<Mutex<Log>>,
+}
+
+impl Invert {
+    fn new(pixels: &Pixels, log: &Arc<Mutex<Log>>) -> Box<Self> {
+        let device = pixels.device();
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("invert"),
+            source: wgpu::ShaderSource::Wgsl(INVERT_SHADER.into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("invert"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("invert"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("invert"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: pixels.render_texture_format(),
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Box::new(Self {
+            layout,
+            pipeline,
+            bind_group: None,
+            log: Arc::clone(log),
+        })
+    }
+}
+
+impl RenderPass for Invert {
+    fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        input: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) {
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("invert"),
+            layout: &self.layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(input),
+            }],
+        }));
+        self.log.lock().unwrap().resized.push((width, height));
+    }
+
+    fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        context: &PassContext<'_>,
+    ) {
+        self.log.lock().unwrap().rendered.push(Rendered {
+            clip_rect: context.clip_rect,
+            size: context.size,
+            time: context.time,
+            delta_time: context.delta_time,
+        });
+
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("invert"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, self.bind_group.as_ref().unwrap(), &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+/// Build a `4x3` pixel buffer with the test pattern, rendered at 2x to a linear target, or `None`
+/// without a software adapter.
+fn build() -> Option<Pixels<'static>> {
+    let mut pixels = pattern_buffer(
+        WIDTH,
+        HEIGHT,
+        WIDTH * 2 + 2,
+        HEIGHT * 2,
+        TextureFormat::Rgba8Unorm,
+    )?;
+    pixels.clear_color(wgpu::Color {
+        r: 0.0,
+        g: 0.0,
+        b: 1.0,
+        a: 1.0,
+    });
+
+    Some(pixels)
+}
+
+/// Invert the color channels of an image, keeping alpha.
+fn inverted(image: &RgbaImage) -> Vec<u8> {
+    image
+        .pixels()
+        .chunks_exact(4)
+        .flat_map(|p| [255 - p[0], 255 - p[1], 255 - p[2], p[3]])
+        .collect()
+}
+
+#[test]
+fn send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    // Render passes are stored in the pixel buffer, which can be shared between threads
+    assert_send_sync::<Pixels<'static>>();
+    assert_send_sync::<Box<dyn RenderPass>>();
+}
+
+#[test]
+fn single_pass() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let log = Arc::default();
+    let expected = pixels.read_rendered_frame().unwrap();
+
+    pixels.add_pass(Invert::new(&pixels, &log));
+    assert_eq!(pixels.pass_count(), 1);
+    assert_eq!(log.lock().unwrap().resized, [(10, 6)]);
+
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), inverted(&expected));
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.rendered.len(), 1);
+    let rendered = log.rendered[0];
+    assert_eq!(rendered.clip_rect, (1, 0, 8, 6));
+    assert_eq!(rendered.size, (10, 6));
+    assert_eq!(rendered.time, Duration::ZERO);
+    assert_eq!(rendered.delta_time, Duration::ZERO);
+}
+
+#[test]
+fn ping_pong() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let log = Arc::default();
+    let expected = pixels.read_rendered_frame().unwrap();
+
+    // An odd number of passes uses both intermediate targets in both directions
+    for _ in 0..3 {
+        pixels.add_pass(Invert::new(&pixels, &log));
+    }
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), inverted(&expected));
+
+    pixels.add_pass(Invert::new(&pixels, &log));
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), expected.pixels());
+
+    // Frame times advance across frames
+    {
+        let log = log.lock().unwrap();
+        assert_eq!(log.rendered.len(), 3 + 4);
+        let first = log.rendered[0];
+        let last = log.rendered[6];
+        assert!(last.time >= first.time);
+        assert_eq!(last.time - first.time, last.delta_time);
+    }
+
+    let passes = pixels.clear_passes();
+    assert_eq!(passes.len(), 4);
+    assert_eq!(pixels.pass_count(), 0);
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), expected.pixels());
+}
+
+#[test]
+fn resize_surface() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let log = Arc::default();
+    pixels.add_pass(Invert::new(&pixels, &log));
+    pixels.add_pass(Invert::new(&pixels, &log));
+
+    pixels.resize_surface(WIDTH * 3, HEIGHT * 3).unwrap();
+    assert_eq!(
+        log.lock().unwrap().resized,
+        [(10, 6), (10, 6), (12, 9), (12, 9)]
+    );
+
+    let image = pixels.read_rendered_frame().unwrap();
+    assert_eq!((image.width(), image.height()), (WIDTH * 3, HEIGHT * 3));
+    pixels.clear_passes();
+    let expected = pixels.read_rendered_frame().unwrap();
+    assert_eq!(image.pixels(), expected.pixels());
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.rendered[0].clip_rect, (0, 0, 12, 9));
+    assert_eq!(log.rendered[0].size, (12, 9));
+}
@@ -0,0 +1,298 @@
+use pixels::record::{RecordError, RecordFormat, Recorder};
+use pixels::wgpu::TextureFormat;
+use pixels::{Pixels, Resolution, RgbaImage};
+use pixels_mocks::golden::{headless_builder, try_build};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const RED: [u8; 4] = [0xff, 0x00, 0x00, 0xff];
+const WHITE: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+const BLACK: [u8; 4] = [0x00, 0x00, 0x00, 0xff];
+
+/// A writer that can be inspected after the recorder is finished with it.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn bytes(&self) -> Vec<u8> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A `3x2` image filled with one color.
+fn solid(color: [u8; 4]) -> RgbaImage {
+    RgbaImage::new(3, 2, color.repeat(6))
+}
+
+/// Record the images at the given timestamps in milliseconds.
+fn record(format: RecordFormat, frame_rate: u32, frames: &[([u8; 4], u64)]) -> Vec<u8> {
+    let buffer = SharedBuffer::default();
+    let mut recorder =
+        Recorder::new(buffer.clone(), format, Resolution::Buffer).max_frame_rate(frame_rate);
+    for &(color, timestamp) in frames {
+        recorder.capture_at(solid(color), Duration::from_millis(timestamp));
+    }
+    recorder.finish().unwrap();
+
+    buffer.bytes()
+}
+
+/// A path in the temporary directory that is unique to the test.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pixels-{}-{name}", std::process::id()))
+}
+
+#[test]
+fn gif() {
+    let bytes = record(
+        RecordFormat::Gif,
+        60,
+        &[(RED, 0), (WHITE, 100), (BLACK, 350)],
+    );
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(bytes.as_slice()).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (3, 2));
+
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push((frame.buffer[..4].to_vec(), frame.delay));
+    }
+    assert_eq!(
+        frames,
+        [
+            (RED.to_vec(), 10),
+            (WHITE.to_vec(), 25),
+            // The last frame repeats the previous delay
+            (BLACK.to_vec(), 25),
+        ]
+    );
+}
+
+#[test]
+fn apng() {
+    let bytes = record(
+        RecordFormat::Apng,
+        60,
+        &[(RED, 0), (WHITE, 100), (BLACK, 350)],
+    );
+
+    let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+    let animation = reader.info().animation_control.unwrap();
+    assert_eq!((animation.num_frames, animation.num_plays), (3, 0));
+
+    let mut frames = Vec::new();
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    for _ in 0..3 {
+        reader.next_frame(&mut buffer).unwrap();
+        let control = reader.info().frame_control.unwrap();
+        let delay = control.delay_num as u32 * 1000 / control.delay_den as u32;
+        assert_eq!(buffer, buffer[..4].repeat(6));
+        frames.push((buffer[..4].to_vec(), delay));
+    }
+    assert_eq!(
+        frames,
+        [
+            (RED.to_vec(), 100),
+            (WHITE.to_vec(), 250),
+            (BLACK.to_vec(), 250),
+        ]
+    );
+}
+
+#[test]
+fn y4m() {
+    let bytes = record(
+        RecordFormat::Y4m,
+        10,
+        &[(WHITE, 0), (BLACK, 100), (RED, 350)],
+    );
+
+    let header = b"YUV4MPEG2 W3 H2 F10:1 Ip A1:1 C420jpeg\n";
+    assert_eq!(bytes[..header.len()], header[..]);
+
+    // Each frame has 6 luma samples and 2 chroma planes with 2 samples each
+    let frames: Vec<_> = bytes[header.len()..].chunks(6 + 10).collect();
+    assert!(frames.iter().all(|frame| frame.starts_with(b"FRAME\n")));
+    let luma: Vec<_> = frames.iter().map(|frame| frame[6]).collect();
+
+    // The frames are shown for 100ms, 250ms, and 250ms at 10 frames per second
+    assert_eq!(luma, [235, 16, 16, 16, 82, 82]);
+    assert_eq!(frames[4][6..], [82, 82, 82, 82, 82, 82, 90, 90, 240, 240]);
+}
+
+#[test]
+fn max_frame_rate() {
+    let bytes = record(
+        RecordFormat::Gif,
+        10,
+        &[(RED, 0), (WHITE, 50), (BLACK, 100)],
+    );
+
+    let mut decoder = gif::DecodeOptions::new()
+        .read_info(bytes.as_slice())
+        .unwrap();
+    let mut count = 0;
+    while decoder.read_next_frame().unwrap().is_some() {
+        count += 1;
+    }
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn dropped_frames() {
+    /// A writer that blocks until the gate is unlocked.
+    struct GatedWriter(Arc<Mutex<()>>);
+
+    impl Write for GatedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let _gate = self.0.lock().unwrap();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // The encoder is stuck writing the second frame, so frames beyond the queue are dropped
+    let gate = Arc::new(Mutex::new(()));
+    let locked = gate.lock().unwrap();
+    let writer = GatedWriter(Arc::clone(&gate));
+    let mut recorder = Recorder::new(writer, RecordFormat::Y4m, Resolution::Buffer);
+    for i in 0..32 {
+        recorder.capture_at(solid(RED), Duration::from_millis(i * 20));
+    }
+    assert!(recorder.dropped_frames() >= 20, "{recorder:?}");
+
+    drop(locked);
+    recorder.finish().unwrap();
+}
+
+#[test]
+fn size_changed() {
+    let mut recorder = Recorder::new(
+        SharedBuffer::default(),
+        RecordFormat::Apng,
+        Resolution::Buffer,
+    );
+    recorder.capture(solid(RED));
+    recorder.capture(RgbaImage::new(1, 1, RED.to_vec()));
+
+    assert!(matches!(
+        recorder.finish(),
+        Err(RecordError::SizeChanged(3, 2, 1, 1))
+    ));
+}
+
+#[test]
+fn create_file() {
+    let path = temp_path("empty.y4m");
+    Recorder::create(&path, Resolution::Buffer)
+        .unwrap()
+        .finish()
+        .unwrap();
+    // Nothing is written without frames
+    assert!(std::fs::read(&path).unwrap().is_empty());
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(
+        Recorder::create(temp_path("recording.mp4"), Resolution::Buffer),
+        Err(RecordError::UnknownFormat)
+    ));
+    assert_eq!(
+        RecordFormat::from_extension("APNG"),
+        Some(RecordFormat::Apng)
+    );
+}
+
+/// Build a `3x2` pixel buffer rendered at 2x, or `None` without a software adapter.
+fn build(format: TextureFormat) -> Option<Pixels<'static>> {
+    try_build(headless_builder(3, 2, 6, 4).texture_format(format))
+}
+
+/// Render three frames with different colors while recording.
+fn record_pixels(pixels: &mut Pixels, resolution: Resolution) -> Vec<u8> {
+    let buffer = SharedBuffer::default();
+    let recorder = Recorder::new(buffer.clone(), RecordFormat::Apng, resolution);
+    assert!(pixels.start_recording(recorder).unwrap().is_none());
+
+    for color in [RED, WHITE, BLACK] {
+        pixels.frame_mut().copy_from_slice(&color.repeat(6));
+        pixels.render().unwrap();
+    }
+    let recorder = pixels.stop_recording().unwrap();
+    assert_eq!(recorder.dropped_frames(), 0);
+    recorder.finish().unwrap();
+    assert!(pixels.stop_recording().is_none());
+
+    buffer.bytes()
+}
+
+/// Decode the first pixel of each frame in an animated PNG, and the frame size.
+fn decode_apng(bytes: &[u8]) -> ((u32, u32), Vec<[u8; 4]>) {
+    let mut reader = png::Decoder::new(bytes).read_info().unwrap();
+    let size = (reader.info().width, reader.info().height);
+    let frames = reader.info().animation_control.unwrap().num_frames;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let colors = (0..frames)
+        .map(|_| {
+            reader.next_frame(&mut buffer).unwrap();
+            [buffer[0], buffer[1], buffer[2], buffer[3]]
+        })
+        .collect();
+
+    (size, colors)
+}
+
+#[test]
+fn record_buffer() {
+    let Some(mut pixels) = build(TextureFormat::Rgba8UnormSrgb) else {
+        return;
+    };
+
+    let bytes = record_pixels(&mut pixels, Resolution::Buffer);
+    assert_eq!(decode_apng(&bytes), ((3, 2), vec![RED, WHITE, BLACK]));
+}
+
+#[test]
+fn record_surface() {
+    let Some(mut pixels) = build(TextureFormat::Rgba8UnormSrgb) else {
+        return;
+    };
+
+    let bytes = record_pixels(&mut pixels, Resolution::Surface);
+    assert_eq!(decode_apng(&bytes), ((6, 4), vec![RED, WHITE, BLACK]));
+}
+
+#[test]
+fn record_unsupported_format() {
+    let Some(mut pixels) = build(TextureFormat::Rg8Unorm) else {
+        return;
+    };
+
+    let recorder = Recorder::new(
+        SharedBuffer::default(),
+        RecordFormat::Gif,
+        Resolution::Buffer,
+    );
+    assert!(matches!(
+        pixels.start_recording(recorder),
+        Err(pixels::Error::UnsupportedFormat(TextureFormat::Rg8Unorm))
+    ));
+    assert!(pixels.stop_recording().is_none());
+}
@@ -0,0 +1,182 @@
+use pixels::wgpu::{self, TextureFormat};
+use pixels::{PassContext, Pixels, RenderPass};
+use pixels_mocks::golden::pattern_buffer;
+use std::sync::Arc;
+
+const WIDTH: u32 = 4;
+const HEIGHT: u32 = 3;
+const FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+/// Build a `4x3` pixel buffer with the test pattern on an `8x6` surface, without any color space
+/// conversion, or `None` without a software adapter.
+fn build() -> Option<Pixels<'static>> {
+    pattern_buffer(WIDTH, HEIGHT, WIDTH * 2, HEIGHT * 2, FORMAT)
+}
+
+/// A texture owned by the application, e.g. for a widget.
+fn create_target(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("widget"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+/// Render into the texture with the application's encoder, and read it back.
+fn render_to(pixels: &mut Pixels, texture: &wgpu::Texture) -> Vec<u8> {
+    let device = Arc::clone(&pixels.context().device);
+    let (width, height) = (texture.width(), texture.height());
+    let padded_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("widget_readback"),
+        size: (padded_row * height) as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    pixels
+        .render_to(&mut encoder, &view, (width, height))
+        .unwrap();
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    pixels.queue().submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+    let data = slice.get_mapped_range();
+
+    data.chunks_exact(padded_row as usize)
+        .flat_map(|row| &row[..(width * 4) as usize])
+        .copied()
+        .collect()
+}
+
+#[test]
+fn matches_render() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let texture = create_target(pixels.device(), WIDTH * 2, HEIGHT * 2);
+
+    let actual = render_to(&mut pixels, &texture);
+    assert!(!pixels.needs_redraw());
+    let expected = pixels.read_rendered_frame().unwrap();
+    assert_eq!(actual, expected.pixels());
+
+    // Changes to the pixel buffer are uploaded
+    pixels.frame_mut()[..4].copy_from_slice(&[0x12, 0x34, 0x56, 0xff]);
+    let actual = render_to(&mut pixels, &texture);
+    let row = (WIDTH * 2 * 4) as usize;
+    for i in [0, 4, row, row + 4] {
+        assert_eq!(actual[i..i + 4], [0x12, 0x34, 0x56, 0xff]);
+    }
+}
+
+#[test]
+fn resize_target() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let small = create_target(pixels.device(), WIDTH, HEIGHT);
+    let large = create_target(pixels.device(), WIDTH * 3, HEIGHT * 3);
+
+    let first = render_to(&mut pixels, &small);
+    assert_eq!(first, pixels.frame());
+    let scaled = render_to(&mut pixels, &large);
+    assert_eq!(scaled.len(), (WIDTH * HEIGHT * 9 * 4) as usize);
+
+    // Every pixel is scaled up to a 3x3 block
+    for (i, pixel) in scaled.chunks_exact(4).enumerate() {
+        let (x, y) = (i % (WIDTH * 3) as usize, i / (WIDTH * 3) as usize);
+        let j = (y / 3 * WIDTH as usize + x / 3) * 4;
+        assert_eq!(pixel, &first[j..j + 4]);
+    }
+
+    let mut encoder = pixels
+        .device()
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    let view = small.create_view(&wgpu::TextureViewDescriptor::default());
+    assert!(matches!(
+        pixels.render_to(&mut encoder, &view, (0, HEIGHT)),
+        Err(pixels::Error::InvalidTexture(_))
+    ));
+}
+
+#[test]
+fn surface_unchanged() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    let texture = create_target(pixels.device(), WIDTH * 3, HEIGHT * 3);
+    render_to(&mut pixels, &texture);
+
+    // The surface keeps its size, and positions stay relative to the surface
+    let frame = pixels.read_rendered_frame().unwrap();
+    assert_eq!((frame.width(), frame.height()), (WIDTH * 2, HEIGHT * 2));
+    assert_eq!(
+        pixels.window_pos_to_pixel((7.0, 5.0)),
+        Ok(((WIDTH - 1) as usize, (HEIGHT - 1) as usize))
+    );
+    assert_eq!(
+        pixels.window_pos_to_pixel((8.0, 5.0)),
+        Err((WIDTH as isize, 2))
+    );
+}
+
+/// A pass that draws nothing.
+struct Noop;
+
+impl RenderPass for Noop {
+    fn render(
+        &self,
+        _encoder: &mut wgpu::CommandEncoder,
+        _output: &wgpu::TextureView,
+        _context: &PassContext<'_>,
+    ) {
+    }
+}
+
+#[test]
+fn passes_need_surface_size() {
+    let Some(mut pixels) = build() else {
+        return;
+    };
+    pixels.add_pass(Box::new(Noop));
+    let texture = create_target(pixels.device(), WIDTH * 3, HEIGHT * 3);
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let mut encoder = pixels
+        .device()
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    assert!(matches!(
+        pixels.render_to(&mut encoder, &view, (WIDTH * 3, HEIGHT * 3)),
+        Err(pixels::Error::PassTargetSize(8, 6))
+    ));
+
+    // The passes run at the target size once the surface matches it
+    pixels.resize_surface(WIDTH * 3, HEIGHT * 3).unwrap();
+    pixels
+        .render_to(&mut encoder, &view, (WIDTH * 3, HEIGHT * 3))
+        .unwrap();
+}
@@ -0,0 +1,226 @@
+use pixels::wgpu::TextureFormat;
+use pixels::{Pixels, Resolution, RgbaImage};
+use pixels_mocks::golden::{headless_builder, read_png, test_pattern, try_build};
+use std::path::PathBuf;
+
+const WIDTH: u32 = 4;
+const HEIGHT: u32 = 3;
+
+/// Build a `4x3` pixel buffer with the given texture formats, rendered at the given scale, or
+/// `None` without a software adapter.
+fn build(
+    format: TextureFormat,
+    render_format: TextureFormat,
+    scale: u32,
+) -> Option<Pixels<'static>> {
+    let builder = headless_builder(WIDTH, HEIGHT, WIDTH * scale, HEIGHT * scale)
+        .texture_format(format)
+        .render_texture_format(render_format)
+        .surface_texture_format(render_format);
+    try_build(builder)
+}
+
+/// A path in the temporary directory that is unique to the test.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("pixels-{}-{name}.png", std::process::id()))
+}
+
+/// Assert that two images are equal, allowing each color channel to differ by `tolerance`.
+#[track_caller]
+fn assert_similar(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) {
+    assert_eq!(
+        (actual.width(), actual.height()),
+        (expected.width(), expected.height())
+    );
+    let pixels = actual
+        .pixels()
+        .chunks_exact(4)
+        .zip(expected.pixels().chunks_exact(4));
+    for (i, (actual, expected)) in pixels.enumerate() {
+        let similar = actual
+            .iter()
+            .zip(expected)
+            .all(|(actual, expected)| actual.abs_diff(*expected) <= tolerance);
+        assert!(similar, "Pixel {i}: expected {expected:?}, got {actual:?}");
+    }
+}
+
+/// Assert that converting the pixel buffer gives the same colors as rendering it at 1x.
+#[track_caller]
+fn assert_matches_rendered(pixels: &Pixels) {
+    let converted = pixels.frame_to_rgba().unwrap();
+    let rendered = pixels.read_rendered_frame().unwrap();
+
+    assert_similar(&converted, &rendered, 1);
+}
+
+#[test]
+fn buffer_resolution() {
+    let Some(mut pixels) = build(
+        TextureFormat::Rgba8UnormSrgb,
+        TextureFormat::Rgba8UnormSrgb,
+        2,
+    ) else {
+        return;
+    };
+    test_pattern(pixels.frame_mut(), WIDTH, HEIGHT);
+
+    let path = temp_path("buffer");
+    pixels.save_screenshot(&path, Resolution::Buffer).unwrap();
+    let image = read_png(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!((image.width(), image.height()), (WIDTH, HEIGHT));
+    assert_eq!(image.pixels(), pixels.frame());
+}
+
+#[test]
+fn surface_resolution() {
+    let Some(mut pixels) = build(
+        TextureFormat::Rgba8UnormSrgb,
+        TextureFormat::Bgra8UnormSrgb,
+        2,
+    ) else {
+        return;
+    };
+    test_pattern(pixels.frame_mut(), WIDTH, HEIGHT);
+
+    let path = temp_path("surface");
+    pixels.save_screenshot(&path, Resolution::Surface).unwrap();
+    let image = read_png(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!((image.width(), image.height()), (WIDTH * 2, HEIGHT * 2));
+    assert_similar(&image, &pixels.read_rendered_frame().unwrap(), 0);
+
+    // The top-left pixel is scaled to 2x2 pixels
+    let frame = pixels.frame();
+    let row = (WIDTH * 2 * 4) as usize;
+    assert_eq!(image.pixels()[..8], [&frame[..4], &frame[..4]].concat());
+    assert_eq!(image.pixels()[row..row + 4], frame[..4]);
+}
+
+#[test]
+fn custom_render_function() {
+    let Some(pixels) = build(
+        TextureFormat::Rgba8UnormSrgb,
+        TextureFormat::Rgba8UnormSrgb,
+        1,
+    ) else {
+        return;
+    };
+
+    let path = temp_path("custom");
+    pixels
+        .save_screenshot_with(&path, |encoder, render_target, _context| {
+            encoder.begin_render_pass(&pixels::wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(pixels::wgpu::RenderPassColorAttachment {
+                    view: render_target,
+                    resolve_target: None,
+                    ops: pixels::wgpu::Operations {
+                        load: pixels::wgpu::LoadOp::Clear(pixels::wgpu::Color::RED),
+                        store: pixels::wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            Ok(())
+        })
+        .unwrap();
+    let image = read_png(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let red = [0xff, 0x00, 0x00, 0xff];
+    assert_eq!(image.pixels(), red.repeat((WIDTH * HEIGHT) as usize));
+}
+
+#[test]
+fn convert_bgra() {
+    let Some(mut pixels) = build(
+        TextureFormat::Bgra8UnormSrgb,
+        TextureFormat::Rgba8UnormSrgb,
+        1,
+    ) else {
+        return;
+    };
+    test_pattern(pixels.frame_mut(), WIDTH, HEIGHT);
+
+    let image = pixels.frame_to_rgba().unwrap();
+    assert_eq!(
+        image.pixels()[..4],
+        [
+            pixels.frame()[2],
+            pixels.frame()[1],
+            pixels.frame()[0],
+            pixels.frame()[3]
+        ]
+    );
+    assert_matches_rendered(&pixels);
+}
+
+#[test]
+fn convert_linear_to_srgb() {
+    let Some(mut pixels) = build(TextureFormat::Rgba8Unorm, TextureFormat::Rgba8UnormSrgb, 1)
+    else {
+        return;
+    };
+    test_pattern(pixels.frame_mut(), WIDTH, HEIGHT);
+    pixels.frame_mut()[..4].copy_from_slice(&[0x80, 0x00, 0xff, 0xff]);
+
+    // Linear values are sRGB encoded
+    let image = pixels.frame_to_rgba().unwrap();
+    assert_eq!(image.pixels()[..4], [188, 0, 255, 0xff]);
+    assert_matches_rendered(&pixels);
+}
+
+#[test]
+fn convert_srgb_to_linear() {
+    let Some(mut pixels) = build(TextureFormat::Rgba8UnormSrgb, TextureFormat::Bgra8Unorm, 1)
+    else {
+        return;
+    };
+    test_pattern(pixels.frame_mut(), WIDTH, HEIGHT);
+    pixels.frame_mut()[..4].copy_from_slice(&[188, 0x00, 0xff, 0xff]);
+
+    let image = pixels.frame_to_rgba().unwrap();
+    assert_eq!(image.pixels()[..4], [0x80, 0, 255, 0xff]);
+    assert_matches_rendered(&pixels);
+}
+
+#[test]
+fn convert_indexed() {
+    let Some(mut pixels) = build(TextureFormat::R8Uint, TextureFormat::Rgba8UnormSrgb, 1) else {
+        return;
+    };
+    pixels.set_palette(&[[0xff, 0x00, 0x00, 0xff], [0x00, 0x80, 0xff, 0xff]]);
+    pixels
+        .frame_mut()
+        .copy_from_slice(&[0, 1, 2, 3, 1, 1, 0, 0, 255, 254, 0, 1]);
+
+    let image = pixels.frame_to_rgba().unwrap();
+    assert_eq!(
+        image.pixels()[..12],
+        [0xff, 0, 0, 0xff, 0, 0x80, 0xff, 0xff, 2, 2, 2, 0xff]
+    );
+    assert_matches_rendered(&pixels);
+}
+
+#[test]
+fn unsupported_format() {
+    let Some(pixels) = build(TextureFormat::Rg8Unorm, TextureFormat::Rgba8UnormSrgb, 1) else {
+        return;
+    };
+
+    assert!(matches!(
+        pixels.frame_to_rgba(),
+        Err(pixels::Error::UnsupportedFormat(TextureFormat::Rg8Unorm))
+    ));
+    assert!(matches!(
+        pixels.save_screenshot(temp_path("unsupported"), Resolution::Buffer),
+        Err(pixels::Error::UnsupportedFormat(TextureFormat::Rg8Unorm))
+    ));
+}
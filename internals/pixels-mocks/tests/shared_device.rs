@@ -0,0 +1,165 @@
+use pixels::wgpu::{self, TextureFormat};
+use pixels::{CrtOptions, Pixels, PixelsDevice, ScalingFilter};
+use pixels_mocks::golden::{headless_builder, skip_without_adapter, try_headless};
+use std::sync::Arc;
+
+#[test]
+fn share_device() {
+    let Some(first) = try_headless(2, 2, 2, 2) else {
+        return;
+    };
+
+    let context = first.context();
+    let mut second = headless_builder(4, 4, 8, 8)
+        .with_device(
+            Arc::clone(&context.instance),
+            Arc::clone(&context.adapter),
+            Arc::clone(&context.device),
+            Arc::clone(&context.queue),
+        )
+        .build()
+        .unwrap();
+    assert!(Arc::ptr_eq(&second.context().device, &context.device));
+    assert!(Arc::ptr_eq(&second.context().queue, &context.queue));
+
+    // Both pixel buffers render independently on the same device
+    second.frame_mut().fill(0xff);
+    let image = second.read_rendered_frame().unwrap();
+    assert_eq!((image.width(), image.height()), (8, 8));
+    assert!(image.pixels().iter().all(|&byte| byte == 0xff));
+    let image = first.read_rendered_frame().unwrap();
+    assert!(image
+        .pixels()
+        .chunks_exact(4)
+        .all(|p| p == [0x00, 0x00, 0x00, 0xff]));
+
+    // The shared device outlives the first pixel buffer
+    drop(first);
+    second.render().unwrap();
+}
+
+#[test]
+fn external_device() {
+    let instance = wgpu::Instance::default();
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::None,
+        force_fallback_adapter: true,
+        compatible_surface: None,
+    }));
+    let Some(adapter) = skip_without_adapter(adapter.ok_or(pixels::Error::AdapterNotFound)) else {
+        return;
+    };
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .unwrap();
+    let device = Arc::new(device);
+    let queue = Arc::new(queue);
+
+    let pixels: Pixels = headless_builder(4, 4, 4, 4)
+        .with_device(
+            Arc::new(instance),
+            Arc::new(adapter),
+            Arc::clone(&device),
+            Arc::clone(&queue),
+        )
+        .build()
+        .unwrap();
+    assert!(Arc::ptr_eq(&pixels.context().device, &device));
+
+    // The application keeps using its device for its own work
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 16,
+        usage: wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    queue.write_buffer(&buffer, 0, &[0; 16]);
+    pixels.render().unwrap();
+}
+
+#[test]
+fn pixels_device() {
+    let Some(gpu) = skip_without_adapter(PixelsDevice::new()) else {
+        return;
+    };
+    let build = |gpu: &PixelsDevice, width, height| -> Pixels {
+        headless_builder(width, height, width * 2, height * 2)
+            .texture_format(TextureFormat::Rgba8Unorm)
+            .render_texture_format(TextureFormat::Rgba8Unorm)
+            .surface_texture_format(TextureFormat::Rgba8Unorm)
+            .with_pixels_device(gpu)
+            .build()
+            .unwrap()
+    };
+    let solid = |pixels: &Pixels, color: [u8; 4]| {
+        let image = pixels.read_rendered_frame().unwrap();
+        image.pixels().chunks_exact(4).all(|p| p == color)
+    };
+
+    let mut game = build(&gpu, 4, 4);
+    let mut tiles = build(&gpu, 2, 2);
+    assert!(Arc::ptr_eq(&game.context().device, gpu.device()));
+    assert!(Arc::ptr_eq(&tiles.context().queue, gpu.queue()));
+
+    // Each pixel buffer has its own frame and scaling options
+    game.frame_view_mut().fill(&[0x10, 0x20, 0x30, 0xff]);
+    tiles.frame_view_mut().fill(&[0xff, 0x00, 0x00, 0xff]);
+    tiles.set_scaling_filter(ScalingFilter::Scale2x);
+    assert!(solid(&game, [0x10, 0x20, 0x30, 0xff]));
+    assert!(solid(&tiles, [0xff, 0x00, 0x00, 0xff]));
+
+    // Other formats work alongside
+    let mut palette: Pixels = headless_builder(2, 2, 2, 2)
+        .with_pixels_device(&gpu)
+        .build()
+        .unwrap();
+    palette.frame_mut().fill(0xff);
+    assert!(solid(&palette, [0xff, 0xff, 0xff, 0xff]));
+
+    // Resizing recreates the renderer on the same device
+    tiles.resize_buffer(3, 3).unwrap();
+    tiles.resize_surface(6, 6).unwrap();
+    tiles.frame_view_mut().fill(&[0x00, 0xff, 0x00, 0xff]);
+    assert!(solid(&tiles, [0x00, 0xff, 0x00, 0xff]));
+
+    // The device outlives the pixel buffers it was shared with
+    let mut viewer = build(&game.pixels_device(), 2, 2);
+    assert!(Arc::ptr_eq(&viewer.context().device, gpu.device()));
+    drop((game, tiles, palette, gpu));
+    viewer.frame_view_mut().fill(&[0x00, 0x00, 0xff, 0xff]);
+    assert!(solid(&viewer, [0x00, 0x00, 0xff, 0xff]));
+}
+
+#[test]
+fn shared_pipelines() {
+    let Some(gpu) = skip_without_adapter(PixelsDevice::new()) else {
+        return;
+    };
+    let build = |format| -> Pixels {
+        let mut pixels = headless_builder(4, 4, 8, 8)
+            .texture_format(format)
+            .with_pixels_device(&gpu)
+            .build()
+            .unwrap();
+        for (i, byte) in pixels.frame_mut().iter_mut().enumerate() {
+            *byte = (i * 7) as u8;
+        }
+        pixels.set_crt(Some(CrtOptions::default()));
+        let layer = pixels
+            .add_layer("cursor", 2, 2, TextureFormat::Rgba8Unorm)
+            .unwrap();
+        layer.frame_view_mut().fill(&[0xff, 0x00, 0xff, 0x80]);
+        layer.set_offset(1, 1);
+        pixels
+    };
+
+    // Both pixel buffers draw with the same palette, CRT, and compositor pipelines
+    for format in [TextureFormat::Rgba8UnormSrgb, TextureFormat::R8Uint] {
+        let first = build(format);
+        let second = build(format);
+        assert_eq!(
+            first.read_rendered_frame().unwrap().pixels(),
+            second.read_rendered_frame().unwrap().pixels()
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use pixels::text::{self, BitmapFont, FontError};
+use pixels::wgpu::TextureFormat;
+use pixels::{Frame, FrameMut};
+
+const WHITE: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
+/// Draw into a blank `width` by `height` frame and render it as ASCII art.
+fn ascii<F>(width: u32, height: u32, draw: F) -> Vec<String>
+where
+    F: FnOnce(&mut FrameMut),
+{
+    let mut data = vec![0; (width * height * 4) as usize];
+    draw(&mut FrameMut::new(
+        &mut data,
+        width,
+        height,
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+
+    let frame = Frame::new(&data, width, height, TextureFormat::Rgba8UnormSrgb);
+    frame
+        .rows_rgba()
+        .map(|row| {
+            row.iter()
+                .map(|&pixel| if pixel == WHITE { '#' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+/// A tiny BDF font with a 3x3 `A` and a 1x5 `!` that descends below the baseline.
+const BDF: &str = "\
+STARTFONT 2.1
+FONT -test-tiny
+SIZE 4 75 75
+FONTBOUNDINGBOX 4 5 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 4
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 2
+STARTCHAR A
+ENCODING 65
+DWIDTH 4 0
+BBX 3 3 0 0
+BITMAP
+40
+E0
+A0
+ENDCHAR
+STARTCHAR exclam
+ENCODING 33
+DWIDTH 2 0
+BBX 1 5 0 -1
+BITMAP
+80
+80
+80
+00
+80
+ENDCHAR
+ENDFONT
+";
+
+#[test]
+fn builtin_font() {
+    let font = BitmapFont::default();
+    assert_eq!(font.line_height(), 8);
+    assert!(font.has_glyph('A'));
+    assert!(font.has_glyph('é'));
+    assert!(!font.has_glyph('\u{263a}'));
+
+    let art = ascii(10, 8, |frame| {
+        text::draw_text(frame, &font, -3, 0, "HI", WHITE);
+    });
+    assert_eq!(
+        art,
+        [
+            ".##...####",
+            ".##....##.",
+            ".##....##.",
+            "###....##.",
+            ".##....##.",
+            ".##....##.",
+            ".##...####",
+            "..........",
+        ]
+    );
+}
+
+#[test]
+fn measure_and_wrap() {
+    let font = BitmapFont::default();
+    assert_eq!(font.measure("Hello\nWorld!"), (48, 16));
+    assert_eq!(font.measure(""), (0, 8));
+
+    assert_eq!(
+        font.wrap("The quick brown fox", 80),
+        ["The quick", "brown fox"]
+    );
+    assert_eq!(
+        font.wrap("Supercalifragilistic", 64),
+        ["Supercal", "ifragili", "stic"]
+    );
+    assert_eq!(font.wrap("a  b\n\nc", 24), ["a", "b", "", "c"]);
+    assert_eq!(font.wrap("tiny", 0), ["t", "i", "n", "y"]);
+}
+
+#[test]
+fn wrapped_text_height() {
+    let font = BitmapFont::default();
+    let mut data = vec![0; 32 * 32 * 4];
+    let mut frame = FrameMut::new(&mut data, 32, 32, TextureFormat::Bgra8UnormSrgb);
+
+    let height = text::draw_text_wrapped(&mut frame, &font, 0, 0, 32, "one two six", WHITE);
+    assert_eq!(height, 24);
+}
+
+#[test]
+fn bdf_font() {
+    let font = BitmapFont::from_bdf(BDF.as_bytes()).unwrap();
+    assert_eq!(font.line_height(), 5);
+    assert_eq!(font.measure("A!A"), (10, 5));
+
+    let art = ascii(11, 6, |frame| {
+        text::draw_text(frame, &font, 0, 0, "A!A?A", WHITE);
+    });
+    assert_eq!(
+        art,
+        [
+            "....#......",
+            ".#..#..#...",
+            "###.#.###..",
+            "#.#...#.#..",
+            "....#......",
+            "...........",
+        ]
+    );
+}
+
+#[test]
+fn bdf_errors() {
+    let err = BitmapFont::from_bdf(BDF.replace("BBX 3 3 0 0", "BBX 3 x 0 0").as_bytes());
+    assert!(
+        matches!(err, Err(FontError::Bdf { line: 13, .. })),
+        "{err:?}"
+    );
+
+    let err = BitmapFont::from_bdf(BDF.replace("E0\n", "").as_bytes());
+    assert!(matches!(err, Err(FontError::Bdf { .. })), "{err:?}");
+
+    let err = BitmapFont::from_bdf(BDF.replace("FONTBOUNDINGBOX 4 5 0 -1\n", "").as_bytes());
+    assert!(matches!(err, Err(FontError::Bdf { .. })), "{err:?}");
+}
+
+#[test]
+fn bdf_malformed() {
+    // Huge or overflowing bounding boxes are errors, not allocations or panics
+    for bbx in [
+        "BBX 3 2000000000 0 0",
+        "BBX -3 3 0 0",
+        "BBX 3 3 0 2147483647",
+        "BBX 3 3 -2147483648 0",
+    ] {
+        let err = BitmapFont::from_bdf(BDF.replace("BBX 3 3 0 0", bbx).as_bytes());
+        assert!(
+            matches!(err, Err(FontError::Bdf { line: 13, .. })),
+            "{bbx}: {err:?}"
+        );
+    }
+
+    // Huge advances would overflow when measuring text
+    for dwidth in ["DWIDTH 2000000000 0", "DWIDTH -2000000000 0"] {
+        let err = BitmapFont::from_bdf(BDF.replace("DWIDTH 4 0", dwidth).as_bytes());
+        assert!(
+            matches!(err, Err(FontError::Bdf { line: 12, .. })),
+            "{dwidth}: {err:?}"
+        );
+    }
+
+    let err = BitmapFont::from_bdf(
+        BDF.replace(
+            "FONTBOUNDINGBOX 4 5 0 -1",
+            "FONTBOUNDINGBOX 4 5 0 -2147483648",
+        )
+        .as_bytes(),
+    );
+    assert!(
+        matches!(err, Err(FontError::Bdf { line: 4, .. })),
+        "{err:?}"
+    );
+
+    let err = BitmapFont::from_bdf(
+        BDF.replace("FONT_ASCENT 4", "FONT_ASCENT 2147483647")
+            .as_bytes(),
+    );
+    assert!(matches!(err, Err(FontError::Bdf { .. })), "{err:?}");
+}
+
+/// Glyph bitmaps for a PSF font with two glyphs that are two rows tall: a bar and a checker.
+const PSF_GLYPHS: [u8; 4] = [0xf0, 0x00, 0xa0, 0x50];
+
+#[test]
+fn psf1_font() {
+    // Header with a Unicode table, and 256 glyphs of 2 bytes each
+    let mut data = vec![0x36, 0x04, 0x02, 2];
+    data.extend(PSF_GLYPHS);
+    data.resize(4 + 512, 0);
+
+    // Glyph 0 is `-`, glyph 1 is `x` and `X` along with a sequence that is ignored
+    let mut table = vec![0x2d, 0xffff, 0x78, 0x58, 0xfffe, 0x41, 0x42, 0xffff];
+    table.resize(table.len() + 254, 0xffff);
+    data.extend(table.iter().flat_map(|entry: &u16| entry.to_le_bytes()));
+
+    let font = BitmapFont::from_psf(&data).unwrap();
+    assert_eq!(font.line_height(), 2);
+    assert!(font.has_glyph('x') && font.has_glyph('X'));
+    assert!(!font.has_glyph('A') && !font.has_glyph('\0'));
+
+    // PSF1 glyphs are always 8 pixels wide
+    let art = ascii(16, 2, |frame| {
+        text::draw_text(frame, &font, 0, 0, "-X", WHITE)
+    });
+    assert_eq!(art, ["####....#.#.....", ".........#.#...."]);
+
+    // A truncated table is an error
+    data.truncate(data.len() - 2);
+    assert!(matches!(
+        BitmapFont::from_psf(&data),
+        Err(FontError::Psf(_))
+    ));
+}
+
+#[test]
+fn psf2_font() {
+    let header = [
+        0x864a_b572_u32, // Magic
+        0,               // Version
+        32,              // Header size
+        1,               // Flags: has Unicode table
+        2,               // Glyph count
+        2,               // Bytes per glyph
+        2,               // Height
+        4,               // Width
+    ];
+    let mut data: Vec<u8> = header.iter().flat_map(|word| word.to_le_bytes()).collect();
+    data.extend(PSF_GLYPHS);
+
+    // Glyph 0 is `-`, glyph 1 is `é` along with a sequence that is ignored
+    data.extend(b"-\xff");
+    data.extend("é".as_bytes());
+    data.extend(b"\xfeab\xff");
+
+    let font = BitmapFont::from_psf(&data).unwrap();
+    assert!(font.has_glyph('-') && font.has_glyph('é') && !font.has_glyph('a'));
+
+    let art = ascii(8, 2, |frame| {
+        text::draw_text(frame, &font, 0, 0, "é-", WHITE)
+    });
+    assert_eq!(art, ["#.#.####", ".#.#...."]);
+
+    // Without a Unicode table, glyph indices are code points
+    data[12] = 0;
+    let font = BitmapFont::from_psf(&data).unwrap();
+    assert!(font.has_glyph('\0') && font.has_glyph('\u{1}') && !font.has_glyph('-'));
+}
+
+#[test]
+fn psf_errors() {
+    assert!(matches!(
+        BitmapFont::from_psf(b"nope"),
+        Err(FontError::Psf(_))
+    ));
+    assert!(matches!(
+        BitmapFont::from_psf(&[0x36, 0x04, 0x00, 8, 0, 0]),
+        Err(FontError::Psf(_))
+    ));
+}
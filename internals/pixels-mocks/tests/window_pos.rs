@@ -0,0 +1,104 @@
+use pixels::wgpu::TextureFormat;
+use pixels::{Anchor, Pixels, ScalingMode};
+use pixels_mocks::golden::pattern_buffer;
+
+/// Build an `8x6` pixel buffer with the test pattern, or `None` without a software adapter.
+fn build(surface_width: u32, surface_height: u32) -> Option<Pixels<'static>> {
+    pattern_buffer(
+        8,
+        6,
+        surface_width,
+        surface_height,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+#[test]
+fn integer_scale() {
+    let Some(pixels) = build(32, 24) else {
+        return;
+    };
+
+    assert_eq!(pixels.window_pos_to_pixel((0.0, 0.0)), Ok((0, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((4.0, 4.0)), Ok((1, 1)));
+    assert_eq!(pixels.window_pos_to_pixel((31.5, 23.5)), Ok((7, 5)));
+    assert_eq!(pixels.window_pos_to_pixel((32.0, 24.0)), Err((8, 6)));
+}
+
+#[test]
+fn border() {
+    let Some(pixels) = build(30, 28) else {
+        return;
+    };
+
+    // The buffer is scaled 3x and centered, with a border of 3 pixels left and 5 pixels above
+    assert_eq!(pixels.window_pos_to_pixel((3.0, 5.0)), Ok((0, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((2.5, 5.0)), Err((-1, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((26.9, 22.9)), Ok((7, 5)));
+    assert_eq!(pixels.window_pos_to_pixel((27.1, 23.1)), Err((8, 6)));
+}
+
+#[test]
+fn pixel_aspect_ratio() {
+    let Some(mut pixels) = build(32, 24) else {
+        return;
+    };
+    pixels.set_pixel_aspect_ratio(2.0);
+
+    // Pixels are 4 physical pixels wide and 2 tall, with a border of 6 pixels above
+    assert_eq!(pixels.window_pos_to_pixel((3.9, 6.0)), Ok((0, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((4.0, 8.0)), Ok((1, 1)));
+    assert_eq!(pixels.window_pos_to_pixel((31.9, 17.9)), Ok((7, 5)));
+    assert_eq!(pixels.window_pos_to_pixel((16.0, 5.9)), Err((4, -1)));
+}
+
+#[test]
+fn cropped() {
+    let Some(mut pixels) = build(30, 28) else {
+        return;
+    };
+    pixels.set_scaling_mode(ScalingMode::Fixed(5));
+
+    // The buffer is scaled to `40x30` and centered, cropping 5 pixels left and 1 pixel above
+    assert_eq!(pixels.window_pos_to_pixel((0.0, 0.0)), Ok((1, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((4.9, 3.9)), Ok((1, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((5.1, 4.1)), Ok((2, 1)));
+    assert_eq!(pixels.window_pos_to_pixel((29.9, 27.9)), Ok((6, 5)));
+}
+
+#[test]
+fn anchor_offset() {
+    let Some(mut pixels) = build(30, 28) else {
+        return;
+    };
+    pixels.set_anchor(Anchor::TopLeft);
+    pixels.set_anchor_offset(2, 1);
+
+    // The buffer is scaled 3x with its top-left corner at `(2, 1)`
+    assert_eq!(pixels.window_pos_to_pixel((2.0, 1.0)), Ok((0, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((1.9, 1.0)), Err((-1, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((25.9, 18.9)), Ok((7, 5)));
+    assert_eq!(pixels.window_pos_to_pixel((26.1, 19.1)), Err((8, 6)));
+}
+
+#[test]
+fn viewport() {
+    let Some(mut pixels) = build(40, 30) else {
+        return;
+    };
+    pixels.set_viewport(4, 2, 20, 16);
+    assert_eq!(pixels.viewport(), (4, 2, 20, 16));
+
+    // The buffer is scaled 2x and centered in the viewport, with its top-left corner at `(6, 4)`
+    assert_eq!(pixels.window_pos_to_pixel((6.0, 4.0)), Ok((0, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((5.9, 4.0)), Err((-1, 0)));
+    assert_eq!(pixels.window_pos_to_pixel((21.9, 15.9)), Ok((7, 5)));
+    assert_eq!(pixels.window_pos_to_pixel((22.1, 16.1)), Err((8, 6)));
+
+    // The viewport is clipped to the surface
+    pixels.resize_surface(12, 10).unwrap();
+    assert_eq!(pixels.viewport(), (4, 2, 8, 8));
+
+    pixels.reset_viewport();
+    assert_eq!(pixels.viewport(), (0, 0, 12, 10));
+}
@@ -0,0 +1,23 @@
+// Vertex shader bindings
+
+struct Locals {
+    transform: mat4x4<f32>,
+    input_size: vec4<f32>,
+    clear_color: vec4<f32>,
+}
+@group(0) @binding(2) var<uniform> r_locals: Locals;
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+) -> @builtin(position) vec4<f32> {
+    // Cover the whole render target; the scissor rectangle limits the cleared area
+    return vec4<f32>(position, 0.0, 1.0);
+}
+
+// Fragment shader bindings
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return r_locals.clear_color;
+}
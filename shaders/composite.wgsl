@@ -0,0 +1,44 @@
+// Vertex shader bindings
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // A single triangle that covers the whole render target
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+// Fragment shader bindings
+
+struct Locals {
+    // The position of the layer on the base layer, in pixels
+    offset: vec2<f32>,
+    opacity: f32,
+}
+
+@group(0) @binding(0) var r_layer: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> r_locals: Locals;
+
+// The layer color at a position on the base layer, or transparent outside of the layer
+fn layer_color(position: vec2<f32>) -> vec4<f32> {
+    let pos = vec2<i32>(floor(position - r_locals.offset));
+    let size = vec2<i32>(textureDimensions(r_layer));
+    if any(pos < vec2<i32>(0)) || any(pos >= size) {
+        return vec4<f32>(0.0);
+    }
+
+    let color = textureLoad(r_layer, pos, 0);
+    return vec4<f32>(color.rgb, color.a * r_locals.opacity);
+}
+
+// Normal and additive blending are done by the blend state
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return layer_color(position.xy);
+}
+
+// The blend state multiplies the destination with this color
+@fragment
+fn fs_multiply(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let color = layer_color(position.xy);
+    return vec4<f32>(mix(vec3<f32>(1.0), color.rgb, color.a), 1.0);
+}
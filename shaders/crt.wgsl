@@ -0,0 +1,90 @@
+// Vertex shader bindings
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // A single triangle that covers the whole render target
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+// Fragment shader bindings
+
+struct Locals {
+    // The scaled pixel buffer on the surface: x, y, width, height
+    screen: vec4<f32>,
+    // The pixel buffer width and height, and the surface width and height
+    sizes: vec4<f32>,
+    // The clear color, shown outside of the curved screen
+    border: vec4<f32>,
+    scanlines: f32,
+    mask_strength: f32,
+    curvature: f32,
+    vignette: f32,
+    bloom: f32,
+    // 0: none, 1: aperture grille, 2: shadow mask
+    mask: u32,
+}
+
+@group(0) @binding(0) var r_tex_color: texture_2d<f32>;
+@group(0) @binding(1) var r_tex_sampler: sampler;
+@group(0) @binding(2) var<uniform> r_locals: Locals;
+
+const TAU: f32 = 6.283185307179586;
+
+fn sample(position: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(r_tex_color, r_tex_sampler, position / r_locals.sizes.zw, 0.0).rgb;
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let screen = r_locals.screen;
+    let color = textureSampleLevel(r_tex_color, r_tex_sampler, position.xy / r_locals.sizes.zw, 0.0);
+
+    // Leave everything outside of the scaled pixel buffer untouched
+    let uv = (position.xy - screen.xy) / screen.zw;
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        return color;
+    }
+
+    // Barrel distortion; this must match `CrtRenderer::distort`
+    let centered = uv * 2.0 - 1.0;
+    let curved = centered * (1.0 + r_locals.curvature * dot(centered, centered));
+    if any(abs(curved) > vec2<f32>(1.0)) {
+        return r_locals.border;
+    }
+    let source = screen.xy + (curved * 0.5 + 0.5) * screen.zw;
+
+    // Bloom: bright neighbors bleed into each other
+    var rgb = sample(source);
+    let texel = screen.zw / r_locals.sizes.xy;
+    let blur = (
+        sample(source + vec2<f32>(texel.x, 0.0)) +
+        sample(source - vec2<f32>(texel.x, 0.0)) +
+        sample(source + vec2<f32>(0.0, texel.y)) +
+        sample(source - vec2<f32>(0.0, texel.y))
+    ) * 0.25;
+    rgb = rgb + r_locals.bloom * blur * blur;
+
+    // Scanlines are brightest at the center of each pixel buffer row
+    let row = (curved.y * 0.5 + 0.5) * r_locals.sizes.y;
+    let scanline = 0.5 + 0.5 * cos(TAU * (row - 0.5));
+    rgb = rgb * mix(1.0, scanline, r_locals.scanlines);
+
+    // Phosphor mask, one physical pixel per subpixel
+    if r_locals.mask != 0u {
+        let x = u32(position.x);
+        let y = u32(position.y);
+        var phase = x % 3u;
+        if r_locals.mask == 2u {
+            phase = (x + (y % 2u) * 2u) % 3u;
+        }
+        var mask = vec3<f32>(1.0 - r_locals.mask_strength);
+        mask[phase] = 1.0;
+        rgb = rgb * mask;
+    }
+
+    // Vignette darkens the corners
+    rgb = rgb * (1.0 - r_locals.vignette * dot(curved, curved) * 0.5);
+
+    return vec4<f32>(rgb, color.a);
+}
@@ -0,0 +1,403 @@
+// Vertex shader bindings
+
+struct VertexOutput {
+    @location(0) tex_coord: vec2<f32>,
+    @builtin(position) position: vec4<f32>,
+}
+
+struct Locals {
+    transform: mat4x4<f32>,
+    input_size: vec4<f32>
+}
+@group(0) @binding(2) var<uniform> r_locals: Locals;
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.tex_coord = fma(position, vec2<f32>(0.5, -0.5), vec2<f32>(0.5, 0.5));
+    out.position = r_locals.transform * vec4<f32>(position, 0.0, 1.0);
+    return out;
+}
+
+// Fragment shader bindings
+
+@group(0) @binding(0) var r_tex_color: texture_2d<f32>;
+@group(0) @binding(1) var r_tex_sampler: sampler;
+
+const PI: f32 = 3.141592653589793;
+
+// Load a texel, clamping the coordinates to the edges of the texture
+fn texel(coord: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(r_tex_color));
+    return textureLoad(r_tex_color, clamp(coord, vec2<i32>(0), size - 1), 0);
+}
+
+fn same(a: vec4<f32>, b: vec4<f32>) -> bool {
+    return all(a == b);
+}
+
+// Sharp bilinear: nearest neighbor at the integer scale, then bilinear for the remainder.
+@fragment
+fn fs_sharp_bilinear(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+    let size = r_locals.input_size.xy;
+    let position = tex_coord * size;
+
+    // Output pixels per texel
+    let scale = max(floor(1.0 / fwidth(position) + 1e-3), vec2<f32>(1.0));
+    let region = 0.5 - 0.5 / scale;
+    let center = fract(position) - 0.5;
+    let offset = (center - clamp(center, -region, region)) * scale + 0.5;
+
+    return textureSample(r_tex_color, r_tex_sampler, (floor(position) + offset) / size);
+}
+
+// Scale2x, also known as EPX.
+@fragment
+fn fs_scale2x(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+    let position = tex_coord * r_locals.input_size.xy;
+    let p = vec2<i32>(floor(position));
+    let sub = fract(position);
+
+    let b = texel(p + vec2<i32>(0, -1));
+    let d = texel(p + vec2<i32>(-1, 0));
+    let e = texel(p);
+    let f = texel(p + vec2<i32>(1, 0));
+    let h = texel(p + vec2<i32>(0, 1));
+
+    // Mirror the neighbors so the output pixel is in the top left quadrant
+    var left = d;
+    var right = f;
+    if sub.x >= 0.5 {
+        left = f;
+        right = d;
+    }
+    var up = b;
+    var down = h;
+    if sub.y >= 0.5 {
+        up = h;
+        down = b;
+    }
+
+    if same(left, up) && !same(up, right) && !same(left, down) {
+        return left;
+    }
+    return e;
+}
+
+// Scale3x, the 3x extension of Scale2x.
+@fragment
+fn fs_scale3x(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+    let position = tex_coord * r_locals.input_size.xy;
+    let p = vec2<i32>(floor(position));
+    let cell = min(vec2<i32>(fract(position) * 3.0), vec2<i32>(2));
+
+    var a = texel(p + vec2<i32>(-1, -1));
+    var b = texel(p + vec2<i32>(0, -1));
+    var c = texel(p + vec2<i32>(1, -1));
+    var d = texel(p + vec2<i32>(-1, 0));
+    let e = texel(p);
+    var f = texel(p + vec2<i32>(1, 0));
+    var g = texel(p + vec2<i32>(-1, 1));
+    var h = texel(p + vec2<i32>(0, 1));
+    var i = texel(p + vec2<i32>(1, 1));
+
+    // Mirror the neighbors so the output pixel is in the top left corner, top edge, left edge,
+    // or center of the 3x3 block
+    var x = cell.x;
+    var y = cell.y;
+    if x == 2 {
+        x = 0;
+        let t0 = a;
+        a = c;
+        c = t0;
+        let t1 = d;
+        d = f;
+        f = t1;
+        let t2 = g;
+        g = i;
+        i = t2;
+    }
+    if y == 2 {
+        y = 0;
+        let t0 = a;
+        a = g;
+        g = t0;
+        let t1 = b;
+        b = h;
+        h = t1;
+        let t2 = c;
+        c = i;
+        i = t2;
+    }
+
+    let corner = same(d, b) && !same(b, f) && !same(d, h);
+    if x == 0 && y == 0 {
+        if corner {
+            return d;
+        }
+    } else if x == 1 && y == 0 {
+        let right = same(b, f) && !same(b, d) && !same(f, h);
+        if (corner && !same(e, c)) || (right && !same(e, a)) {
+            return b;
+        }
+    } else if x == 0 && y == 1 {
+        let bottom = same(d, h) && !same(d, b) && !same(h, f);
+        if (corner && !same(e, g)) || (bottom && !same(e, a)) {
+            return d;
+        }
+    }
+    return e;
+}
+
+// xBR level 2, with smoothed edges.
+const XBR_WEIGHTS: vec3<f32> = vec3<f32>(14.352, 28.176, 5.472);
+const XBR_THRESHOLD: vec4<f32> = vec4<f32>(15.0);
+
+fn luma4(a: vec4<f32>, b: vec4<f32>, c: vec4<f32>, d: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(
+        dot(a.rgb, XBR_WEIGHTS),
+        dot(b.rgb, XBR_WEIGHTS),
+        dot(c.rgb, XBR_WEIGHTS),
+        dot(d.rgb, XBR_WEIGHTS),
+    );
+}
+
+fn xbr_eq(a: vec4<f32>, b: vec4<f32>) -> vec4<bool> {
+    return abs(a - b) < XBR_THRESHOLD;
+}
+
+fn weighted_distance(
+    a: vec4<f32>,
+    b: vec4<f32>,
+    c: vec4<f32>,
+    d: vec4<f32>,
+    e: vec4<f32>,
+    f: vec4<f32>,
+    g: vec4<f32>,
+    h: vec4<f32>,
+) -> vec4<f32> {
+    return abs(a - b) + abs(a - c) + abs(d - e) + abs(d - f) + 4.0 * abs(g - h);
+}
+
+fn color_distance(a: vec3<f32>, b: vec3<f32>) -> f32 {
+    let d = abs(a - b);
+    return d.r + d.g + d.b;
+}
+
+@fragment
+fn fs_xbr(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+    let position = tex_coord * r_locals.input_size.xy;
+    let p = vec2<i32>(floor(position));
+    let fp = fract(position);
+
+    //    A1 B1 C1
+    // A0 A  B  C  C4
+    // D0 D  E  F  F4
+    // G0 G  H  I  I4
+    //    G5 H5 I5
+    let A1 = texel(p + vec2<i32>(-1, -2));
+    let B1 = texel(p + vec2<i32>(0, -2));
+    let C1 = texel(p + vec2<i32>(1, -2));
+    let A0 = texel(p + vec2<i32>(-2, -1));
+    let A = texel(p + vec2<i32>(-1, -1));
+    let B = texel(p + vec2<i32>(0, -1));
+    let C = texel(p + vec2<i32>(1, -1));
+    let C4 = texel(p + vec2<i32>(2, -1));
+    let D0 = texel(p + vec2<i32>(-2, 0));
+    let D = texel(p + vec2<i32>(-1, 0));
+    let E = texel(p);
+    let F = texel(p + vec2<i32>(1, 0));
+    let F4 = texel(p + vec2<i32>(2, 0));
+    let G0 = texel(p + vec2<i32>(-2, 1));
+    let G = texel(p + vec2<i32>(-1, 1));
+    let H = texel(p + vec2<i32>(0, 1));
+    let I = texel(p + vec2<i32>(1, 1));
+    let I4 = texel(p + vec2<i32>(2, 1));
+    let G5 = texel(p + vec2<i32>(-1, 2));
+    let H5 = texel(p + vec2<i32>(0, 2));
+    let I5 = texel(p + vec2<i32>(1, 2));
+
+    // Each component handles one corner of the texel: bottom right, top right, top left, and
+    // bottom left
+    let b = luma4(B, D, H, F);
+    let c = luma4(C, A, G, I);
+    let e = luma4(E, E, E, E);
+    let d = b.yzwx;
+    let f = b.wxyz;
+    let g = c.zwxy;
+    let h = b.zwxy;
+    let i = c.wxyz;
+    let i4 = luma4(I4, C1, A0, G5);
+    let i5 = luma4(I5, C4, A1, G0);
+    let h5 = luma4(H5, F4, B1, D0);
+    let f4 = h5.yzwx;
+
+    let Ao = vec4<f32>(1.0, -1.0, -1.0, 1.0);
+    let Bo = vec4<f32>(1.0, 1.0, -1.0, -1.0);
+    let Co = vec4<f32>(1.5, 0.5, -0.5, 0.5);
+    let Ax = vec4<f32>(1.0, -1.0, -1.0, 1.0);
+    let Bx = vec4<f32>(0.5, 2.0, -0.5, -2.0);
+    let Cx = vec4<f32>(1.0, 1.0, -0.5, 0.0);
+    let Ay = vec4<f32>(1.0, -1.0, -1.0, 1.0);
+    let By = vec4<f32>(2.0, 0.5, -2.0, -0.5);
+    let Cy = vec4<f32>(2.0, 0.0, -1.0, 0.5);
+
+    let fx = Ao * fp.y + Bo * fp.x;
+    let fx_left = Ax * fp.y + Bx * fp.x;
+    let fx_up = Ay * fp.y + By * fp.x;
+
+    // Smooth the edges over about one output pixel
+    let delta = vec4<f32>(max(max(fwidth(fp).x, fwidth(fp).y), 1e-3));
+
+    let restriction_lv1 = (e != f) & (e != h) & (
+        (!xbr_eq(f, b) & !xbr_eq(h, d))
+        | (xbr_eq(e, i) & !xbr_eq(f, i4) & !xbr_eq(h, i5))
+        | xbr_eq(e, g)
+        | xbr_eq(e, c)
+    );
+    let restriction_lv2_left = (e != g) & (d != g);
+    let restriction_lv2_up = (e != c) & (b != c);
+
+    let edr = (weighted_distance(e, c, g, i, h5, f4, h, f)
+        < weighted_distance(h, d, i5, f, i4, b, e, i)) & restriction_lv1;
+    let edr_left = (2.0 * abs(f - g) <= abs(h - c)) & restriction_lv2_left & edr;
+    let edr_up = (abs(f - g) >= 2.0 * abs(h - c)) & restriction_lv2_up & edr;
+
+    let zero = vec4<f32>(0.0);
+    let fx45 = select(zero, smoothstep(Co - delta, Co + delta, fx), edr);
+    let fx30 = select(zero, smoothstep(Cx - delta, Cx + delta, fx_left), edr_left);
+    let fx60 = select(zero, smoothstep(Cy - delta, Cy + delta, fx_up), edr_up);
+
+    let px = abs(e - f) <= abs(e - h);
+    let maximum = max(max(fx30, fx60), fx45);
+
+    var res1 = E.rgb;
+    res1 = mix(res1, select(H.rgb, F.rgb, px.x), maximum.x);
+    res1 = mix(res1, select(B.rgb, D.rgb, px.z), maximum.z);
+    var res2 = E.rgb;
+    res2 = mix(res2, select(F.rgb, B.rgb, px.y), maximum.y);
+    res2 = mix(res2, select(D.rgb, H.rgb, px.w), maximum.w);
+
+    let res = mix(res1, res2, step(color_distance(E.rgb, res1), color_distance(E.rgb, res2)));
+    return vec4<f32>(res, E.a);
+}
+
+// An hq2x-like filter: each quadrant of a texel blends with the neighbors it borders, depending
+// on how similar they are in YUV space.
+fn similar(a: vec4<f32>, b: vec4<f32>) -> bool {
+    let yuv = mat3x3<f32>(
+        vec3<f32>(0.299, -0.169, 0.5),
+        vec3<f32>(0.587, -0.331, -0.419),
+        vec3<f32>(0.114, 0.5, -0.081),
+    );
+    let diff = abs(yuv * (a.rgb - b.rgb));
+    return all(diff <= vec3<f32>(48.0, 7.0, 6.0) / 255.0) && abs(a.a - b.a) <= 48.0 / 255.0;
+}
+
+@fragment
+fn fs_hqx(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+    let position = tex_coord * r_locals.input_size.xy;
+    let p = vec2<i32>(floor(position));
+    let sub = fract(position);
+
+    // The neighbors bordering the quadrant of the output pixel
+    let direction = vec2<i32>(select(-1, 1, sub.x >= 0.5), select(-1, 1, sub.y >= 0.5));
+    let e = texel(p);
+    let horizontal = texel(p + vec2<i32>(direction.x, 0));
+    let vertical = texel(p + vec2<i32>(0, direction.y));
+    let corner = texel(p + direction);
+
+    let same_h = similar(e, horizontal);
+    let same_v = similar(e, vertical);
+    if similar(horizontal, vertical) && !same_h {
+        // A diagonal edge crosses this quadrant
+        if similar(e, corner) {
+            return (6.0 * e + horizontal + vertical) / 8.0;
+        }
+        return (2.0 * e + horizontal + vertical) / 4.0;
+    }
+    if same_h && same_v {
+        if !similar(e, corner) {
+            return (7.0 * e + corner) / 8.0;
+        }
+        return e;
+    }
+    if !same_h && !same_v {
+        return (2.0 * e + horizontal + vertical) / 4.0;
+    }
+    if !same_h {
+        return (3.0 * e + horizontal) / 4.0;
+    }
+    return (3.0 * e + vertical) / 4.0;
+}
+
+// Bicubic interpolation with the Catmull-Rom spline.
+fn catmull_rom(t: f32) -> vec4<f32> {
+    return vec4<f32>(
+        t * (-0.5 + t * (1.0 - 0.5 * t)),
+        1.0 + t * t * (-2.5 + 1.5 * t),
+        t * (0.5 + t * (2.0 - 1.5 * t)),
+        t * t * (-0.5 + 0.5 * t),
+    );
+}
+
+@fragment
+fn fs_bicubic(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+    let position = tex_coord * r_locals.input_size.xy - 0.5;
+    let base = floor(position);
+    let t = position - base;
+    let wx = catmull_rom(t.x);
+    let wy = catmull_rom(t.y);
+    let p = vec2<i32>(base);
+
+    var color = vec4<f32>(0.0);
+    for (var y = 0; y < 4; y++) {
+        var row = vec4<f32>(0.0);
+        for (var x = 0; x < 4; x++) {
+            row += texel(p + vec2<i32>(x - 1, y - 1)) * wx[x];
+        }
+        color += row * wy[y];
+    }
+    return clamp(color, vec4<f32>(0.0), vec4<f32>(1.0));
+}
+
+// Lanczos interpolation with three lobes.
+fn lanczos3(x: f32) -> f32 {
+    if abs(x) < 1e-5 {
+        return 1.0;
+    }
+    if abs(x) >= 3.0 {
+        return 0.0;
+    }
+    let px = PI * x;
+    return 3.0 * sin(px) * sin(px / 3.0) / (px * px);
+}
+
+@fragment
+fn fs_lanczos(@location(0) tex_coord: vec2<f32>) -> @location(0) vec4<f32> {
+    let position = tex_coord * r_locals.input_size.xy - 0.5;
+    let base = floor(position);
+    let t = position - base;
+    let p = vec2<i32>(base);
+
+    var wx: array<f32, 6>;
+    var wy: array<f32, 6>;
+    var sum = vec2<f32>(0.0);
+    for (var k = 0; k < 6; k++) {
+        wx[k] = lanczos3(t.x - f32(k - 2));
+        wy[k] = lanczos3(t.y - f32(k - 2));
+        sum += vec2<f32>(wx[k], wy[k]);
+    }
+
+    var color = vec4<f32>(0.0);
+    for (var y = 0; y < 6; y++) {
+        var row = vec4<f32>(0.0);
+        for (var x = 0; x < 6; x++) {
+            row += texel(p + vec2<i32>(x - 2, y - 2)) * wx[x];
+        }
+        color += row * wy[y];
+    }
+    return clamp(color / (sum.x * sum.y), vec4<f32>(0.0), vec4<f32>(1.0));
+}
@@ -0,0 +1,19 @@
+// Vertex shader bindings
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // A single triangle that covers the whole render target
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+// Fragment shader bindings
+
+@group(0) @binding(0) var r_indices: texture_2d<u32>;
+@group(0) @binding(1) var r_palette: texture_2d<f32>;
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let index = textureLoad(r_indices, vec2<i32>(position.xy), 0).r;
+    return textureLoad(r_palette, vec2<i32>(i32(index), 0), 0);
+}
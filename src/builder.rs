@@ -1,16 +1,31 @@
-use crate::renderers::{ScalingMatrix, ScalingRenderer};
-use crate::{Error, Pixels, PixelsContext, ScalingMode, SurfaceSize, SurfaceTexture, TextureError};
+use crate::dirty::DirtyRegions;
+use crate::layers::LayerStack;
+use crate::passes::PassChain;
+use crate::renderers::{
+    PaletteRenderer, PipelineCache, ScalingMatrix, ScalingOptions, ScalingRenderer,
+};
+use crate::{
+    Anchor, Error, Headless, Pixels, PixelsContext, PixelsDevice, RenderTarget, ScalingFilter,
+    ScalingMode, SurfaceSize, SurfaceTexture, TextureError,
+};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
 
 /// A builder to help create customized pixel buffers.
 pub struct PixelsBuilder<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> {
     request_adapter_options: Option<wgpu::RequestAdapterOptions<'req, 'win>>,
     device_descriptor: Option<wgpu::DeviceDescriptor<'dev>>,
     backend: wgpu::Backends,
+    pixels_device: Option<PixelsDevice>,
     width: u32,
     height: u32,
-    _pixel_aspect_ratio: f64,
+    pixel_aspect_ratio: f64,
+    scaling_filter: ScalingFilter,
+    anchor: Anchor,
+    anchor_offset: (i32, i32),
     present_mode: wgpu::PresentMode,
-    surface_texture: SurfaceTexture<W>,
+    window: Option<W>,
+    surface_size: SurfaceSize,
     texture_format: wgpu::TextureFormat,
     render_texture_format: Option<wgpu::TextureFormat>,
     surface_texture_format: Option<wgpu::TextureFormat>,
@@ -44,6 +59,12 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
     ///
     /// Panics when `width` or `height` are 0.
     pub fn new(width: u32, height: u32, surface_texture: SurfaceTexture<W>) -> Self {
+        let SurfaceTexture { window, size } = surface_texture;
+
+        Self::new_impl(width, height, Some(window), size)
+    }
+
+    fn new_impl(width: u32, height: u32, window: Option<W>, surface_size: SurfaceSize) -> Self {
         assert!(width > 0);
         assert!(height > 0);
 
@@ -51,11 +72,16 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
             request_adapter_options: None,
             device_descriptor: None,
             backend: wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all),
+            pixels_device: None,
             width,
             height,
-            _pixel_aspect_ratio: 1.0,
+            pixel_aspect_ratio: 1.0,
+            scaling_filter: ScalingFilter::Nearest,
+            anchor: Anchor::Center,
+            anchor_offset: (0, 0),
             present_mode: wgpu::PresentMode::AutoVsync,
-            surface_texture,
+            window,
+            surface_size,
             texture_format: wgpu::TextureFormat::Rgba8UnormSrgb,
             render_texture_format: None,
             surface_texture_format: None,
@@ -87,25 +113,175 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
         self
     }
 
+    /// Use an existing `wgpu` instance, adapter, device, and queue instead of creating new ones.
+    ///
+    /// This allows embedding a pixel buffer in an application that already has a `wgpu`
+    /// renderer, or sharing one device between multiple pixel buffers. The surface is still
+    /// created from the `instance`, and must be supported by the `adapter`. The options for
+    /// requesting an adapter and a device, and the backend, are ignored.
+    ///
+    /// The device must support textures of the pixel buffer size, and the features and limits of
+    /// any texture formats that are used.
+    ///
+    /// The device belongs to the application, so no device lost callback is set on it. Report
+    /// device loss with [`Pixels::notify_device_lost`].
+    ///
+    /// Pixel buffers built this way do not share pipelines with other pixel buffers. Use
+    /// [`PixelsBuilder::with_pixels_device`] with [`Pixels::pixels_device`] to share the device
+    /// with more pixel buffers.
+    ///
+    /// # Examples
+    ///
+    /// Draw on the device of an existing `wgpu` application, and share it with a second window:
+    ///
+    /// ```no_run
+    /// use pixels::{wgpu, PixelsBuilder, SurfaceTexture};
+    /// use pixels_mocks::Window;
+    /// use std::sync::Arc;
+    ///
+    /// fn create_windows(
+    ///     instance: Arc<wgpu::Instance>,
+    ///     adapter: Arc<wgpu::Adapter>,
+    ///     device: Arc<wgpu::Device>,
+    ///     queue: Arc<wgpu::Queue>,
+    ///     window: &Window,
+    ///     other_window: &Window,
+    /// ) -> Result<(), pixels::Error> {
+    ///     let pixels = PixelsBuilder::new(320, 240, SurfaceTexture::new(320, 240, window))
+    ///         .with_device(instance, adapter, device, queue)
+    ///         .build()?;
+    ///
+    ///     let other = PixelsBuilder::new(160, 120, SurfaceTexture::new(320, 240, other_window))
+    ///         .with_pixels_device(&pixels.pixels_device())
+    ///         .build()?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_device(
+        mut self,
+        instance: Arc<wgpu::Instance>,
+        adapter: Arc<wgpu::Adapter>,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+    ) -> Self {
+        self.pixels_device = Some(PixelsDevice::from_wgpu(instance, adapter, device, queue));
+        self
+    }
+
+    /// Create the pixel buffer on a [`PixelsDevice`], sharing the device, shader modules, and
+    /// pipelines with every other pixel buffer created on it.
+    ///
+    /// The surface is created from the instance of the `PixelsDevice`, and must be supported by
+    /// its adapter. The options for requesting an adapter and a device, and the backend, are
+    /// ignored.
+    ///
+    /// # Examples
+    ///
+    /// Share the device of one window with a second window:
+    ///
+    /// ```no_run
+    /// # use pixels::{Pixels, PixelsBuilder, SurfaceTexture};
+    /// # let window = pixels_mocks::Window;
+    /// # let other_window = pixels_mocks::Window;
+    /// let pixels = Pixels::new(320, 240, SurfaceTexture::new(320, 240, &window))?;
+    ///
+    /// let other = PixelsBuilder::new(160, 120, SurfaceTexture::new(320, 240, &other_window))
+    ///     .with_pixels_device(&pixels.pixels_device())
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn with_pixels_device(mut self, device: &PixelsDevice) -> Self {
+        self.pixels_device = Some(device.clone());
+        self
+    }
+
     /// Set the pixel aspect ratio to simulate non-square pixels.
     ///
-    /// This setting enables a render pass that horizontally scales the pixel buffer by the given
-    /// factor.
+    /// The scaled pixel buffer is stretched horizontally by the given factor, which is the width
+    /// of a single pixel relative to its height. With [`ScalingMode::PixelPerfect`], the vertical
+    /// scale remains an integer multiple of the buffer height.
     ///
-    /// E.g. set this to `8.0 / 7.0` for an 8:7 pixel aspect ratio.
+    /// E.g. set this to `8.0 / 7.0` for an 8:7 pixel aspect ratio, or `5.0 / 6.0` to show a
+    /// `320x200` buffer with a 4:3 display aspect ratio. The default is `1.0` (square pixels).
     ///
-    /// # Panics
+    /// The pixel aspect ratio can be changed later with [`Pixels::set_pixel_aspect_ratio`].
     ///
-    /// The aspect ratio must be > 0.
+    /// ```no_run
+    /// # use pixels::PixelsBuilder;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 960, &window);
+    /// let mut pixels = PixelsBuilder::new(256, 240, surface_texture)
+    ///     .pixel_aspect_ratio(8.0 / 7.0)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
     ///
-    /// # Warning
+    /// # Panics
     ///
-    /// This documentation is hidden because support for pixel aspect ratio is incomplete.
-    #[doc(hidden)]
+    /// The aspect ratio must be > 0.
     pub fn pixel_aspect_ratio(mut self, pixel_aspect_ratio: f64) -> Self {
         assert!(pixel_aspect_ratio > 0.0);
 
-        self._pixel_aspect_ratio = pixel_aspect_ratio;
+        self.pixel_aspect_ratio = pixel_aspect_ratio;
+        self
+    }
+
+    /// Set the scaling filter.
+    ///
+    /// The filter controls how the pixel buffer is sampled when it is scaled to the surface. The
+    /// default is [`ScalingFilter::Nearest`]. See [`ScalingFilter`] for the available filters.
+    ///
+    /// The filter can be changed later with [`Pixels::set_scaling_filter`].
+    ///
+    /// ```no_run
+    /// use pixels::ScalingFilter;
+    ///
+    /// # use pixels::PixelsBuilder;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1280, 960, &window);
+    /// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .scaling_filter(ScalingFilter::SharpBilinear)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn scaling_filter(mut self, filter: ScalingFilter) -> Self {
+        self.scaling_filter = filter;
+        self
+    }
+
+    /// Set the anchor for the scaled pixel buffer.
+    ///
+    /// The anchor controls where the pixel buffer is placed on the surface after scaling. The
+    /// default is [`Anchor::Center`]. Anchoring to an edge or corner leaves the remaining space on
+    /// the surface free for other content, like GUI side panels.
+    ///
+    /// The anchor affects the scaling transform, the clipping rectangle, and
+    /// [`Pixels::window_pos_to_pixel`]. It can be changed later with [`Pixels::set_anchor`].
+    ///
+    /// ```no_run
+    /// use pixels::Anchor;
+    ///
+    /// # use pixels::PixelsBuilder;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1280, 720, &window);
+    /// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .anchor(Anchor::TopLeft)
+    ///     .anchor_offset(8, 8)
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Set an offset in physical pixels for the scaled pixel buffer, relative to its anchor.
+    ///
+    /// Positive values move the buffer right and down, regardless of the anchor. The default is
+    /// `(0, 0)`. It can be changed later with [`Pixels::set_anchor_offset`].
+    pub fn anchor_offset(mut self, x: i32, y: i32) -> Self {
+        self.anchor_offset = (x, y);
         self
     }
 
@@ -147,6 +323,9 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
     ///
     /// This is the pixel format of the texture that most applications will interact with directly.
     /// The format influences the structure of byte data that is returned by [`Pixels::frame`].
+    ///
+    /// Use `R8Uint` for an indexed color pixel buffer, with one byte per pixel indexing into a
+    /// palette of 256 colors. See [`Pixels::set_palette`].
     pub fn texture_format(mut self, texture_format: wgpu::TextureFormat) -> Self {
         self.texture_format = texture_format;
         self
@@ -171,6 +350,7 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
     ///
     /// The default value is chosen automatically by the surface (if it can) with a fallback to
     /// `Bgra8UnormSrgb` (which is 4 unsigned bytes in `BGRA` order using the sRGB color space).
+    /// Headless pixel buffers have no surface to choose a format, and default to `Rgba8UnormSrgb`.
     /// Setting this format correctly depends on the hardware/platform the pixel buffer is rendered
     /// to. The chosen format can be retrieved later with [`Pixels::surface_texture_format`].
     ///
@@ -237,57 +417,111 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
         self
     }
 
-    /// Create a pixel buffer from the options builder.
-    ///
-    /// This is the private implementation shared by [`PixelsBuilder::build`] and
-    /// [`PixelsBuilder::build_async`].
+    /// Create the surface, and request an adapter and a device that are compatible with it.
     ///
     /// # Errors
     ///
-    /// Returns an error when a [`wgpu::Adapter`] cannot be found.
-    async fn build_impl(self) -> Result<Pixels<'win>, Error> {
+    /// Returns an error when a [`wgpu::Adapter`] or [`wgpu::Device`] cannot be found.
+    pub(crate) async fn request_device(
+        &mut self,
+    ) -> Result<(Option<wgpu::Surface<'win>>, PixelsDevice), Error> {
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: self.backend,
             ..Default::default()
         });
 
-        // TODO: Use `options.pixel_aspect_ratio` to stretch the scaled texture
-        let surface = instance.create_surface(self.surface_texture.window)?;
-        let compatible_surface = Some(&surface);
-        let request_adapter_options = &self.request_adapter_options;
+        let surface = match self.window.take() {
+            Some(window) => Some(instance.create_surface(window)?),
+            None => None,
+        };
+        let compatible_surface = surface.as_ref();
+        let request_adapter_options = self.request_adapter_options.as_ref().map_or_else(
+            || wgpu::RequestAdapterOptions {
+                compatible_surface,
+                force_fallback_adapter: false,
+                power_preference: wgpu::util::power_preference_from_env().unwrap_or_default(),
+            },
+            |rao| wgpu::RequestAdapterOptions {
+                compatible_surface: rao.compatible_surface.or(compatible_surface),
+                force_fallback_adapter: rao.force_fallback_adapter,
+                power_preference: rao.power_preference,
+            },
+        );
         let adapter = match wgpu::util::initialize_adapter_from_env(&instance, compatible_surface) {
             Some(adapter) => Some(adapter),
-            None => {
-                instance
-                    .request_adapter(&request_adapter_options.as_ref().map_or_else(
-                        || wgpu::RequestAdapterOptions {
-                            compatible_surface,
-                            force_fallback_adapter: false,
-                            power_preference:
-                                wgpu::util::power_preference_from_env().unwrap_or_default(),
-                        },
-                        |rao| wgpu::RequestAdapterOptions {
-                            compatible_surface: rao.compatible_surface.or(compatible_surface),
-                            force_fallback_adapter: rao.force_fallback_adapter,
-                            power_preference: rao.power_preference,
-                        },
-                    ))
-                    .await
-            }
+            None => match instance.request_adapter(&request_adapter_options).await {
+                // Headless pixel buffers fall back to a software adapter when no GPU is available.
+                None if surface.is_none() && !request_adapter_options.force_fallback_adapter => {
+                    instance
+                        .request_adapter(&wgpu::RequestAdapterOptions {
+                            force_fallback_adapter: true,
+                            ..request_adapter_options
+                        })
+                        .await
+                }
+                adapter => adapter,
+            },
         };
 
         let adapter = adapter.ok_or(Error::AdapterNotFound)?;
 
-        let device_descriptor = self
-            .device_descriptor
-            .unwrap_or_else(|| wgpu::DeviceDescriptor {
-                required_limits: adapter.limits(),
-                ..wgpu::DeviceDescriptor::default()
-            });
+        let device_descriptor =
+            self.device_descriptor
+                .take()
+                .unwrap_or_else(|| wgpu::DeviceDescriptor {
+                    required_limits: adapter.limits(),
+                    ..wgpu::DeviceDescriptor::default()
+                });
 
         let (device, queue) = adapter.request_device(&device_descriptor, None).await?;
 
-        let surface_capabilities = surface.get_capabilities(&adapter);
+        let pixels_device = PixelsDevice::from_requested(instance, adapter, device, queue);
+
+        Ok((surface, pixels_device))
+    }
+
+    /// Create a pixel buffer from the options builder.
+    ///
+    /// This is the private implementation shared by [`PixelsBuilder::build`] and
+    /// [`PixelsBuilder::build_async`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when a [`wgpu::Adapter`] or [`wgpu::Device`] cannot be found, or when the
+    /// adapter given to [`PixelsBuilder::with_device`] or [`PixelsBuilder::with_pixels_device`]
+    /// does not support the surface.
+    async fn build_impl(mut self) -> Result<Pixels<'win>, Error> {
+        let (surface, pixels_device) = match self.pixels_device.take() {
+            Some(pixels_device) => {
+                let surface = match self.window.take() {
+                    Some(window) => Some(pixels_device.instance.create_surface(window)?),
+                    None => None,
+                };
+                if let Some(surface) = &surface {
+                    if !pixels_device.adapter.is_surface_supported(surface) {
+                        return Err(Error::SurfaceNotSupported);
+                    }
+                }
+
+                (surface, pixels_device)
+            }
+            None => self.request_device().await?,
+        };
+        let PixelsDevice {
+            device_lost,
+            instance,
+            adapter,
+            device,
+            queue,
+            pipeline_cache,
+            features,
+            limits,
+        } = pixels_device;
+
+        let surface_capabilities = surface
+            .as_ref()
+            .map(|surface| surface.get_capabilities(&adapter))
+            .unwrap_or_default();
         let present_mode = if surface_capabilities
             .present_modes
             .contains(&self.present_mode)
@@ -297,6 +531,9 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
             wgpu::PresentMode::AutoVsync
         };
         let surface_texture_format = self.surface_texture_format.unwrap_or_else(|| {
+            if surface.is_none() {
+                return wgpu::TextureFormat::Rgba8UnormSrgb;
+            }
             *surface_capabilities
                 .formats
                 .iter()
@@ -306,25 +543,40 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
         let render_texture_format = self.render_texture_format.unwrap_or(surface_texture_format);
 
         // Create the backing texture
-        let surface_size = self.surface_texture.size;
+        let surface_size = self.surface_size;
         let clear_color = self.clear_color;
         let blend_state = self.blend_state;
-        let scaling_mode = ScalingMode::PixelPerfect;
-        let (scaling_matrix_inverse, texture_extent, texture, scaling_renderer, pixels_buffer_size) =
-            create_backing_texture(
-                &device,
-                // Backing texture values
-                self.width,
-                self.height,
-                self.texture_format,
-                // Render texture values
-                &surface_size,
-                render_texture_format,
-                // Clear color and blending values
-                clear_color,
-                blend_state,
-                scaling_mode,
-            )?;
+        let scaling_options = ScalingOptions {
+            scaling_mode: ScalingMode::PixelPerfect,
+            filter: self.scaling_filter,
+            pixel_aspect_ratio: self.pixel_aspect_ratio as f32,
+            anchor: self.anchor,
+            anchor_offset: self.anchor_offset,
+            viewport: None,
+        };
+        let BackingTexture {
+            scaling_matrix_inverse,
+            texture_extent,
+            texture,
+            palette_renderer,
+            scaling_renderer,
+            pixels_buffer_size,
+        } = create_backing_texture(
+            &device,
+            &queue,
+            &pipeline_cache,
+            // Backing texture values
+            self.width,
+            self.height,
+            self.texture_format,
+            // Render texture values
+            &surface_size,
+            render_texture_format,
+            // Clear color and blending values
+            clear_color,
+            blend_state,
+            scaling_options,
+        )?;
 
         // Create the pixel buffer
         let mut pixels = Vec::with_capacity(pixels_buffer_size);
@@ -332,30 +584,56 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
 
         let alpha_mode = surface_capabilities.alpha_modes[0];
 
+        // Headless pixel buffers render to an internal texture in place of a surface
+        let target = match surface {
+            Some(surface) => RenderTarget::Surface(surface),
+            None => RenderTarget::Headless(create_render_target(
+                &device,
+                &surface_size,
+                surface_texture_format,
+            )),
+        };
+
         // Instantiate the Pixels struct
         let context = PixelsContext {
+            device_lost,
+            features,
+            limits,
+            instance,
+            adapter,
             device,
             queue,
-            surface,
+            pipeline_cache,
+            target,
             texture,
+            palette_renderer,
             texture_extent,
             texture_format: self.texture_format,
             texture_format_size: texture_format_size(self.texture_format),
             scaling_renderer,
             surface_capabilities,
+            layers: LayerStack::default(),
+            passes: PassChain::new(
+                render_texture_format,
+                surface_size.width,
+                surface_size.height,
+            ),
         };
 
         let pixels = Pixels {
             context,
-            adapter,
             surface_size,
             present_mode,
             render_texture_format,
             surface_texture_format,
             blend_state,
             pixels,
+            dirty: Mutex::new(DirtyRegions::new(self.width, self.height)),
+            redraw: AtomicBool::new(true),
             scaling_matrix_inverse,
             alpha_mode,
+            #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+            recorder: Mutex::new(None),
         };
         pixels.reconfigure_surface();
 
@@ -369,7 +647,9 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
     ///
     /// # Errors
     ///
-    /// Returns an error when a [`wgpu::Adapter`] or [`wgpu::Device`] cannot be found.
+    /// Returns an error when a [`wgpu::Adapter`] or [`wgpu::Device`] cannot be found, or when the
+    /// adapter given to [`PixelsBuilder::with_device`] or [`PixelsBuilder::with_pixels_device`]
+    /// does not support the surface.
     #[cfg(not(target_arch = "wasm32"))]
     pub fn build(self) -> Result<Pixels<'win>, Error> {
         pollster::block_on(self.build_impl())
@@ -396,12 +676,51 @@ impl<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win> PixelsBuilder<'req, 'dev, '
     ///
     /// # Errors
     ///
-    /// Returns an error when a [`wgpu::Adapter`] or [`wgpu::Device`] cannot be found.
+    /// Returns an error when a [`wgpu::Adapter`] or [`wgpu::Device`] cannot be found, or when the
+    /// adapter given to [`PixelsBuilder::with_device`] or [`PixelsBuilder::with_pixels_device`]
+    /// does not support the surface.
     pub async fn build_async(self) -> Result<Pixels<'win>, Error> {
         self.build_impl().await
     }
 }
 
+impl<'req, 'dev> PixelsBuilder<'req, 'dev, 'static, Headless> {
+    /// Create a builder for a headless pixel buffer, which has no window surface.
+    ///
+    /// A headless [`Pixels`] renders into an internally owned texture with the given surface size,
+    /// instead of presenting to a window. This is useful for integration tests, render servers,
+    /// and batch jobs that run without a display. A software (fallback) adapter will be used when
+    /// no hardware adapter is available.
+    ///
+    /// Rendering works exactly the same as with a window surface; [`Pixels::render_with`] provides
+    /// a view of the internal texture as its render target. The texture can be accessed with
+    /// [`Pixels::headless_texture`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use pixels::PixelsBuilder;
+    /// let mut pixels = PixelsBuilder::new_headless(320, 240, 640, 480).build()?;
+    /// pixels.render()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width`, `height`, `surface_width`, or `surface_height` are 0.
+    pub fn new_headless(width: u32, height: u32, surface_width: u32, surface_height: u32) -> Self {
+        assert!(surface_width > 0);
+        assert!(surface_height > 0);
+
+        let surface_size = SurfaceSize {
+            width: surface_width,
+            height: surface_height,
+        };
+
+        Self::new_impl(width, height, None, surface_size)
+    }
+}
+
 /// Compare the given size to the limits defined by `device`.
 ///
 /// # Errors
@@ -424,9 +743,21 @@ pub fn check_texture_size(
     Ok(())
 }
 
+/// The GPU resources for a pixel buffer, created by [`create_backing_texture`].
+pub(crate) struct BackingTexture {
+    pub(crate) scaling_matrix_inverse: ultraviolet::Mat4,
+    pub(crate) texture_extent: wgpu::Extent3d,
+    pub(crate) texture: wgpu::Texture,
+    pub(crate) palette_renderer: Option<PaletteRenderer>,
+    pub(crate) scaling_renderer: ScalingRenderer,
+    pub(crate) pixels_buffer_size: usize,
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn create_backing_texture(
     device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    pipeline_cache: &PipelineCache,
     width: u32,
     height: u32,
     backing_texture_format: wgpu::TextureFormat,
@@ -434,23 +765,14 @@ pub(crate) fn create_backing_texture(
     render_texture_format: wgpu::TextureFormat,
     clear_color: wgpu::Color,
     blend_state: wgpu::BlendState,
-    scaling_mode: ScalingMode,
-) -> Result<
-    (
-        ultraviolet::Mat4,
-        wgpu::Extent3d,
-        wgpu::Texture,
-        ScalingRenderer,
-        usize,
-    ),
-    TextureError,
-> {
+    scaling_options: ScalingOptions,
+) -> Result<BackingTexture, TextureError> {
     check_texture_size(device, width, height)?;
 
     let scaling_matrix_inverse = ScalingMatrix::new(
         (width as f32, height as f32),
         (surface_size.width as f32, surface_size.height as f32),
-        scaling_mode,
+        &scaling_options,
     )
     .transform
     .inversed();
@@ -473,27 +795,67 @@ pub(crate) fn create_backing_texture(
     });
     let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
+    // Indexed color textures are converted to `RGBA` before scaling
+    let palette_renderer = (backing_texture_format == wgpu::TextureFormat::R8Uint).then(|| {
+        PaletteRenderer::new(
+            device,
+            queue,
+            pipeline_cache,
+            &texture_view,
+            &texture_extent,
+        )
+    });
+    let scaling_input = palette_renderer
+        .as_ref()
+        .map_or(&texture_view, PaletteRenderer::output_view);
+
     let scaling_renderer = ScalingRenderer::new(
         device,
-        &texture_view,
+        pipeline_cache,
+        scaling_input,
         &texture_extent,
         surface_size,
         render_texture_format,
         clear_color,
         blend_state,
-        scaling_mode,
+        scaling_options,
     );
 
     let texture_format_size = texture_format_size(backing_texture_format);
     let pixels_buffer_size = ((width * height) as f32 * texture_format_size) as usize;
 
-    Ok((
+    Ok(BackingTexture {
         scaling_matrix_inverse,
         texture_extent,
         texture,
+        palette_renderer,
         scaling_renderer,
         pixels_buffer_size,
-    ))
+    })
+}
+
+/// Create the texture that headless pixel buffers render to.
+pub(crate) fn create_render_target(
+    device: &wgpu::Device,
+    surface_size: &SurfaceSize,
+    format: wgpu::TextureFormat,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("pixels_headless_render_target"),
+        size: wgpu::Extent3d {
+            width: surface_size.width,
+            height: surface_size.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
 }
 
 #[rustfmt::skip]
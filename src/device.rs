@@ -0,0 +1,356 @@
+use crate::renderers::PipelineCache;
+use crate::{Error, PixelsBuilder, SurfaceTexture};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+/// A callback for device loss, see [`Pixels::set_device_lost_callback`].
+///
+/// [`Pixels::set_device_lost_callback`]: crate::Pixels::set_device_lost_callback
+type DeviceLostCallback = Arc<dyn Fn(wgpu::DeviceLostReason, String) + Send + Sync>;
+
+/// A GPU device that can create many pixel buffers.
+///
+/// Every [`Pixels`](crate::Pixels) created from the same `PixelsDevice` shares its `wgpu`
+/// instance, adapter, device, and queue. Each pixel buffer still has its own texture, surface, and
+/// [`ScalingRenderer`](crate::ScalingRenderer), but shader modules and render pipelines are
+/// created once and shared between pixel buffers with the same render texture format and blend
+/// state.
+///
+/// This is the way to open several windows, e.g. a main window with a couple of debug viewers,
+/// without paying for a new device and new pipelines for each of them. Cloning a `PixelsDevice`
+/// is cheap, and the clones refer to the same device.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pixels::{PixelsDevice, SurfaceTexture};
+///
+/// # let window = pixels_mocks::Window;
+/// # let tile_window = pixels_mocks::Window;
+/// # let palette_window = pixels_mocks::Window;
+/// let gpu = PixelsDevice::new()?;
+///
+/// let game = gpu
+///     .builder(320, 240, SurfaceTexture::new(640, 480, &window))
+///     .build()?;
+/// let tiles = gpu
+///     .builder(128, 128, SurfaceTexture::new(512, 512, &tile_window))
+///     .build()?;
+/// let palette = gpu
+///     .builder(16, 16, SurfaceTexture::new(256, 256, &palette_window))
+///     .build()?;
+/// # Ok::<(), pixels::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct PixelsDevice {
+    // Dropped before the device, so that dropping the device is not reported as a loss
+    pub(crate) device_lost: Arc<DeviceLost>,
+    pub(crate) instance: Arc<wgpu::Instance>,
+    pub(crate) adapter: Arc<wgpu::Adapter>,
+    pub(crate) device: Arc<wgpu::Device>,
+    pub(crate) queue: Arc<wgpu::Queue>,
+    pub(crate) pipeline_cache: Arc<PipelineCache>,
+    // Requested again by `PixelsDevice::recreate`, since a lost device cannot be queried
+    pub(crate) features: wgpu::Features,
+    pub(crate) limits: wgpu::Limits,
+}
+
+impl PixelsDevice {
+    /// Request an adapter and a device with the default options.
+    ///
+    /// The adapter is selected without a surface, honoring the same environment variables as
+    /// [`PixelsBuilder`]. A software adapter is used when no hardware adapter is available.
+    /// Building a pixel buffer for a window returns [`Error::SurfaceNotSupported`] if the adapter
+    /// cannot present to it. For more control over the adapter and device, create them with
+    /// `wgpu` and use [`PixelsDevice::from_wgpu`], or share the device of an existing pixel buffer
+    /// with [`Pixels::pixels_device`](crate::Pixels::pixels_device).
+    ///
+    /// This method blocks the current thread, making it unusable on Web targets. Use
+    /// [`PixelsDevice::new_async`] for a non-blocking alternative.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when a [`wgpu::Adapter`] or [`wgpu::Device`] cannot be found.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new() -> Result<Self, Error> {
+        pollster::block_on(Self::new_async())
+    }
+
+    /// Request an adapter and a device with the default options, without blocking the current
+    /// thread.
+    ///
+    /// See [`PixelsDevice::new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when a [`wgpu::Adapter`] or [`wgpu::Device`] cannot be found.
+    pub async fn new_async() -> Result<Self, Error> {
+        let (_, device) = PixelsBuilder::new_headless(1, 1, 1, 1)
+            .request_device()
+            .await?;
+
+        Ok(device)
+    }
+
+    /// Use an existing `wgpu` instance, adapter, device, and queue.
+    ///
+    /// This has the same requirements as [`PixelsBuilder::with_device`]. The device belongs to
+    /// the application, so no device lost callback is set on it. Report device loss with
+    /// [`PixelsDevice::notify_device_lost`] instead.
+    pub fn from_wgpu(
+        instance: Arc<wgpu::Instance>,
+        adapter: Arc<wgpu::Adapter>,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+    ) -> Self {
+        Self {
+            device_lost: DeviceLost::external(),
+            features: device.features(),
+            limits: device.limits(),
+            instance,
+            adapter,
+            device,
+            queue,
+            pipeline_cache: Arc::default(),
+        }
+    }
+
+    /// Request a new device to replace a lost one.
+    ///
+    /// The new device is requested from the same adapter with the same features and limits, or
+    /// from a new adapter when that fails. Pixel buffers move to it with
+    /// [`Pixels::recreate_device_with`](crate::Pixels::recreate_device_with), and share it again
+    /// along with new shader modules and pipelines. The device lost callback is kept.
+    ///
+    /// This method blocks the current thread, making it unusable on Web targets. Use
+    /// [`PixelsDevice::recreate_async`] for a non-blocking alternative.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::ExternalDevice`] when the device was created with [`PixelsDevice::from_wgpu`].
+    /// - [`Error::AdapterNotFound`] or [`Error::DeviceNotFound`] when no device can be created.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn recreate(&self) -> Result<Self, Error> {
+        pollster::block_on(self.recreate_async())
+    }
+
+    /// Request a new device to replace a lost one, without blocking the current thread.
+    ///
+    /// See [`PixelsDevice::recreate`].
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::ExternalDevice`] when the device was created with [`PixelsDevice::from_wgpu`].
+    /// - [`Error::AdapterNotFound`] or [`Error::DeviceNotFound`] when no device can be created.
+    pub async fn recreate_async(&self) -> Result<Self, Error> {
+        self.recreate_for(None).await
+    }
+
+    /// Request a new device, from an adapter that supports `compatible_surface` when the adapter
+    /// of this device is gone.
+    pub(crate) async fn recreate_for(
+        &self,
+        compatible_surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<Self, Error> {
+        // Replacing the device of the application would leave the application on the lost one
+        if self.device_lost.is_external() {
+            return Err(Error::ExternalDevice);
+        }
+
+        let device_descriptor = wgpu::DeviceDescriptor {
+            label: None,
+            required_features: self.features,
+            required_limits: self.limits.clone(),
+        };
+
+        // The adapter usually survives a lost device; request a new one when it does not
+        let (adapter, (device, queue)) =
+            match self.adapter.request_device(&device_descriptor, None).await {
+                Ok(device) => (Arc::clone(&self.adapter), device),
+                Err(_) => {
+                    let adapter = self
+                        .instance
+                        .request_adapter(&wgpu::RequestAdapterOptions {
+                            power_preference: wgpu::util::power_preference_from_env()
+                                .unwrap_or_default(),
+                            force_fallback_adapter: false,
+                            compatible_surface,
+                        })
+                        .await
+                        .ok_or(Error::AdapterNotFound)?;
+                    let device = adapter.request_device(&device_descriptor, None).await?;
+
+                    (Arc::new(adapter), device)
+                }
+            };
+
+        Ok(Self {
+            device_lost: DeviceLost::new(&device, Some(&self.device_lost)),
+            instance: Arc::clone(&self.instance),
+            adapter,
+            device: Arc::new(device),
+            queue: Arc::new(queue),
+            pipeline_cache: Arc::default(),
+            features: self.features,
+            limits: self.limits.clone(),
+        })
+    }
+
+    /// Use a device that was requested by pixels, and set a device lost callback on it.
+    pub(crate) fn from_requested(
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+    ) -> Self {
+        Self {
+            device_lost: DeviceLost::new(&device, None),
+            features: device.features(),
+            limits: device.limits(),
+            instance: Arc::new(instance),
+            adapter: Arc::new(adapter),
+            device: Arc::new(device),
+            queue: Arc::new(queue),
+            pipeline_cache: Arc::default(),
+        }
+    }
+
+    /// Create a builder for a pixel buffer on this device.
+    ///
+    /// This is a shortcut for [`PixelsBuilder::new`] followed by
+    /// [`PixelsBuilder::with_pixels_device`].
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width` or `height` are 0.
+    pub fn builder<'req, 'dev, 'win, W: wgpu::WindowHandle + 'win>(
+        &self,
+        width: u32,
+        height: u32,
+        surface_texture: SurfaceTexture<W>,
+    ) -> PixelsBuilder<'req, 'dev, 'win, W> {
+        PixelsBuilder::new(width, height, surface_texture).with_pixels_device(self)
+    }
+
+    /// The `Instance` that creates the surfaces.
+    pub fn instance(&self) -> &Arc<wgpu::Instance> {
+        &self.instance
+    }
+
+    /// The `Adapter` that the device was requested from.
+    pub fn adapter(&self) -> &Arc<wgpu::Adapter> {
+        &self.adapter
+    }
+
+    /// The `Device` shared by all pixel buffers.
+    pub fn device(&self) -> &Arc<wgpu::Device> {
+        &self.device
+    }
+
+    /// The `Queue` shared by all pixel buffers.
+    pub fn queue(&self) -> &Arc<wgpu::Queue> {
+        &self.queue
+    }
+
+    /// Returns `true` when the device was lost, e.g. after a driver reset.
+    ///
+    /// Every pixel buffer on this device must be moved to a new device, see
+    /// [`PixelsDevice::recreate`].
+    pub fn is_lost(&self) -> bool {
+        self.device_lost.is_lost()
+    }
+
+    /// Report that the device was lost, see
+    /// [`Pixels::notify_device_lost`](crate::Pixels::notify_device_lost).
+    pub fn notify_device_lost(&self, reason: wgpu::DeviceLostReason, message: String) {
+        self.device_lost.notify(reason, message);
+    }
+
+    /// Set a callback that is called when the device is lost, see
+    /// [`Pixels::set_device_lost_callback`](crate::Pixels::set_device_lost_callback).
+    pub fn set_device_lost_callback<F>(&self, callback: F)
+    where
+        F: Fn(wgpu::DeviceLostReason, String) + Send + Sync + 'static,
+    {
+        self.device_lost.set_callback(Arc::new(callback));
+    }
+}
+
+/// Tracks whether a device was lost, and forwards the loss to the application.
+pub(crate) struct DeviceLost {
+    lost: AtomicBool,
+    // The device belongs to the application, which reports the loss
+    external: bool,
+    callback: Mutex<Option<DeviceLostCallback>>,
+}
+
+impl DeviceLost {
+    /// Start tracking `device`, with the callback of the device it replaces, if any.
+    pub(crate) fn new(device: &wgpu::Device, previous: Option<&DeviceLost>) -> Arc<Self> {
+        let callback = previous.and_then(|previous| previous.callback.lock().unwrap().clone());
+        let device_lost = Arc::new(Self {
+            lost: AtomicBool::new(false),
+            external: false,
+            callback: Mutex::new(callback),
+        });
+
+        // `wgpu` 0.19 also calls the callback when the device is dropped, with the `Unknown`
+        // reason. The tracker is always dropped first, so that is not reported.
+        let tracker = Arc::downgrade(&device_lost);
+        device.set_device_lost_callback(move |reason, message| {
+            if let Some(tracker) = Weak::upgrade(&tracker) {
+                tracker.notify(reason, message);
+            }
+        });
+
+        device_lost
+    }
+
+    /// Track a device owned by the application, which reports the loss with
+    /// [`DeviceLost::notify`].
+    pub(crate) fn external() -> Arc<Self> {
+        Arc::new(Self {
+            lost: AtomicBool::new(false),
+            external: true,
+            callback: Mutex::new(None),
+        })
+    }
+
+    pub(crate) fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn is_external(&self) -> bool {
+        self.external
+    }
+
+    /// Mark the device as lost and call the callback, unless the `reason` is not a loss.
+    pub(crate) fn notify(&self, reason: wgpu::DeviceLostReason, message: String) {
+        let lost = match reason {
+            wgpu::DeviceLostReason::Unknown | wgpu::DeviceLostReason::Destroyed => true,
+            wgpu::DeviceLostReason::Dropped | wgpu::DeviceLostReason::ReplacedCallback => false,
+        };
+        // Report each loss once
+        if !lost || self.lost.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let callback = self.callback.lock().unwrap().clone();
+        if let Some(callback) = callback {
+            callback(reason, message);
+        }
+    }
+
+    pub(crate) fn set_callback(&self, callback: DeviceLostCallback) {
+        *self.callback.lock().unwrap() = Some(callback);
+    }
+}
+
+impl std::fmt::Debug for DeviceLost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceLost")
+            .field("lost", &self.is_lost())
+            .field("external", &self.external)
+            .finish_non_exhaustive()
+    }
+}
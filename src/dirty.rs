@@ -0,0 +1,122 @@
+/// The number of separate regions tracked before they are merged into one.
+///
+/// Each region is uploaded with its own `write_texture` call, so this bounds the number of calls
+/// per frame.
+const MAX_REGIONS: usize = 8;
+
+/// A rectangle in pixel buffer coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Rect {
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+impl Rect {
+    pub(crate) const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    const fn right(&self) -> u32 {
+        self.x + self.width
+    }
+
+    const fn bottom(&self) -> u32 {
+        self.y + self.height
+    }
+
+    const fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Clip this rectangle to a `width` by `height` area at the origin.
+    fn clip(self, width: u32, height: u32) -> Self {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        let right = self.x.saturating_add(self.width).min(width);
+        let bottom = self.y.saturating_add(self.height).min(height);
+
+        Self::new(x, y, right - x, bottom - y)
+    }
+
+    /// Whether the rectangles overlap or share an edge.
+    fn touches(&self, other: &Self) -> bool {
+        self.x <= other.right()
+            && other.x <= self.right()
+            && self.y <= other.bottom()
+            && other.y <= self.bottom()
+    }
+
+    /// The smallest rectangle containing both rectangles.
+    fn union(&self, other: &Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+
+        Self::new(x, y, right - x, bottom - y)
+    }
+}
+
+/// Tracks the regions of the pixel buffer that have changed since the last texture upload.
+///
+/// Regions that touch are merged, and when too many separate regions accumulate they are merged
+/// into their bounding box. This keeps the number of uploads small without uploading much more
+/// than what actually changed.
+#[derive(Debug)]
+pub(crate) struct DirtyRegions {
+    width: u32,
+    height: u32,
+    rects: Vec<Rect>,
+}
+
+impl DirtyRegions {
+    /// Create a tracker for a `width` by `height` pixel buffer, with the whole buffer marked.
+    pub(crate) fn new(width: u32, height: u32) -> Self {
+        let mut dirty = Self {
+            width,
+            height,
+            rects: Vec::new(),
+        };
+        dirty.mark_all();
+
+        dirty
+    }
+
+    /// Mark a region as changed. The region is clipped to the pixel buffer.
+    pub(crate) fn mark(&mut self, rect: Rect) {
+        let mut rect = rect.clip(self.width, self.height);
+        if rect.is_empty() {
+            return;
+        }
+
+        // Absorb every region that touches the new one; the union may touch more regions
+        while let Some(index) = self.rects.iter().position(|other| rect.touches(other)) {
+            rect = rect.union(&self.rects.swap_remove(index));
+        }
+        self.rects.push(rect);
+
+        if self.rects.len() > MAX_REGIONS {
+            let bounds = self.rects.iter().fold(rect, |acc, other| acc.union(other));
+            self.rects.clear();
+            self.rects.push(bounds);
+        }
+    }
+
+    /// Mark the whole pixel buffer as changed.
+    pub(crate) fn mark_all(&mut self) {
+        self.rects.clear();
+        self.rects.push(Rect::new(0, 0, self.width, self.height));
+    }
+
+    /// Remove and return all changed regions.
+    pub(crate) fn take(&mut self) -> Vec<Rect> {
+        std::mem::take(&mut self.rects)
+    }
+}
@@ -0,0 +1,384 @@
+//! CPU drawing primitives for the pixel buffer.
+//!
+//! All primitives draw into a [`FrameMut`] of any size, and are clipped to it; coordinates may be
+//! negative or beyond the edges of the frame. Colors are always given in `RGBA` order, and are
+//! converted to the texture format of the frame. The supported formats are `Rgba8Unorm`,
+//! `Rgba8UnormSrgb`, `Bgra8Unorm`, and `Bgra8UnormSrgb`.
+//!
+//! Blending is done on the stored color values, without converting sRGB colors to linear. This
+//! matches what most software renderers do.
+//!
+//! This module requires the `draw` cargo feature.
+//!
+//! # Example
+//!
+//! ```
+//! use pixels::{draw, FrameMut};
+//!
+//! let mut data = vec![0; 64 * 48 * 4];
+//! let mut frame = FrameMut::new(&mut data, 64, 48, pixels::wgpu::TextureFormat::Rgba8UnormSrgb);
+//!
+//! draw::clear(&mut frame, [0x00, 0x00, 0x00, 0xff]);
+//! draw::rect(&mut frame, 4, 4, 56, 40, [0xff, 0xff, 0xff, 0xff]);
+//! draw::line(&mut frame, -10, -10, 100, 70, [0xff, 0x00, 0x00, 0xff]);
+//! draw::fill_circle(&mut frame, 32, 24, 8, [0x00, 0xff, 0x00, 0xff]);
+//! ```
+
+use crate::{Frame, FrameMut};
+
+/// Fill the whole frame with a color.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported.
+pub fn clear(frame: &mut FrameMut, color: [u8; 4]) {
+    frame.fill(&encode(frame.format(), color));
+}
+
+/// Fill a rectangle with a color.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported.
+pub fn fill_rect(frame: &mut FrameMut, x: i32, y: i32, width: u32, height: u32, color: [u8; 4]) {
+    let pixel = encode(frame.format(), color);
+    let Some((x, y, width, height)) = clip_rect(frame, x, y, width, height) else {
+        return;
+    };
+
+    frame.view_mut(x, y, width, height).fill(&pixel);
+}
+
+/// Draw the outline of a rectangle, one pixel wide.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported.
+pub fn rect(frame: &mut FrameMut, x: i32, y: i32, width: u32, height: u32, color: [u8; 4]) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    let right = (x as i64 + width as i64 - 1).min(i32::MAX as i64) as i32;
+    let bottom = (y as i64 + height as i64 - 1).min(i32::MAX as i64) as i32;
+
+    hline(frame, x, right, y, color);
+    hline(frame, x, right, bottom, color);
+    vline(frame, x, y, bottom, color);
+    vline(frame, right, y, bottom, color);
+}
+
+/// Draw a horizontal line from `x0` to `x1`, inclusive.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported.
+pub fn hline(frame: &mut FrameMut, x0: i32, x1: i32, y: i32, color: [u8; 4]) {
+    let pixel = encode(frame.format(), color);
+    let (left, right) = (x0.min(x1), x0.max(x1));
+
+    span_clipped(frame, left as i64, right as i64, y as i64, &pixel);
+}
+
+/// Draw a vertical line from `y0` to `y1`, inclusive.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported.
+pub fn vline(frame: &mut FrameMut, x: i32, y0: i32, y1: i32, color: [u8; 4]) {
+    let pixel = encode(frame.format(), color);
+    let top = (y0.min(y1) as i64).clamp(0, frame.height() as i64);
+    let bottom = (y0.max(y1) as i64 + 1).clamp(0, frame.height() as i64);
+    if top < bottom && (0..frame.width() as i64).contains(&(x as i64)) {
+        frame
+            .view_mut(x as u32, top as u32, 1, (bottom - top) as u32)
+            .fill(&pixel);
+    }
+}
+
+/// Draw a line from `(x0, y0)` to `(x1, y1)`, inclusive.
+///
+/// Clipping does not change which pixels are drawn; the visible part of a line is the same as if
+/// the frame were large enough to contain the whole line. Only the visible part is visited, so
+/// lines with far away endpoints are cheap.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported.
+pub fn line(frame: &mut FrameMut, x0: i32, y0: i32, x1: i32, y1: i32, color: [u8; 4]) {
+    let pixel = encode(frame.format(), color);
+    let (width, height) = (frame.width() as i64, frame.height() as i64);
+    let (x0, y0, x1, y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+    let (dx, dy) = ((x1 - x0).abs(), (y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+
+    // Step along the major axis, rounding the minor axis to the nearest pixel
+    let x_major = dx >= dy;
+    let (major, minor) = if x_major { (dx, dy) } else { (dy, dx) };
+    let point = |i: i64| {
+        let j =
+            ((2 * i as i128 * minor as i128 + major as i128) / (2 * major as i128).max(1)) as i64;
+        if x_major {
+            (x0 + sx * i, y0 + sy * j)
+        } else {
+            (x0 + sx * j, y0 + sy * i)
+        }
+    };
+
+    // Only visit the steps where the major axis is within the frame
+    let (start, sign, limit) = if x_major {
+        (x0, sx, width)
+    } else {
+        (y0, sy, height)
+    };
+    let (first, last) = match sign {
+        0 => (0, 0),
+        1 => (-start, limit - 1 - start),
+        _ => (start - (limit - 1), start),
+    };
+
+    for i in first.max(0)..=last.min(major) {
+        let (x, y) = point(i);
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            frame.set_pixel(x as u32, y as u32, &pixel);
+        }
+    }
+}
+
+/// Draw the outline of a circle, one pixel wide.
+///
+/// Nothing is drawn when `radius` is negative.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported.
+pub fn circle(frame: &mut FrameMut, cx: i32, cy: i32, radius: i32, color: [u8; 4]) {
+    let pixel = encode(frame.format(), color);
+    let (cx, cy) = (cx as i64, cy as i64);
+
+    midpoint_circle(radius, |x, y| {
+        for (px, py) in [
+            (cx + x, cy + y),
+            (cx - x, cy + y),
+            (cx + x, cy - y),
+            (cx - x, cy - y),
+            (cx + y, cy + x),
+            (cx - y, cy + x),
+            (cx + y, cy - x),
+            (cx - y, cy - x),
+        ] {
+            set_clipped(frame, px, py, &pixel);
+        }
+    });
+}
+
+/// Draw a filled circle.
+///
+/// The filled circle covers exactly the pixels of [`circle`] and everything inside of it.
+/// Nothing is drawn when `radius` is negative.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported.
+pub fn fill_circle(frame: &mut FrameMut, cx: i32, cy: i32, radius: i32, color: [u8; 4]) {
+    let pixel = encode(frame.format(), color);
+    let (cx, cy) = (cx as i64, cy as i64);
+
+    midpoint_circle(radius, |x, y| {
+        for (half_width, dy) in [(x, y), (x, -y), (y, x), (y, -x)] {
+            span_clipped(frame, cx - half_width, cx + half_width, cy + dy, &pixel);
+        }
+    });
+}
+
+/// Fill the 4-connected area of pixels that have the same color as the pixel at `(x, y)`.
+///
+/// Nothing is drawn when `(x, y)` is outside of the frame.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported.
+pub fn flood_fill(frame: &mut FrameMut, x: i32, y: i32, color: [u8; 4]) {
+    let pixel = encode(frame.format(), color);
+    let (width, height) = (frame.width(), frame.height());
+    let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+        return;
+    };
+    let Some(target) = frame.pixel_rgba(x, y) else {
+        return;
+    };
+    if target == pixel {
+        return;
+    }
+
+    // Scanline fill: fill a whole span, then look for new spans above and below it
+    let mut seeds = vec![(x, y)];
+    while let Some((x, y)) = seeds.pop() {
+        if frame.pixel_rgba(x, y) != Some(target) {
+            continue;
+        }
+        let mut left = x;
+        while left > 0 && frame.pixel_rgba(left - 1, y) == Some(target) {
+            left -= 1;
+        }
+        let mut right = x;
+        while right + 1 < width && frame.pixel_rgba(right + 1, y) == Some(target) {
+            right += 1;
+        }
+        frame.view_mut(left, y, right - left + 1, 1).fill(&pixel);
+
+        let neighbors = [y.checked_sub(1), Some(y + 1).filter(|&y| y < height)];
+        for ny in neighbors.into_iter().flatten() {
+            let mut in_span = false;
+            for nx in left..=right {
+                let matches = frame.pixel_rgba(nx, ny) == Some(target);
+                if matches && !in_span {
+                    seeds.push((nx, ny));
+                }
+                in_span = matches;
+            }
+        }
+    }
+}
+
+/// Draw a source image with its top-left corner at `(x, y)`, blending it with its alpha channel.
+///
+/// The source and destination may have different texture formats, e.g. an `Rgba8UnormSrgb`
+/// sprite can be drawn to a `Bgra8UnormSrgb` frame.
+///
+/// # Panics
+///
+/// Panics when the texture format of either frame is not supported.
+pub fn blit(frame: &mut FrameMut, x: i32, y: i32, source: &Frame) {
+    let (source_format, format) = (source.format(), frame.format());
+    assert_supported(source_format);
+    assert_supported(format);
+
+    let Some((left, top, width, height)) = clip_rect(frame, x, y, source.width(), source.height())
+    else {
+        return;
+    };
+    let source = source.view(
+        (left as i64 - x as i64) as u32,
+        (top as i64 - y as i64) as u32,
+        width,
+        height,
+    );
+    let mut dest = frame.view_mut(left, top, width, height);
+
+    for (source_row, dest_row) in source.rows_rgba().zip(dest.rows_rgba_mut()) {
+        for (source, dest) in source_row.iter().zip(dest_row) {
+            let color = decode(source_format, *source);
+            match color[3] {
+                0 => {}
+                0xff => *dest = encode(format, color),
+                _ => *dest = encode(format, blend(color, decode(format, *dest))),
+            }
+        }
+    }
+}
+
+/// Blend a source color over a destination color, with straight alpha.
+fn blend(source: [u8; 4], dest: [u8; 4]) -> [u8; 4] {
+    let alpha = source[3] as u32;
+    let inverse = 255 - alpha;
+    let mix = |s: u8, d: u8| ((s as u32 * alpha + d as u32 * inverse + 127) / 255) as u8;
+
+    [
+        mix(source[0], dest[0]),
+        mix(source[1], dest[1]),
+        mix(source[2], dest[2]),
+        (alpha + (dest[3] as u32 * inverse + 127) / 255) as u8,
+    ]
+}
+
+/// Visit one point in each octant step of a circle with the midpoint algorithm.
+fn midpoint_circle(radius: i32, mut visit: impl FnMut(i64, i64)) {
+    if radius < 0 {
+        return;
+    }
+    let (mut x, mut y) = (radius as i64, 0);
+    let mut error = 1 - x;
+
+    while x >= y {
+        visit(x, y);
+        y += 1;
+        if error < 0 {
+            error += 2 * y + 1;
+        } else {
+            x -= 1;
+            error += 2 * (y - x) + 1;
+        }
+    }
+}
+
+/// Clip a rectangle to the frame, returning `None` when nothing is visible.
+fn clip_rect(
+    frame: &FrameMut,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    let left = (x as i64).clamp(0, frame.width() as i64);
+    let top = (y as i64).clamp(0, frame.height() as i64);
+    let right = (x as i64 + width as i64).clamp(0, frame.width() as i64);
+    let bottom = (y as i64 + height as i64).clamp(0, frame.height() as i64);
+
+    (left < right && top < bottom).then(|| {
+        (
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        )
+    })
+}
+
+pub(crate) fn set_clipped(frame: &mut FrameMut, x: i64, y: i64, pixel: &[u8; 4]) {
+    if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
+        if let Some(dest) = frame.pixel_mut(x, y) {
+            dest.copy_from_slice(pixel);
+        }
+    }
+}
+
+fn span_clipped(frame: &mut FrameMut, x0: i64, x1: i64, y: i64, pixel: &[u8; 4]) {
+    let left = x0.clamp(0, frame.width() as i64);
+    let right = (x1 + 1).clamp(0, frame.width() as i64);
+    if left < right && (0..frame.height() as i64).contains(&y) {
+        frame
+            .view_mut(left as u32, y as u32, (right - left) as u32, 1)
+            .fill(pixel);
+    }
+}
+
+#[track_caller]
+fn assert_supported(format: wgpu::TextureFormat) {
+    use wgpu::TextureFormat::*;
+
+    assert!(
+        matches!(
+            format,
+            Rgba8Unorm | Rgba8UnormSrgb | Bgra8Unorm | Bgra8UnormSrgb
+        ),
+        "Texture format {format:?} is not supported for drawing"
+    );
+}
+
+/// Convert an `RGBA` color to the byte order of a texture format.
+#[track_caller]
+pub(crate) fn encode(format: wgpu::TextureFormat, [r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    use wgpu::TextureFormat::*;
+
+    assert_supported(format);
+    match format {
+        Bgra8Unorm | Bgra8UnormSrgb => [b, g, r, a],
+        _ => [r, g, b, a],
+    }
+}
+
+/// Convert a pixel in the byte order of a texture format to `RGBA`.
+fn decode(format: wgpu::TextureFormat, pixel: [u8; 4]) -> [u8; 4] {
+    // Swapping red and blue is its own inverse
+    encode(format, pixel)
+}
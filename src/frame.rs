@@ -0,0 +1,457 @@
+//! Typed views of the pixel buffer.
+//!
+//! [`Frame`] and [`FrameMut`] know the dimensions and texture format of the pixel buffer, so
+//! pixels can be addressed by their `(x, y)` coordinates instead of computing byte offsets by
+//! hand. Views can be narrowed to a sub-rectangle, and mutable views can be split into disjoint
+//! bands of rows that can be written from multiple threads.
+
+/// The layout of a view into the pixel buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Layout {
+    width: u32,
+    height: u32,
+    stride: usize,
+    bytes_per_pixel: usize,
+    format: wgpu::TextureFormat,
+}
+
+impl Layout {
+    /// Get the layout for a `width` by `height` pixel buffer with no padding between rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the format is compressed, or does not have a fixed texel size.
+    fn new(width: u32, height: u32, format: wgpu::TextureFormat) -> Self {
+        let bytes_per_pixel = match (format.block_dimensions(), format.block_copy_size(None)) {
+            ((1, 1), Some(size)) => size as usize,
+            _ => panic!("Frame views are not supported for texture format {format:?}"),
+        };
+
+        Self {
+            width,
+            height,
+            stride: width as usize * bytes_per_pixel,
+            bytes_per_pixel,
+            format,
+        }
+    }
+
+    /// The number of bytes a view needs, which does not include the padding after the last row.
+    fn len(&self) -> usize {
+        match self.height {
+            0 => 0,
+            height => (height as usize - 1) * self.stride + self.row_len(),
+        }
+    }
+
+    fn row_len(&self) -> usize {
+        self.width as usize * self.bytes_per_pixel
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        y as usize * self.stride + x as usize * self.bytes_per_pixel
+    }
+
+    fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Get the byte range and layout of a sub-rectangle.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the rectangle is not within the view.
+    #[track_caller]
+    fn sub_rect(&self, x: u32, y: u32, width: u32, height: u32) -> (std::ops::Range<usize>, Self) {
+        let in_bounds = x
+            .checked_add(width)
+            .zip(y.checked_add(height))
+            .is_some_and(|(right, bottom)| right <= self.width && bottom <= self.height);
+        assert!(
+            in_bounds,
+            "Rectangle {:?} is outside of the {}x{} frame",
+            (x, y, width, height),
+            self.width,
+            self.height,
+        );
+
+        let layout = Self {
+            width,
+            height,
+            ..*self
+        };
+        let start = if layout.len() == 0 {
+            0
+        } else {
+            self.offset(x, y)
+        };
+
+        (start..start + layout.len(), layout)
+    }
+
+    #[track_caller]
+    fn assert_rgba(&self) {
+        assert_eq!(
+            self.bytes_per_pixel, 4,
+            "Texture format {:?} is not a 4 byte format",
+            self.format
+        );
+    }
+}
+
+/// An immutable view of the pixel buffer, or a rectangle within it.
+///
+/// Get one with [`Pixels::frame_view`](crate::Pixels::frame_view).
+#[derive(Debug, Clone, Copy)]
+pub struct Frame<'frame> {
+    data: &'frame [u8],
+    layout: Layout,
+}
+
+/// A mutable view of the pixel buffer, or a rectangle within it.
+///
+/// Get one with [`Pixels::frame_view_mut`](crate::Pixels::frame_view_mut) or
+/// [`Pixels::frame_view_mut_region`](crate::Pixels::frame_view_mut_region).
+#[derive(Debug)]
+pub struct FrameMut<'frame> {
+    data: &'frame mut [u8],
+    layout: Layout,
+}
+
+/// Accessors shared by [`Frame`] and [`FrameMut`].
+macro_rules! impl_frame_common {
+    () => {
+        /// The width of the view in pixels.
+        pub fn width(&self) -> u32 {
+            self.layout.width
+        }
+
+        /// The height of the view in pixels.
+        pub fn height(&self) -> u32 {
+            self.layout.height
+        }
+
+        /// The number of bytes from the start of one row to the start of the next.
+        ///
+        /// This is larger than `width() * bytes_per_pixel()` for views of a rectangle that is
+        /// narrower than the pixel buffer.
+        pub fn stride(&self) -> usize {
+            self.layout.stride
+        }
+
+        /// The number of bytes for each pixel.
+        pub fn bytes_per_pixel(&self) -> usize {
+            self.layout.bytes_per_pixel
+        }
+
+        /// The texture format of the pixel buffer.
+        pub fn format(&self) -> wgpu::TextureFormat {
+            self.layout.format
+        }
+
+        /// Get the bytes for the pixel at `(x, y)`, or `None` when it is outside of the view.
+        pub fn pixel(&self, x: u32, y: u32) -> Option<&[u8]> {
+            self.layout.contains(x, y).then(|| {
+                let offset = self.layout.offset(x, y);
+
+                &self.data[offset..offset + self.layout.bytes_per_pixel]
+            })
+        }
+
+        /// Get the pixel at `(x, y)` as 4 bytes, or `None` when it is outside of the view.
+        ///
+        /// # Panics
+        ///
+        /// Panics when the texture format does not have 4 bytes per pixel.
+        pub fn pixel_rgba(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+            self.layout.assert_rgba();
+
+            self.pixel(x, y)
+                .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+        }
+
+        /// Get the pixel at `(x, y)` as a little-endian `u32`, or `None` when it is outside of the
+        /// view.
+        ///
+        /// For `Rgba8UnormSrgb`, red is in the least significant byte: `0xAABBGGRR`.
+        ///
+        /// # Panics
+        ///
+        /// Panics when the texture format does not have 4 bytes per pixel.
+        pub fn pixel_u32(&self, x: u32, y: u32) -> Option<u32> {
+            self.pixel_rgba(x, y).map(u32::from_le_bytes)
+        }
+
+        /// Iterate over the rows of the view. Each row is `width() * bytes_per_pixel()` bytes.
+        pub fn rows(&self) -> impl Iterator<Item = &[u8]> + '_ {
+            let row_len = self.layout.row_len();
+
+            (0..self.layout.height as usize).map(move |y| {
+                let start = y * self.layout.stride;
+
+                &self.data[start..start + row_len]
+            })
+        }
+
+        /// Iterate over the rows of the view as 4 byte pixels.
+        ///
+        /// # Panics
+        ///
+        /// Panics when the texture format does not have 4 bytes per pixel.
+        pub fn rows_rgba(&self) -> impl Iterator<Item = &[[u8; 4]]> + '_ {
+            self.layout.assert_rgba();
+
+            self.rows().map(bytemuck::cast_slice)
+        }
+    };
+}
+
+impl<'frame> Frame<'frame> {
+    /// Create a view of a tightly packed `width` by `height` pixel buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the format is compressed, or when `data` is too short.
+    pub fn new(data: &'frame [u8], width: u32, height: u32, format: wgpu::TextureFormat) -> Self {
+        let layout = Layout::new(width, height, format);
+        assert!(data.len() >= layout.len(), "Frame data is too short");
+
+        Self { data, layout }
+    }
+
+    impl_frame_common!();
+
+    /// Get a view of a rectangle within this view.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the rectangle is not within the view.
+    #[track_caller]
+    pub fn view(&self, x: u32, y: u32, width: u32, height: u32) -> Frame<'frame> {
+        let (range, layout) = self.layout.sub_rect(x, y, width, height);
+
+        Frame {
+            data: &self.data[range],
+            layout,
+        }
+    }
+}
+
+impl<'frame> FrameMut<'frame> {
+    /// Create a mutable view of a tightly packed `width` by `height` pixel buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the format is compressed, or when `data` is too short.
+    pub fn new(
+        data: &'frame mut [u8],
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let layout = Layout::new(width, height, format);
+        assert!(data.len() >= layout.len(), "Frame data is too short");
+
+        Self { data, layout }
+    }
+
+    impl_frame_common!();
+
+    /// Get a mutable reference to the bytes for the pixel at `(x, y)`, or `None` when it is
+    /// outside of the view.
+    pub fn pixel_mut(&mut self, x: u32, y: u32) -> Option<&mut [u8]> {
+        self.layout.contains(x, y).then(|| {
+            let offset = self.layout.offset(x, y);
+
+            &mut self.data[offset..offset + self.layout.bytes_per_pixel]
+        })
+    }
+
+    /// Set the bytes for the pixel at `(x, y)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the pixel is outside of the view, or when the length of `value` is not
+    /// `bytes_per_pixel()`.
+    #[track_caller]
+    pub fn set_pixel(&mut self, x: u32, y: u32, value: &[u8]) {
+        let (width, height) = (self.layout.width, self.layout.height);
+        self.pixel_mut(x, y)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Pixel {:?} is outside of the {width}x{height} frame",
+                    (x, y)
+                )
+            })
+            .copy_from_slice(value);
+    }
+
+    /// Set the pixel at `(x, y)` from 4 bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the pixel is outside of the view, or when the texture format does not have 4
+    /// bytes per pixel.
+    #[track_caller]
+    pub fn set_pixel_rgba(&mut self, x: u32, y: u32, value: [u8; 4]) {
+        self.layout.assert_rgba();
+        self.set_pixel(x, y, &value);
+    }
+
+    /// Set the pixel at `(x, y)` from a little-endian `u32`. See [`FrameMut::pixel_u32`].
+    ///
+    /// # Panics
+    ///
+    /// Panics when the pixel is outside of the view, or when the texture format does not have 4
+    /// bytes per pixel.
+    #[track_caller]
+    pub fn set_pixel_u32(&mut self, x: u32, y: u32, value: u32) {
+        self.set_pixel_rgba(x, y, value.to_le_bytes());
+    }
+
+    /// Iterate mutably over the rows of the view. Each row is `width() * bytes_per_pixel()`
+    /// bytes.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [u8]> + '_ {
+        let row_len = self.layout.row_len();
+        let height = self.layout.height as usize;
+
+        self.data
+            .chunks_mut(self.layout.stride.max(1))
+            .take(height)
+            .map(move |row| &mut row[..row_len])
+    }
+
+    /// Iterate mutably over the rows of the view as 4 byte pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the texture format does not have 4 bytes per pixel.
+    pub fn rows_rgba_mut(&mut self) -> impl Iterator<Item = &mut [[u8; 4]]> + '_ {
+        self.layout.assert_rgba();
+
+        self.rows_mut().map(bytemuck::cast_slice_mut)
+    }
+
+    /// Fill every pixel in the view with the same bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the length of `value` is not `bytes_per_pixel()`.
+    #[track_caller]
+    pub fn fill(&mut self, value: &[u8]) {
+        assert_eq!(value.len(), self.layout.bytes_per_pixel);
+
+        for row in self.rows_mut() {
+            for pixel in row.chunks_exact_mut(value.len()) {
+                pixel.copy_from_slice(value);
+            }
+        }
+    }
+
+    /// Get an immutable view of this view.
+    pub fn as_frame(&self) -> Frame<'_> {
+        Frame {
+            data: self.data,
+            layout: self.layout,
+        }
+    }
+
+    /// Get a mutable view of a rectangle within this view.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the rectangle is not within the view.
+    #[track_caller]
+    pub fn view_mut(&mut self, x: u32, y: u32, width: u32, height: u32) -> FrameMut<'_> {
+        let (range, layout) = self.layout.sub_rect(x, y, width, height);
+
+        FrameMut {
+            data: &mut self.data[range],
+            layout,
+        }
+    }
+
+    /// Convert this view into a mutable view of a rectangle within it.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the rectangle is not within the view.
+    #[track_caller]
+    pub fn into_view_mut(self, x: u32, y: u32, width: u32, height: u32) -> FrameMut<'frame> {
+        let (range, layout) = self.layout.sub_rect(x, y, width, height);
+
+        FrameMut {
+            data: &mut self.data[range],
+            layout,
+        }
+    }
+
+    /// Split the view into two disjoint views: the rows above `y`, and the rows from `y` down.
+    ///
+    /// The views can be written independently, e.g. from different threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `y` is greater than the height of the view.
+    #[track_caller]
+    pub fn split_at_row_mut(self, y: u32) -> (FrameMut<'frame>, FrameMut<'frame>) {
+        assert!(y <= self.layout.height, "Row {y} is outside of the frame");
+
+        let mid = self.layout.offset(0, y).min(self.data.len());
+        let (top, bottom) = self.data.split_at_mut(mid);
+        let top_layout = Layout {
+            height: y,
+            ..self.layout
+        };
+        let bottom_layout = Layout {
+            height: self.layout.height - y,
+            ..self.layout
+        };
+
+        (
+            FrameMut {
+                data: top,
+                layout: top_layout,
+            },
+            FrameMut {
+                data: bottom,
+                layout: bottom_layout,
+            },
+        )
+    }
+
+    /// Split the view into disjoint bands of `rows` rows each. The last band may be shorter.
+    ///
+    /// This is useful for filling the frame from multiple threads.
+    ///
+    /// ```
+    /// # use pixels::FrameMut;
+    /// # let mut data = vec![0; 64 * 48 * 4];
+    /// # let frame = FrameMut::new(&mut data, 64, 48, pixels::wgpu::TextureFormat::Rgba8UnormSrgb);
+    /// std::thread::scope(|scope| {
+    ///     for mut band in frame.into_row_bands(16) {
+    ///         scope.spawn(move || band.fill(&[0xff, 0x00, 0x00, 0xff]));
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when `rows` is 0.
+    #[track_caller]
+    pub fn into_row_bands(self, rows: u32) -> impl Iterator<Item = FrameMut<'frame>> {
+        assert!(rows > 0, "Bands must have at least one row");
+
+        let mut rest = Some(self);
+        std::iter::from_fn(move || {
+            let frame = rest.take()?;
+            if frame.layout.height <= rows {
+                return (frame.layout.height > 0).then_some(frame);
+            }
+            let (band, remainder) = frame.split_at_row_mut(rows);
+            rest = Some(remainder);
+
+            Some(band)
+        })
+    }
+}
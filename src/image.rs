@@ -0,0 +1,517 @@
+//! Image loading for the pixel buffer.
+//!
+//! Images are decoded to [`RgbaImage`], which can be drawn with [`draw::blit`](crate::draw::blit)
+//! through [`RgbaImage::as_frame`], or copied into a whole frame with [`copy_to_frame`]. The
+//! supported file formats are PNG, BMP, PCX (including paletted images), and QOI.
+//!
+//! [`Pixels::load_frame_from`](crate::Pixels::load_frame_from) loads an image straight into the
+//! pixel buffer.
+//!
+//! This module requires the `image` cargo feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use pixels::image::{self, Fit};
+//! use pixels::{draw, FrameMut};
+//!
+//! let background = image::open("background.png")?;
+//! let sprite = image::decode(&std::fs::read("sprite.qoi")?)?;
+//!
+//! let mut data = vec![0; 320 * 240 * 4];
+//! let mut frame = FrameMut::new(&mut data, 320, 240, pixels::wgpu::TextureFormat::Rgba8UnormSrgb);
+//!
+//! image::copy_to_frame(&background, &mut frame, Fit::Letterbox);
+//! draw::blit(&mut frame, 16, 16, &sprite.as_frame());
+//! # Ok::<(), pixels::image::ImageError>(())
+//! ```
+
+use crate::draw::encode;
+use crate::{FrameMut, RgbaImage};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The largest image that will be decoded, in pixels.
+///
+/// This bounds the memory used for images with compressed pixel data.
+const MAX_PIXELS: u64 = 400_000_000;
+
+/// All the ways in which loading an image can fail.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ImageError {
+    /// Reading the image file failed.
+    #[error("Unable to read the image file.")]
+    Io(#[from] std::io::Error),
+    /// The image is not in one of the supported formats.
+    #[error("Unrecognized image format.")]
+    UnknownFormat,
+    /// The image is larger than the decoders allow.
+    #[error("Image dimensions are too large: {0}x{1}")]
+    TooLarge(u32, u32),
+    /// The PNG image is malformed or unsupported.
+    #[error("Invalid PNG image.")]
+    Png(#[from] png::DecodingError),
+    /// The PCX image is malformed or unsupported.
+    #[error("Invalid PCX image.")]
+    Pcx(#[source] std::io::Error),
+    /// The BMP image is malformed or unsupported.
+    #[error("Invalid BMP image: {0}")]
+    Bmp(&'static str),
+    /// The QOI image is malformed.
+    #[error("Invalid QOI image: {0}")]
+    Qoi(&'static str),
+    /// The pixel buffer texture format cannot be written with `RGBA` colors.
+    #[error("Texture format {0:?} is not supported for images.")]
+    UnsupportedTextureFormat(wgpu::TextureFormat),
+}
+
+/// The image file formats that can be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ImageFormat {
+    /// Portable Network Graphics.
+    Png,
+    /// Windows bitmap, uncompressed with 1, 4, 8, 16, 24, or 32 bits per pixel.
+    Bmp,
+    /// ZSoft PC Paintbrush, paletted or 24-bit.
+    Pcx,
+    /// The Quite OK Image format.
+    Qoi,
+}
+
+impl ImageFormat {
+    /// Guess the format of an encoded image from its first bytes.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0x89, b'P', b'N', b'G', ..] => Some(Self::Png),
+            [b'q', b'o', b'i', b'f', ..] => Some(Self::Qoi),
+            [b'B', b'M', ..] => Some(Self::Bmp),
+            // Manufacturer, version, and run-length encoding
+            [0x0a, 0 | 2..=5, 0 | 1, ..] => Some(Self::Pcx),
+            _ => None,
+        }
+    }
+}
+
+/// How an image is fitted into a frame with different dimensions.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Fit {
+    /// Scale the image to fill the frame, ignoring its aspect ratio.
+    Stretch,
+    /// Scale the image as large as it fits while keeping its aspect ratio, centered with opaque
+    /// black bars on the sides that are not covered.
+    #[default]
+    Letterbox,
+}
+
+/// Where to load an image from.
+///
+/// This is usually created implicitly from a path or a byte slice.
+#[derive(Debug, Clone, Copy)]
+pub enum ImageSource<'a> {
+    /// An image file.
+    Path(&'a Path),
+    /// An encoded image in memory.
+    Bytes(&'a [u8]),
+}
+
+impl<'a> From<&'a Path> for ImageSource<'a> {
+    fn from(path: &'a Path) -> Self {
+        Self::Path(path)
+    }
+}
+
+impl<'a> From<&'a PathBuf> for ImageSource<'a> {
+    fn from(path: &'a PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+impl<'a> From<&'a str> for ImageSource<'a> {
+    fn from(path: &'a str) -> Self {
+        Self::Path(Path::new(path))
+    }
+}
+
+impl<'a> From<&'a [u8]> for ImageSource<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u8; N]> for ImageSource<'a> {
+    fn from(bytes: &'a [u8; N]) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl<'a> From<&'a Vec<u8>> for ImageSource<'a> {
+    fn from(bytes: &'a Vec<u8>) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+impl ImageSource<'_> {
+    /// Load and decode the image.
+    pub fn load(self) -> Result<RgbaImage, ImageError> {
+        match self {
+            Self::Path(path) => open(path),
+            Self::Bytes(bytes) => decode(bytes),
+        }
+    }
+}
+
+/// Read and decode an image file, detecting its format from its contents.
+pub fn open<P: AsRef<Path>>(path: P) -> Result<RgbaImage, ImageError> {
+    decode(&std::fs::read(path)?)
+}
+
+/// Decode an image, detecting its format from its contents.
+pub fn decode(bytes: &[u8]) -> Result<RgbaImage, ImageError> {
+    let format = ImageFormat::detect(bytes).ok_or(ImageError::UnknownFormat)?;
+
+    decode_format(bytes, format)
+}
+
+/// Decode an image in a known format.
+pub fn decode_format(bytes: &[u8], format: ImageFormat) -> Result<RgbaImage, ImageError> {
+    match format {
+        ImageFormat::Png => decode_png(bytes),
+        ImageFormat::Bmp => decode_bmp(bytes),
+        ImageFormat::Pcx => decode_pcx(bytes),
+        ImageFormat::Qoi => decode_qoi(bytes),
+    }
+}
+
+/// Copy an image into the whole frame, scaling it with nearest-neighbor sampling.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported by the [`draw`](crate::draw)
+/// module.
+pub fn copy_to_frame(image: &RgbaImage, frame: &mut FrameMut, fit: Fit) {
+    let format = frame.format();
+    let (frame_width, frame_height) = (frame.width(), frame.height());
+    let (image_width, image_height) = (image.width(), image.height());
+
+    let (width, height) = match fit {
+        _ if image_width == 0 || image_height == 0 || frame_width == 0 || frame_height == 0 => {
+            (0, 0)
+        }
+        Fit::Stretch => (frame_width, frame_height),
+        Fit::Letterbox => {
+            let (fw, fh) = (frame_width as u64, frame_height as u64);
+            let (iw, ih) = (image_width as u64, image_height as u64);
+            if fw * ih <= fh * iw {
+                (frame_width, ((ih * fw + iw / 2) / iw).clamp(1, fh) as u32)
+            } else {
+                (((iw * fh + ih / 2) / ih).clamp(1, fw) as u32, frame_height)
+            }
+        }
+    };
+    let left = (frame_width - width) / 2;
+    let top = (frame_height - height) / 2;
+
+    let black = encode(format, [0x00, 0x00, 0x00, 0xff]);
+    for (y, row) in frame.rows_mut().enumerate() {
+        let y = y as u32;
+        for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+            let x = x as u32;
+            let inside = (left..left + width).contains(&x) && (top..top + height).contains(&y);
+            let color = if inside {
+                // Sample the center of the destination pixel
+                let sx = sample(x - left, width, image_width);
+                let sy = sample(y - top, height, image_height);
+                let offset = (sy as usize * image_width as usize + sx as usize) * 4;
+                let rgba = &image.pixels()[offset..offset + 4];
+                encode(format, [rgba[0], rgba[1], rgba[2], rgba[3]])
+            } else {
+                black
+            };
+            pixel.copy_from_slice(&color);
+        }
+    }
+}
+
+/// Map a destination coordinate to a source coordinate for nearest-neighbor sampling.
+fn sample(dest: u32, dest_size: u32, source_size: u32) -> u32 {
+    ((dest as u64 * 2 + 1) * source_size as u64 / (dest_size as u64 * 2)) as u32
+}
+
+/// Check the image dimensions against [`MAX_PIXELS`].
+fn check_size(width: u32, height: u32) -> Result<(), ImageError> {
+    if width as u64 * height as u64 > MAX_PIXELS {
+        return Err(ImageError::TooLarge(width, height));
+    }
+
+    Ok(())
+}
+
+fn decode_png(bytes: &[u8]) -> Result<RgbaImage, ImageError> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let (width, height) = (reader.info().width, reader.info().height);
+    check_size(width, height)?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let pixels = buffer[..info.buffer_size()]
+        .chunks_exact(info.line_size)
+        .flat_map(|line| {
+            let channels = info.color_type.samples();
+            line[..width as usize * channels]
+                .chunks_exact(channels)
+                .flat_map(|pixel| match *pixel {
+                    [l] => [l, l, l, 0xff],
+                    [l, a] => [l, l, l, a],
+                    [r, g, b] => [r, g, b, 0xff],
+                    [r, g, b, a] => [r, g, b, a],
+                    _ => unreachable!(),
+                })
+        })
+        .collect();
+
+    Ok(RgbaImage::new(width, height, pixels))
+}
+
+fn decode_pcx(bytes: &[u8]) -> Result<RgbaImage, ImageError> {
+    let mut reader = pcx::Reader::from_mem(bytes).map_err(ImageError::Pcx)?;
+    let (width, height) = (reader.width() as usize, reader.height() as usize);
+    check_size(width as u32, height as u32)?;
+    let mut pixels = Vec::with_capacity(width * height * 4);
+
+    if reader.is_paletted() {
+        let mut indices = vec![0; width * height];
+        for row in indices.chunks_exact_mut(width.max(1)) {
+            reader.next_row_paletted(row).map_err(ImageError::Pcx)?;
+        }
+
+        let mut palette = [0; 256 * 3];
+        reader.read_palette(&mut palette).map_err(ImageError::Pcx)?;
+        for &index in &indices {
+            let rgb = &palette[index as usize * 3..][..3];
+            pixels.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 0xff]);
+        }
+    } else {
+        let mut row = vec![0; width * 3];
+        for _ in 0..height {
+            reader.next_row_rgb(&mut row).map_err(ImageError::Pcx)?;
+            for rgb in row.chunks_exact(3) {
+                pixels.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 0xff]);
+            }
+        }
+    }
+
+    Ok(RgbaImage::new(width as u32, height as u32, pixels))
+}
+
+/// A color channel in a BMP image with bit fields.
+#[derive(Debug, Clone, Copy)]
+struct BitField {
+    mask: u32,
+    shift: u32,
+    max: u32,
+}
+
+impl BitField {
+    fn new(mask: u32) -> Self {
+        let shift = if mask == 0 { 0 } else { mask.trailing_zeros() };
+        let max = mask >> shift;
+
+        Self { mask, shift, max }
+    }
+
+    /// Extract the channel from a pixel and scale it to 8 bits.
+    fn extract(&self, pixel: u32, default: u8) -> u8 {
+        if self.max == 0 {
+            return default;
+        }
+
+        let value = (pixel & self.mask) >> self.shift;
+        (value as u64 * 255 / self.max as u64) as u8
+    }
+}
+
+fn decode_bmp(bytes: &[u8]) -> Result<RgbaImage, ImageError> {
+    let error = ImageError::Bmp;
+    let u16_at = |offset: usize| {
+        bytes
+            .get(offset..offset + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or(error("Truncated header"))
+    };
+    let u32_at = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or(error("Truncated header"))
+    };
+
+    let data_offset = u32_at(10)? as usize;
+    let header_size = u32_at(14)? as usize;
+    let (width, height, bits, compression, palette_entry_size) = if header_size == 12 {
+        // OS/2 core header
+        let width = u16_at(18)? as i32;
+        let height = u16_at(20)? as i32;
+        (width, height, u16_at(24)?, 0, 3)
+    } else if header_size >= 40 {
+        let width = u32_at(18)? as i32;
+        let height = u32_at(22)? as i32;
+        (width, height, u16_at(28)?, u32_at(30)?, 4)
+    } else {
+        return Err(error("Unsupported header"));
+    };
+    if width <= 0 || height == 0 {
+        return Err(error("Invalid dimensions"));
+    }
+    let top_down = height < 0;
+    let (width, height) = (width as u32, height.unsigned_abs());
+    check_size(width, height)?;
+
+    // Uncompressed, bit fields, and bit fields with alpha
+    let (fields, palette_offset) = match (compression, bits) {
+        (0, 16) => {
+            let fields = [0x7c00, 0x03e0, 0x001f, 0].map(BitField::new);
+            (Some(fields), 14 + header_size)
+        }
+        (0, 24 | 32) => {
+            let fields = [0xff_0000, 0x00_ff00, 0x00_00ff, 0].map(BitField::new);
+            (Some(fields), 14 + header_size)
+        }
+        (0, 1 | 4 | 8) => (None, 14 + header_size),
+        (3 | 6, 16 | 32) => {
+            // The masks follow a 40 byte header, and are part of larger headers
+            let has_alpha = compression == 6 || header_size >= 56;
+            let alpha = if has_alpha { u32_at(66)? } else { 0 };
+            let fields = [u32_at(54)?, u32_at(58)?, u32_at(62)?, alpha].map(BitField::new);
+            let masks_size = match header_size {
+                40 if has_alpha => 16,
+                40 => 12,
+                _ => 0,
+            };
+            (Some(fields), 14 + header_size + masks_size)
+        }
+        (0 | 3 | 6, _) => return Err(error("Unsupported bit depth")),
+        _ => return Err(error("Unsupported compression")),
+    };
+
+    let palette = if fields.is_none() {
+        let used = if header_size >= 40 { u32_at(46)? } else { 0 };
+        let count = match used {
+            0 => 1 << bits,
+            used => used.min(1 << bits),
+        } as usize;
+        let palette = bytes
+            .get(palette_offset..palette_offset + count * palette_entry_size)
+            .ok_or(error("Truncated palette"))?;
+        palette
+            .chunks_exact(palette_entry_size)
+            .map(|bgr| [bgr[2], bgr[1], bgr[0], 0xff])
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let stride = (width as usize * bits as usize).div_ceil(32) * 4;
+    let data = bytes
+        .get(data_offset..)
+        .and_then(|data| data.get(..stride * height as usize))
+        .ok_or(error("Truncated pixel data"))?;
+
+    let mut pixels = vec![0; width as usize * height as usize * 4];
+    for (y, row) in data.chunks_exact(stride).enumerate() {
+        let y = if top_down { y } else { height as usize - 1 - y };
+        let output = &mut pixels[y * width as usize * 4..][..width as usize * 4];
+
+        for (x, pixel) in output.chunks_exact_mut(4).enumerate() {
+            let rgba = match fields {
+                Some([r, g, b, a]) => {
+                    let value = match bits {
+                        16 => u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]) as u32,
+                        24 => u32::from_le_bytes([row[x * 3], row[x * 3 + 1], row[x * 3 + 2], 0]),
+                        _ => u32::from_le_bytes([
+                            row[x * 4],
+                            row[x * 4 + 1],
+                            row[x * 4 + 2],
+                            row[x * 4 + 3],
+                        ]),
+                    };
+                    [
+                        r.extract(value, 0),
+                        g.extract(value, 0),
+                        b.extract(value, 0),
+                        a.extract(value, 0xff),
+                    ]
+                }
+                None => {
+                    let bit = x * bits as usize;
+                    let shift = 8 - bits as usize - bit % 8;
+                    let index = (row[bit / 8] >> shift) & ((1 << bits) - 1) as u8;
+                    *palette
+                        .get(index as usize)
+                        .ok_or(error("Palette index out of range"))?
+                }
+            };
+            pixel.copy_from_slice(&rgba);
+        }
+    }
+
+    Ok(RgbaImage::new(width, height, pixels))
+}
+
+fn decode_qoi(bytes: &[u8]) -> Result<RgbaImage, ImageError> {
+    let error = ImageError::Qoi;
+    let header = bytes.get(..14).ok_or(error("Truncated header"))?;
+    let width = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    let height = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+    if !matches!(header[12], 3 | 4) || header[13] > 1 {
+        return Err(error("Invalid header"));
+    }
+    check_size(width, height)?;
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    let mut index = [[0; 4]; 64];
+    let mut pixel = [0, 0, 0, 0xff];
+    let mut run = 0;
+    let mut chunks = bytes[14..].iter().copied();
+    let mut next = || chunks.next().ok_or(error("Truncated pixel data"));
+
+    for _ in 0..width as usize * height as usize {
+        if run > 0 {
+            run -= 1;
+        } else {
+            let op = next()?;
+            match op {
+                0xfe => pixel = [next()?, next()?, next()?, pixel[3]],
+                0xff => pixel = [next()?, next()?, next()?, next()?],
+                _ => match op >> 6 {
+                    0 => pixel = index[op as usize],
+                    1 => {
+                        pixel[0] = pixel[0].wrapping_add((op >> 4) & 0x03).wrapping_sub(2);
+                        pixel[1] = pixel[1].wrapping_add((op >> 2) & 0x03).wrapping_sub(2);
+                        pixel[2] = pixel[2].wrapping_add(op & 0x03).wrapping_sub(2);
+                    }
+                    2 => {
+                        let dg = (op & 0x3f).wrapping_sub(32);
+                        let rb = next()?;
+                        let dr = dg.wrapping_sub(8).wrapping_add(rb >> 4);
+                        let db = dg.wrapping_sub(8).wrapping_add(rb & 0x0f);
+                        pixel[0] = pixel[0].wrapping_add(dr);
+                        pixel[1] = pixel[1].wrapping_add(dg);
+                        pixel[2] = pixel[2].wrapping_add(db);
+                    }
+                    _ => run = op & 0x3f,
+                },
+            }
+
+            let [r, g, b, a] = pixel.map(|channel| channel as usize);
+            index[(r * 3 + g * 5 + b * 7 + a * 11) % 64] = pixel;
+        }
+
+        pixels.extend_from_slice(&pixel);
+    }
+
+    Ok(RgbaImage::new(width, height, pixels))
+}
@@ -0,0 +1,658 @@
+//! Compositing layers on top of the pixel buffer.
+//!
+//! Each [`Layer`] is a pixel buffer with its own size, texture format, and backing texture. Layers
+//! are added with [`Pixels::add_layer`](crate::Pixels::add_layer) and are stacked above the main
+//! pixel buffer, which acts as the base layer. Only the layers that changed are uploaded, and they
+//! are composited on the GPU before the scaling renderer draws the result to the surface.
+
+use crate::renderers::PipelineCache;
+use crate::{check_texture_size, Frame, FrameMut, TextureError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// The size of the uniform buffer for each layer.
+const UNIFORM_SIZE: u64 = 16;
+
+/// How a [`Layer`] is combined with the layers below it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Draw the layer over the layers below, using its alpha channel.
+    #[default]
+    Normal,
+    /// Add the layer color to the layers below, weighted by its alpha channel. Useful for lights
+    /// and glows.
+    Add,
+    /// Multiply the layers below by the layer color, weighted by its alpha channel. Useful for
+    /// shadows and tinting.
+    Multiply,
+}
+
+/// A named pixel buffer that is composited over the main pixel buffer.
+///
+/// Layers are created with [`Pixels::add_layer`](crate::Pixels::add_layer) and accessed by name
+/// with [`Pixels::layer_mut`](crate::Pixels::layer_mut). The layer is positioned on the main pixel
+/// buffer by its offset in pixels, and anything outside of the main pixel buffer is cropped.
+#[derive(Debug)]
+pub struct Layer {
+    name: String,
+    width: u32,
+    height: u32,
+    texture_format: wgpu::TextureFormat,
+    pixels: Vec<u8>,
+    offset: (i32, i32),
+    opacity: f32,
+    visible: bool,
+    blend_mode: BlendMode,
+
+    // Whether the pixels need to be uploaded to the texture
+    dirty: AtomicBool,
+    texture: wgpu::Texture,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Layer {
+    fn new(
+        device: &wgpu::Device,
+        compositor: &Compositor,
+        name: &str,
+        width: u32,
+        height: u32,
+        texture_format: wgpu::TextureFormat,
+    ) -> Result<Self, TextureError> {
+        check_texture_size(device, width, height)?;
+        let bytes_per_pixel = match texture_format.sample_type(None, None) {
+            Some(wgpu::TextureSampleType::Float { .. }) if !texture_format.is_compressed() => {
+                texture_format.block_copy_size(None).unwrap_or_default()
+            }
+            _ => return Err(TextureError::TextureFormat(texture_format)),
+        };
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(bytes_per_pixel as usize))
+            .ok_or(TextureError::TextureSize(width, height))?;
+
+        let (texture, uniform_buffer, bind_group) =
+            create_layer_texture(device, compositor, width, height, texture_format);
+
+        Ok(Self {
+            name: name.to_string(),
+            width,
+            height,
+            texture_format,
+            pixels: vec![0; size],
+            offset: (0, 0),
+            opacity: 1.0,
+            visible: true,
+            blend_mode: BlendMode::default(),
+            dirty: AtomicBool::new(true),
+            texture,
+            uniform_buffer,
+            bind_group,
+        })
+    }
+
+    /// The name of the layer.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The width of the layer in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the layer in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The texture format of the layer.
+    pub fn texture_format(&self) -> wgpu::TextureFormat {
+        self.texture_format
+    }
+
+    /// Get an immutable byte slice for the layer pixels.
+    pub fn frame(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Get a mutable byte slice for the layer pixels.
+    ///
+    /// The whole layer is uploaded to its texture on the next render.
+    pub fn frame_mut(&mut self) -> &mut [u8] {
+        *self.dirty.get_mut() = true;
+
+        &mut self.pixels
+    }
+
+    /// Get a typed view of the layer pixels, see [`Pixels::frame_view`](crate::Pixels::frame_view).
+    pub fn frame_view(&self) -> Frame<'_> {
+        Frame::new(&self.pixels, self.width, self.height, self.texture_format)
+    }
+
+    /// Get a typed, mutable view of the layer pixels, see
+    /// [`Pixels::frame_view_mut`](crate::Pixels::frame_view_mut).
+    pub fn frame_view_mut(&mut self) -> FrameMut<'_> {
+        let (width, height, format) = (self.width, self.height, self.texture_format);
+
+        FrameMut::new(self.frame_mut(), width, height, format)
+    }
+
+    /// The position of the top-left corner of the layer on the main pixel buffer.
+    pub fn offset(&self) -> (i32, i32) {
+        self.offset
+    }
+
+    /// Move the top-left corner of the layer to `(x, y)` on the main pixel buffer.
+    ///
+    /// The offset may be negative or larger than the main pixel buffer, e.g. to scroll a large
+    /// background layer.
+    pub fn set_offset(&mut self, x: i32, y: i32) {
+        self.offset = (x, y);
+    }
+
+    /// The opacity of the layer.
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    /// Set the opacity of the layer, from `0.0` (invisible) to `1.0` (the default). The opacity is
+    /// multiplied with the alpha channel of every pixel.
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    /// Check whether the layer is drawn.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Show or hide the layer. Hidden layers are not uploaded or drawn.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// The blend mode of the layer.
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Set how the layer is combined with the layers below it.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    /// Create the GPU resources on a new device, see [`Layer::replace`].
+    fn recreate(
+        &self,
+        device: &wgpu::Device,
+        compositor: &Compositor,
+    ) -> Result<(wgpu::Texture, wgpu::Buffer, wgpu::BindGroup), TextureError> {
+        check_texture_size(device, self.width, self.height)?;
+
+        Ok(create_layer_texture(
+            device,
+            compositor,
+            self.width,
+            self.height,
+            self.texture_format,
+        ))
+    }
+
+    /// Move to the GPU resources of a new device, and upload the pixels again.
+    fn replace(&mut self, resources: (wgpu::Texture, wgpu::Buffer, wgpu::BindGroup)) {
+        (self.texture, self.uniform_buffer, self.bind_group) = resources;
+        *self.dirty.get_mut() = true;
+    }
+
+    /// Upload the pixels if they changed, and the uniforms.
+    fn update(&self, queue: &wgpu::Queue) {
+        if self.dirty.swap(false, Ordering::Relaxed) {
+            queue.write_texture(
+                self.texture.as_image_copy(),
+                &self.pixels,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.pixels.len() as u32 / self.height),
+                    rows_per_image: Some(self.height),
+                },
+                self.texture.size(),
+            );
+        }
+
+        let uniforms = [
+            self.offset.0 as f32,
+            self.offset.1 as f32,
+            self.opacity,
+            0.0,
+        ];
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&uniforms));
+    }
+
+    fn is_drawn(&self) -> bool {
+        self.visible && self.opacity > 0.0
+    }
+}
+
+/// Create the texture of a layer, and the bind group to composite it.
+fn create_layer_texture(
+    device: &wgpu::Device,
+    compositor: &Compositor,
+    width: u32,
+    height: u32,
+    texture_format: wgpu::TextureFormat,
+) -> (wgpu::Texture, wgpu::Buffer, wgpu::BindGroup) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("pixels_layer_texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: texture_format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let (uniform_buffer, bind_group) = compositor.create_bind_group(device, &view);
+
+    (texture, uniform_buffer, bind_group)
+}
+
+/// The texture that the scaling renderer samples when there are no layers.
+pub(crate) struct Source<'a> {
+    pub(crate) view: &'a wgpu::TextureView,
+    pub(crate) format: wgpu::TextureFormat,
+    pub(crate) size: wgpu::Extent3d,
+}
+
+/// The layers above the main pixel buffer, in drawing order.
+#[derive(Debug, Default)]
+pub(crate) struct LayerStack {
+    layers: Vec<Layer>,
+    // Created with the first layer
+    compositor: Option<Compositor>,
+}
+
+/// The GPU resources of a [`LayerStack`] on a new device.
+#[derive(Debug)]
+pub(crate) struct LayerResources {
+    compositor: Option<Compositor>,
+    layers: Vec<(wgpu::Texture, wgpu::Buffer, wgpu::BindGroup)>,
+}
+
+impl LayerStack {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    pub(crate) fn iter(&self) -> std::slice::Iter<'_, Layer> {
+        self.layers.iter()
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub(crate) fn get_mut(&mut self, name: &str) -> Option<&mut Layer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    /// Create a layer on top of the stack, or replace the layer with the same name in place.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn insert(
+        &mut self,
+        device: &wgpu::Device,
+        pipeline_cache: &PipelineCache,
+        source: &Source<'_>,
+        name: &str,
+        width: u32,
+        height: u32,
+        texture_format: wgpu::TextureFormat,
+    ) -> Result<(), TextureError> {
+        let compositor = self
+            .compositor
+            .get_or_insert_with(|| Compositor::new(device, pipeline_cache, source));
+        let layer = Layer::new(device, compositor, name, width, height, texture_format)?;
+
+        match self.layers.iter().position(|layer| layer.name == name) {
+            Some(index) => self.layers[index] = layer,
+            None => self.layers.push(layer),
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> Option<Layer> {
+        let index = self.layers.iter().position(|layer| layer.name == name)?;
+
+        Some(self.layers.remove(index))
+    }
+
+    /// Recreate the composited output after the main pixel buffer was resized.
+    pub(crate) fn resize(&mut self, device: &wgpu::Device, source: &Source<'_>) {
+        if let Some(compositor) = &mut self.compositor {
+            compositor.resize(device, source);
+        }
+    }
+
+    /// Create the compositor and every layer on a new device, without replacing anything yet.
+    ///
+    /// Move to the new resources with [`LayerStack::replace`].
+    pub(crate) fn recreate(
+        &self,
+        device: &wgpu::Device,
+        pipeline_cache: &PipelineCache,
+        source: &Source<'_>,
+    ) -> Result<LayerResources, TextureError> {
+        if self.layers.is_empty() {
+            return Ok(LayerResources {
+                compositor: None,
+                layers: Vec::new(),
+            });
+        }
+
+        let compositor = Compositor::new(device, pipeline_cache, source);
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.recreate(device, &compositor))
+            .collect::<Result<_, _>>()?;
+
+        Ok(LayerResources {
+            compositor: Some(compositor),
+            layers,
+        })
+    }
+
+    /// Move the compositor and every layer to the resources from [`LayerStack::recreate`],
+    /// keeping the layer pixels.
+    pub(crate) fn replace(&mut self, resources: LayerResources) {
+        for (layer, resources) in self.layers.iter_mut().zip(resources.layers) {
+            layer.replace(resources);
+        }
+        self.compositor = resources.compositor;
+    }
+
+    /// The composited output, or `None` when there are no layers.
+    pub(crate) fn output_view(&self) -> Option<&wgpu::TextureView> {
+        self.compositor
+            .as_ref()
+            .filter(|_| !self.layers.is_empty())
+            .map(|compositor| &compositor.output_view)
+    }
+
+    /// Upload the changed layers and composite them over the main pixel buffer.
+    pub(crate) fn render(&self, encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue) {
+        let Some(compositor) = self.compositor.as_ref().filter(|_| !self.layers.is_empty()) else {
+            return;
+        };
+
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("pixels_layer_compositor_render_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &compositor.output_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        // The base layer replaces the cleared output
+        rpass.set_pipeline(&compositor.pipelines.pipeline_base);
+        rpass.set_bind_group(0, &compositor.base_bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+
+        for layer in self.layers.iter().filter(|layer| layer.is_drawn()) {
+            layer.update(queue);
+
+            let pipeline = match layer.blend_mode {
+                BlendMode::Normal => &compositor.pipelines.pipeline_normal,
+                BlendMode::Add => &compositor.pipelines.pipeline_add,
+                BlendMode::Multiply => &compositor.pipelines.pipeline_multiply,
+            };
+            rpass.set_pipeline(pipeline);
+            rpass.set_bind_group(0, &layer.bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+    }
+}
+
+/// Composites the layers into a texture with the size of the main pixel buffer.
+#[derive(Debug)]
+struct Compositor {
+    // Shared with every compositor on the device that has the same output format
+    pipelines: Arc<CompositorPipelines>,
+    // The base layer is always drawn at the origin with full opacity
+    base_uniform_buffer: wgpu::Buffer,
+    base_bind_group: wgpu::BindGroup,
+    output_view: wgpu::TextureView,
+    output_format: wgpu::TextureFormat,
+}
+
+impl Compositor {
+    fn new(device: &wgpu::Device, pipeline_cache: &PipelineCache, source: &Source<'_>) -> Self {
+        // Keep the precision and color space of the main pixel buffer
+        let output_format = match source.format {
+            wgpu::TextureFormat::Rgba16Float | wgpu::TextureFormat::Rgba32Float => {
+                wgpu::TextureFormat::Rgba16Float
+            }
+            format if format.is_srgb() => wgpu::TextureFormat::Rgba8UnormSrgb,
+            _ => wgpu::TextureFormat::Rgba8Unorm,
+        };
+        let pipelines = pipeline_cache.compositor(device, output_format);
+
+        let (base_uniform_buffer, base_bind_group, output_view) =
+            create_output(device, &pipelines.bind_group_layout, source, output_format);
+
+        Self {
+            pipelines,
+            base_uniform_buffer,
+            base_bind_group,
+            output_view,
+            output_format,
+        }
+    }
+
+    fn resize(&mut self, device: &wgpu::Device, source: &Source<'_>) {
+        (
+            self.base_uniform_buffer,
+            self.base_bind_group,
+            self.output_view,
+        ) = create_output(
+            device,
+            &self.pipelines.bind_group_layout,
+            source,
+            self.output_format,
+        );
+    }
+
+    /// Create the uniform buffer and bind group for drawing a texture.
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        view: &wgpu::TextureView,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        create_bind_group(device, &self.pipelines.bind_group_layout, view)
+    }
+}
+
+/// The layout and pipelines of the layer compositor for one output texture format.
+#[derive(Debug)]
+pub(crate) struct CompositorPipelines {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_base: wgpu::RenderPipeline,
+    pipeline_normal: wgpu::RenderPipeline,
+    pipeline_add: wgpu::RenderPipeline,
+    pipeline_multiply: wgpu::RenderPipeline,
+}
+
+impl CompositorPipelines {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        module: &wgpu::ShaderModule,
+        output_format: wgpu::TextureFormat,
+    ) -> Self {
+        // Create bind group layout
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_layer_compositor_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(UNIFORM_SIZE),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        // Create pipelines
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("pixels_layer_compositor_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let create_pipeline = |entry_point, blend| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("pixels_layer_compositor_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module,
+                    entry_point,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: output_format,
+                        blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            })
+        };
+        // Additive and multiplicative blending keep the alpha channel of the layers below
+        let keep_alpha = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let pipeline_base = create_pipeline("fs_main", None);
+        let pipeline_normal = create_pipeline("fs_main", Some(wgpu::BlendState::ALPHA_BLENDING));
+        let pipeline_add = create_pipeline(
+            "fs_main",
+            Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            }),
+        );
+        let pipeline_multiply = create_pipeline(
+            "fs_multiply",
+            Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            }),
+        );
+
+        Self {
+            bind_group_layout,
+            pipeline_base,
+            pipeline_normal,
+            pipeline_add,
+            pipeline_multiply,
+        }
+    }
+}
+
+/// Create the bind group for the base layer, and the output texture with the same size.
+fn create_output(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    source: &Source<'_>,
+    format: wgpu::TextureFormat,
+) -> (wgpu::Buffer, wgpu::BindGroup, wgpu::TextureView) {
+    let (uniform_buffer, bind_group) = create_bind_group(device, layout, source.view);
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("pixels_layer_compositor_output_texture"),
+        size: source.size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+
+    (
+        uniform_buffer,
+        bind_group,
+        texture.create_view(&wgpu::TextureViewDescriptor::default()),
+    )
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    view: &wgpu::TextureView,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    use wgpu::util::DeviceExt;
+
+    let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("pixels_layer_uniform_buffer"),
+        contents: bytemuck::cast_slice(&[0.0f32, 0.0, 1.0, 0.0]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("pixels_layer_bind_group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+        ],
+    });
+
+    (uniform_buffer, bind_group)
+}
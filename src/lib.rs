@@ -33,13 +33,46 @@
 #![forbid(unsafe_code)]
 
 pub use crate::builder::{check_texture_size, PixelsBuilder};
+use crate::device::DeviceLost;
+pub use crate::device::PixelsDevice;
+use crate::dirty::{DirtyRegions, Rect};
+pub use crate::frame::{Frame, FrameMut};
+use crate::layers::LayerStack;
+pub use crate::layers::{BlendMode, Layer};
+use crate::passes::PassChain;
+pub use crate::passes::{PassContext, RenderPass};
+pub use crate::readback::RgbaImage;
 pub use crate::renderers::ScalingRenderer;
+#[cfg(feature = "crt")]
+pub use crate::renderers::{CrtMask, CrtOptions, CrtRenderer};
+use crate::renderers::{PaletteRenderer, PipelineCache};
 pub use raw_window_handle;
+use raw_window_handle::{DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 pub use wgpu;
 
 mod builder;
+mod device;
+mod dirty;
+#[cfg(feature = "draw")]
+pub mod draw;
+mod frame;
+#[cfg(feature = "image")]
+pub mod image;
+mod layers;
+mod passes;
+mod readback;
+#[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+pub mod record;
 mod renderers;
+#[cfg(feature = "screenshot")]
+mod screenshot;
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+pub mod shader;
+#[cfg(feature = "text")]
+pub mod text;
 
 /// A logical texture for a window surface.
 #[derive(Debug)]
@@ -48,6 +81,13 @@ pub struct SurfaceTexture<W: wgpu::WindowHandle> {
     size: SurfaceSize,
 }
 
+/// The window type for headless pixel buffers, which have no window surface.
+///
+/// This type cannot be constructed. It only names the window type of the [`PixelsBuilder`]
+/// returned by [`PixelsBuilder::new_headless`].
+#[derive(Debug)]
+pub enum Headless {}
+
 /// A logical texture size for a window surface.
 #[derive(Debug)]
 struct SurfaceSize {
@@ -56,13 +96,103 @@ struct SurfaceSize {
 }
 
 /// The scaling mode controls the scaling behavior of [`renderers::ScalingRenderer`].
-#[derive(Debug, Copy, Clone)]
+///
+/// The scaling mode only decides the size of the scaled buffer. Where it is placed on the surface,
+/// and which edges are cropped when it does not fit, is decided by the [`Anchor`], see
+/// [`PixelsBuilder::anchor`] and [`Pixels::set_anchor`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ScalingMode {
     /// The buffer is scaled up, if needed, to the nearest integer multiple of the buffer size.
     PixelPerfect,
     /// Fill the screen while preserving aspect ratio. The renderer effectively scales the buffer
     /// to the nearest integer multiple first, then linearly interpolates to fit.
+    ///
+    /// The other axis is letterboxed, and the buffer is aligned to the [`Anchor`] edges.
     Fill,
+    /// Fill the screen, ignoring the aspect ratio. The buffer is scaled horizontally and
+    /// vertically by independent factors.
+    Stretch,
+    /// Like [`ScalingMode::PixelPerfect`] when the buffer fits on the screen, and like
+    /// [`ScalingMode::Fill`] when the screen is smaller than the buffer. The buffer is scaled up
+    /// to the nearest integer multiple, or scaled down by a fractional amount to fit.
+    FitIntegerOrDown,
+    /// The buffer is scaled by exactly the given integer multiple, regardless of the screen size.
+    /// When it is larger than the screen, the [`Anchor`] decides which edges are kept.
+    ///
+    /// A scale of 0 is treated as 1.
+    Fixed(u32),
+    /// Cover the whole screen while preserving aspect ratio. The buffer is scaled up or down until
+    /// both axes fill the screen, and the overflow on the other axis is cropped.
+    ///
+    /// The [`Anchor`] decides which edges are kept, e.g. [`Anchor::Bottom`] keeps the bottom edge
+    /// and crops the top when the screen is wider than the buffer.
+    Cover,
+}
+
+/// The scaling filter controls how [`renderers::ScalingRenderer`] samples the pixel buffer.
+///
+/// The filter is independent of the [`ScalingMode`], which decides the size and position of the
+/// scaled buffer. Filters that work on blocks of 2x2 or 3x3 output pixels look best when the buffer
+/// is scaled by a multiple of that size.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ScalingFilter {
+    /// Sample the nearest pixel. Fractional scales are anti-aliased at the pixel edges.
+    #[default]
+    Nearest,
+    /// Scale to the nearest integer multiple with nearest sampling, then interpolate linearly for
+    /// the remaining fraction. Sharp pixels without uneven widths.
+    SharpBilinear,
+    /// The Scale2x algorithm, also known as EPX. Smooths diagonal edges on 2x2 blocks.
+    Scale2x,
+    /// The Scale3x algorithm. Smooths diagonal edges on 3x3 blocks.
+    Scale3x,
+    /// The xBR level 2 algorithm. Detects edges at 30, 45, and 60 degrees and draws them smoothly.
+    Xbr,
+    /// A filter similar to hq2x. Blends each quadrant of a pixel with the neighbors it borders,
+    /// depending on their similarity.
+    Hqx,
+    /// Bicubic interpolation with the Catmull-Rom spline.
+    Bicubic,
+    /// Lanczos interpolation with three lobes.
+    Lanczos,
+}
+
+/// The anchor controls where the scaled pixel buffer is placed on the surface.
+///
+/// When the scaled buffer is smaller than the surface, the anchor decides which edges it is
+/// aligned to. The remaining space is filled with the clear color. When it is larger than the
+/// surface, the anchor decides which edges are kept while the overflow is cropped.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Anchor {
+    /// Align to the top and left edges.
+    TopLeft,
+    /// Align to the top edge, centered horizontally.
+    Top,
+    /// Align to the top and right edges.
+    TopRight,
+    /// Align to the left edge, centered vertically.
+    Left,
+    /// Center on both axes.
+    #[default]
+    Center,
+    /// Align to the right edge, centered vertically.
+    Right,
+    /// Align to the bottom and left edges.
+    BottomLeft,
+    /// Align to the bottom edge, centered horizontally.
+    Bottom,
+    /// Align to the bottom and right edges.
+    BottomRight,
+}
+
+/// The resolution at which frames are captured for screenshots and recordings.
+#[cfg(any(feature = "screenshot", feature = "record"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// The pixel buffer resolution, without scaling.
+    Buffer,
+    /// The surface resolution, scaled by the renderer as it appears on screen.
+    Surface,
 }
 
 /// Provides the internal state for custom shaders.
@@ -71,18 +201,38 @@ pub enum ScalingMode {
 /// [`Pixels::render_with`].
 #[derive(Debug)]
 pub struct PixelsContext<'win> {
+    // Shared with every pixel buffer on the same `PixelsDevice`, and dropped before the device, so
+    // that dropping the device is not reported as a loss
+    device_lost: Arc<DeviceLost>,
+
+    /// The `Instance` that created the surface.
+    pub instance: Arc<wgpu::Instance>,
+
+    /// The `Adapter` that the device was requested from.
+    pub adapter: Arc<wgpu::Adapter>,
+
     /// The `Device` allows creating GPU resources.
-    pub device: wgpu::Device,
+    pub device: Arc<wgpu::Device>,
 
     /// The `Queue` provides access to the GPU command queue.
-    pub queue: wgpu::Queue,
+    pub queue: Arc<wgpu::Queue>,
 
-    surface: wgpu::Surface<'win>,
+    // Shared with every pixel buffer on the same `PixelsDevice`
+    pipeline_cache: Arc<PipelineCache>,
+
+    // Requested again by `Pixels::recreate_device`
+    features: wgpu::Features,
+    limits: wgpu::Limits,
+
+    target: RenderTarget<'win>,
 
     /// This is the texture that your raw data is copied to by [`Pixels::render`] or
     /// [`Pixels::render_with`].
     pub texture: wgpu::Texture,
 
+    // Converts indexed color textures to `RGBA`
+    palette_renderer: Option<PaletteRenderer>,
+
     /// Provides access to the texture size.
     pub texture_extent: wgpu::Extent3d,
     pub texture_format: wgpu::TextureFormat,
@@ -99,6 +249,46 @@ pub struct PixelsContext<'win> {
     /// Defines the capabilities of the surface and adapter including available present_modes, and
     /// alpha_modes.
     pub surface_capabilities: wgpu::SurfaceCapabilities,
+
+    // Layers added with `Pixels::add_layer`
+    layers: LayerStack,
+
+    // Post-processing passes added with `Pixels::add_pass`
+    passes: PassChain,
+}
+
+impl PixelsContext<'_> {
+    /// Draw the pixel buffer to the render target the same way [`Pixels::render`] does.
+    ///
+    /// The scaling renderer draws the pixel buffer, followed by the post-processing passes added
+    /// with [`Pixels::add_pass`]. Without any passes, this is equivalent to
+    /// [`ScalingRenderer::render`].
+    pub fn render_passes(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &wgpu::TextureView,
+    ) {
+        self.passes.render(
+            encoder,
+            render_target,
+            &self.scaling_renderer,
+            &self.device,
+            &self.queue,
+        );
+    }
+
+    /// Get the CRT renderer, when enabled with [`Pixels::set_crt`].
+    #[cfg(feature = "crt")]
+    pub fn crt_renderer(&self) -> Option<&CrtRenderer> {
+        self.passes.crt()
+    }
+}
+
+/// The final render target; either a window surface or an internal texture when headless.
+#[derive(Debug)]
+enum RenderTarget<'win> {
+    Surface(wgpu::Surface<'win>),
+    Headless(wgpu::Texture),
 }
 
 /// Represents a 2D pixel buffer with an explicit image resolution.
@@ -113,14 +303,23 @@ pub struct Pixels<'win> {
     surface_texture_format: wgpu::TextureFormat,
     blend_state: wgpu::BlendState,
     alpha_mode: wgpu::CompositeAlphaMode,
-    adapter: wgpu::Adapter,
 
     // Pixel buffer
     pixels: Vec<u8>,
 
+    // Regions of the pixel buffer that need to be uploaded to the texture
+    dirty: Mutex<DirtyRegions>,
+
+    // Whether anything changed since the last successful render
+    redraw: AtomicBool,
+
     // The inverse of the scaling matrix used by the renderer
     // Used to convert physical coordinates back to pixel coordinates (for the mouse)
     scaling_matrix_inverse: ultraviolet::Mat4,
+
+    // Captures every rendered frame while recording
+    #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+    recorder: Mutex<Option<record::Recorder>>,
 }
 
 /// All the ways in which creating a pixel buffer can fail.
@@ -139,12 +338,36 @@ pub enum Error {
     /// Equivalent to [`wgpu::CreateSurfaceError`]
     #[error("Unable to create a surface.")]
     CreateSurface(#[from] wgpu::CreateSurfaceError),
+    /// The adapter given to [`PixelsBuilder::with_device`] cannot present to the surface
+    #[error("The `wgpu::Adapter` does not support the surface.")]
+    SurfaceNotSupported,
+    /// The GPU device was lost, see [`Pixels::recreate_device`]
+    #[error("The GPU device was lost.")]
+    DeviceLost,
+    /// The GPU device belongs to the application and cannot be recreated by pixels, see
+    /// [`Pixels::recreate_device_with`]
+    #[error("The GPU device belongs to the application and cannot be recreated.")]
+    ExternalDevice,
     /// Equivalent to [`TextureError`]
     #[error("Texture creation failed: {0}")]
     InvalidTexture(#[from] TextureError),
+    /// Post-processing passes run at the surface size, so [`Pixels::render_to`] needs a render
+    /// target of the same size
+    #[error("Post-processing passes need a render target of {0}x{1} pixels.")]
+    PassTargetSize(u32, u32),
+    /// The texture format cannot be converted to `RGBA` when reading back a rendered frame
+    #[error("Texture format {0:?} cannot be read back.")]
+    UnsupportedFormat(wgpu::TextureFormat),
+    /// Equivalent to [`wgpu::BufferAsyncError`]
+    #[error("Unable to map a buffer for reading.")]
+    BufferAsync(#[from] wgpu::BufferAsyncError),
     /// User-defined error from custom render function
     #[error("User-defined error.")]
     UserDefined(#[from] DynError),
+    /// Equivalent to [`png::EncodingError`]
+    #[cfg(feature = "screenshot")]
+    #[error("Unable to save the screenshot.")]
+    Screenshot(#[from] png::EncodingError),
 }
 
 type DynError = Box<dyn std::error::Error + Send + Sync + 'static>;
@@ -159,6 +382,13 @@ pub enum TextureError {
     /// Unable to create a backing texture; Height is either 0 or greater than GPU limits
     #[error("Texture height is invalid: {0}")]
     TextureHeight(u32),
+    /// Unable to create a layer texture; The format cannot be sampled as a floating point texture
+    #[error("Texture format is not supported: {0:?}")]
+    TextureFormat(wgpu::TextureFormat),
+    /// Unable to create a layer; The pixel data for the given width and height does not fit in
+    /// memory
+    #[error("Texture size is too large: {0}x{1}")]
+    TextureSize(u32, u32),
 }
 
 impl<W: wgpu::WindowHandle> SurfaceTexture<W> {
@@ -195,6 +425,51 @@ impl<W: wgpu::WindowHandle> SurfaceTexture<W> {
     }
 }
 
+impl ScalingFilter {
+    /// The fragment shader entry point in `filter.wgsl`, or `None` for the default shaders.
+    const fn entry_point(self) -> Option<&'static str> {
+        match self {
+            ScalingFilter::Nearest => None,
+            ScalingFilter::SharpBilinear => Some("fs_sharp_bilinear"),
+            ScalingFilter::Scale2x => Some("fs_scale2x"),
+            ScalingFilter::Scale3x => Some("fs_scale3x"),
+            ScalingFilter::Xbr => Some("fs_xbr"),
+            ScalingFilter::Hqx => Some("fs_hqx"),
+            ScalingFilter::Bicubic => Some("fs_bicubic"),
+            ScalingFilter::Lanczos => Some("fs_lanczos"),
+        }
+    }
+}
+
+impl Anchor {
+    /// The fraction of the free space on each axis that is placed before the scaled buffer.
+    const fn factors(self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+impl HasWindowHandle for Headless {
+    fn window_handle(&self) -> Result<raw_window_handle::WindowHandle<'_>, HandleError> {
+        match *self {}
+    }
+}
+
+impl HasDisplayHandle for Headless {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        match *self {}
+    }
+}
+
 impl<'win> Pixels<'win> {
     /// Create a pixel buffer instance with default options.
     ///
@@ -303,6 +578,7 @@ impl<'win> Pixels<'win> {
     /// ```
     pub fn clear_color(&mut self, color: wgpu::Color) {
         self.context.scaling_renderer.clear_color = color;
+        self.update_scaling_matrix();
     }
 
     /// Set the scaling mode.
@@ -319,7 +595,276 @@ impl<'win> Pixels<'win> {
     /// # Ok::<(), pixels::Error>(())
     /// ```
     pub fn set_scaling_mode(&mut self, scaling_mode: ScalingMode) {
-        self.context.scaling_renderer.scaling_mode = scaling_mode;
+        self.context.scaling_renderer.options.scaling_mode = scaling_mode;
+        self.update_scaling_matrix();
+    }
+
+    /// Set the scaling filter.
+    ///
+    /// Controls how the pixel buffer is sampled when it is scaled to the screen. See
+    /// [`ScalingFilter`] for the available filters.
+    ///
+    /// ```no_run
+    /// # use pixels::{Pixels, ScalingFilter};
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1280, 960, &window);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// // Smooth the diagonal edges of the pixel art.
+    /// pixels.set_scaling_filter(ScalingFilter::Xbr);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn set_scaling_filter(&mut self, filter: ScalingFilter) {
+        self.context
+            .scaling_renderer
+            .set_filter(&self.context.device, filter);
+        *self.redraw.get_mut() = true;
+    }
+
+    /// Get the current scaling filter.
+    pub fn scaling_filter(&self) -> ScalingFilter {
+        self.context.scaling_renderer.options.filter
+    }
+
+    /// Replace the scaling shader with a WGSL file, which is recompiled by
+    /// [`Pixels::reload_shaders`] when it is modified.
+    ///
+    /// The shader has the same interface as the default scaling shader: a `vs_main` vertex entry
+    /// point and an `fs_main` fragment entry point, with the pixel buffer texture, a sampler, and
+    /// the scaling uniforms in bind group 0. Copy `shaders/scale.wgsl` from the `pixels` source
+    /// code as a starting point. The shader takes precedence over the [`ScalingFilter`].
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, &window);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// pixels.set_scaling_shader("shaders/my_scale.wgsl")?;
+    ///
+    /// // Once per frame
+    /// if let Err(err) = pixels.reload_shaders() {
+    ///     eprintln!("{err}");
+    /// }
+    /// pixels.render()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the file cannot be read or the shader does not compile. The current
+    /// scaling shader is kept in that case.
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    pub fn set_scaling_shader<P: AsRef<std::path::Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<(), shader::ShaderError> {
+        self.context
+            .scaling_renderer
+            .set_shader(&self.context.device, Some(path.as_ref()))?;
+        *self.redraw.get_mut() = true;
+
+        Ok(())
+    }
+
+    /// Restore the default scaling shaders after [`Pixels::set_scaling_shader`].
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    pub fn reset_scaling_shader(&mut self) {
+        // Restoring the default shaders cannot fail
+        let _ = self
+            .context
+            .scaling_renderer
+            .set_shader(&self.context.device, None);
+        *self.redraw.get_mut() = true;
+    }
+
+    /// Recompile any shaders whose files were modified since they were last compiled.
+    ///
+    /// This covers the scaling shader set with [`Pixels::set_scaling_shader`], and every pass added
+    /// with [`Pixels::add_pass`] through [`RenderPass::reload_shaders`]. Checking for modifications
+    /// is cheap, so this can be called once per frame during development.
+    ///
+    /// Returns `true` when any shader was recompiled.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error when a shader file cannot be read or fails to compile. Every shader
+    /// is still checked, and shaders with errors keep their previous pipelines.
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    pub fn reload_shaders(&mut self) -> Result<bool, shader::ShaderError> {
+        let device = &self.context.device;
+        let results = std::iter::once(self.context.scaling_renderer.reload_shader(device))
+            .chain(self.context.passes.reload_shaders(device));
+
+        let mut reloaded = false;
+        let mut error = None;
+        for result in results {
+            match result {
+                Ok(changed) => reloaded |= changed,
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        if reloaded {
+            *self.redraw.get_mut() = true;
+        }
+
+        error.map_or(Ok(reloaded), Err)
+    }
+
+    /// Set the pixel aspect ratio to simulate non-square pixels.
+    ///
+    /// See [`PixelsBuilder::pixel_aspect_ratio`] for more information.
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1280, 800, &window);
+    /// let mut pixels = Pixels::new(320, 200, surface_texture)?;
+    /// // Display the buffer with a 4:3 aspect ratio.
+    /// pixels.set_pixel_aspect_ratio(5.0 / 6.0);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// The aspect ratio must be > 0.
+    pub fn set_pixel_aspect_ratio(&mut self, pixel_aspect_ratio: f64) {
+        assert!(pixel_aspect_ratio > 0.0);
+
+        self.context.scaling_renderer.options.pixel_aspect_ratio = pixel_aspect_ratio as f32;
+        self.update_scaling_matrix();
+    }
+
+    /// Set the anchor for the scaled pixel buffer.
+    ///
+    /// Controls where the pixel buffer is placed on the surface. The default is
+    /// [`Anchor::Center`]. See [`PixelsBuilder::anchor`] for more information.
+    ///
+    /// ```no_run
+    /// # use pixels::{Anchor, Pixels};
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1920, 1080, &window);
+    /// let mut pixels = Pixels::new(640, 480, surface_texture)?;
+    /// // Keep the buffer in the top-left corner, leaving space for a side panel on the right.
+    /// pixels.set_anchor(Anchor::TopLeft);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn set_anchor(&mut self, anchor: Anchor) {
+        self.context.scaling_renderer.options.anchor = anchor;
+        self.update_scaling_matrix();
+    }
+
+    /// Set an offset in physical pixels for the scaled pixel buffer, relative to its anchor.
+    ///
+    /// Positive values move the buffer right and down, regardless of the anchor. The default is
+    /// `(0, 0)`. See [`PixelsBuilder::anchor_offset`] for more information.
+    pub fn set_anchor_offset(&mut self, x: i32, y: i32) {
+        self.context.scaling_renderer.options.anchor_offset = (x, y);
+        self.update_scaling_matrix();
+    }
+
+    /// Set the viewport, restricting the pixel buffer to a rectangle of the surface.
+    ///
+    /// The rectangle is given in physical pixels as `(x, y, width, height)`. Scaling, anchoring,
+    /// clearing, and [`Pixels::window_pos_to_pixel`] all operate within the viewport, as if it
+    /// were the whole surface. The rest of the surface is not cleared or drawn to by the
+    /// [`ScalingRenderer`], leaving room for other content like GUI panels.
+    ///
+    /// The viewport is kept when the surface is resized with [`Pixels::resize_surface`], and any
+    /// part of it outside of the surface is clipped. Use [`Pixels::reset_viewport`] to draw to the
+    /// whole surface again.
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1280, 720, &window);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// // Leave a 320 pixel wide side panel on the right.
+    /// pixels.set_viewport(0, 0, 960, 720);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when `width` or `height` are 0.
+    pub fn set_viewport(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        assert!(width > 0);
+        assert!(height > 0);
+
+        self.context.scaling_renderer.options.viewport = Some((x, y, width, height));
+        self.update_scaling_matrix();
+    }
+
+    /// Remove the viewport, so the pixel buffer is scaled to the whole surface.
+    ///
+    /// This is the default. See [`Pixels::set_viewport`].
+    pub fn reset_viewport(&mut self) {
+        self.context.scaling_renderer.options.viewport = None;
+        self.update_scaling_matrix();
+    }
+
+    /// Get the viewport as `(x, y, width, height)` in physical pixels.
+    ///
+    /// This is the viewport set by [`Pixels::set_viewport`] clipped to the surface, or the whole
+    /// surface when no viewport is set.
+    pub fn viewport(&self) -> (u32, u32, u32, u32) {
+        self.context.scaling_renderer.viewport()
+    }
+
+    /// Set colors in the palette of an indexed color pixel buffer, starting at index 0.
+    ///
+    /// Indexed color pixel buffers are created with the [`wgpu::TextureFormat::R8Uint`] texture
+    /// format (see [`PixelsBuilder::texture_format`]). Each byte in the pixel buffer is an index
+    /// into a 256 entry palette of `RGBA` colors, which is looked up on the GPU. The default
+    /// palette is a grayscale ramp.
+    ///
+    /// Palette entries beyond `colors.len()` are not changed. Since the pixel buffer does not need
+    /// to be touched, this is an efficient way to implement palette cycling effects.
+    ///
+    /// ```no_run
+    /// # use pixels::PixelsBuilder;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, &window);
+    /// let mut pixels = PixelsBuilder::new(320, 240, surface_texture)
+    ///     .texture_format(pixels::wgpu::TextureFormat::R8Uint)
+    ///     .build()?;
+    ///
+    /// // Black, red, green, and blue
+    /// pixels.set_palette(&[
+    ///     [0x00, 0x00, 0x00, 0xff],
+    ///     [0xff, 0x00, 0x00, 0xff],
+    ///     [0x00, 0xff, 0x00, 0xff],
+    ///     [0x00, 0x00, 0xff, 0xff],
+    /// ]);
+    ///
+    /// // Draw a red pixel
+    /// pixels.frame_mut()[0] = 1;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when the pixel buffer is not indexed, or when `colors` has more than 256 entries.
+    pub fn set_palette(&mut self, colors: &[[u8; 4]]) {
+        let palette_renderer = self
+            .context
+            .palette_renderer
+            .as_mut()
+            .expect("The pixel buffer is not indexed");
+        assert!(colors.len() <= 256);
+
+        palette_renderer.set_colors(&self.context.queue, colors);
+        *self.redraw.get_mut() = true;
+    }
+
+    /// Get the palette of an indexed color pixel buffer, or `None` when it is not indexed.
+    ///
+    /// See [`Pixels::set_palette`].
+    pub fn palette(&self) -> Option<&[[u8; 4]; 256]> {
+        self.context
+            .palette_renderer
+            .as_ref()
+            .map(PaletteRenderer::colors)
     }
 
     /// Returns a reference of the `wgpu` adapter used by the crate.
@@ -337,7 +882,7 @@ impl<'win> Pixels<'win> {
     /// # Ok::<(), pixels::Error>(())
     /// ```
     pub fn adapter(&self) -> &wgpu::Adapter {
-        &self.adapter
+        &self.context.adapter
     }
 
     /// Resize the pixel buffer and zero its contents.
@@ -357,34 +902,106 @@ impl<'win> Pixels<'win> {
     /// - [`TextureError::TextureWidth`] when `width` is 0 or greater than GPU texture limits.
     /// - [`TextureError::TextureHeight`] when `height` is 0 or greater than GPU texture limits.
     pub fn resize_buffer(&mut self, width: u32, height: u32) -> Result<(), TextureError> {
-        // Recreate the backing texture
-        let (scaling_matrix_inverse, texture_extent, texture, scaling_renderer, pixels_buffer_size) =
-            builder::create_backing_texture(
-                &self.context.device,
-                // Backing texture values
-                width,
-                height,
-                self.context.texture_format,
-                // Render texture values
-                &self.surface_size,
-                self.render_texture_format,
-                self.context.scaling_renderer.clear_color,
-                self.blend_state,
-                self.context.scaling_renderer.scaling_mode,
-            )?;
-
-        self.scaling_matrix_inverse = scaling_matrix_inverse;
-        self.context.texture_extent = texture_extent;
-        self.context.texture = texture;
-        self.context.scaling_renderer = scaling_renderer;
+        let pixels_buffer_size = self.recreate_backing_texture(width, height)?;
+
+        // Composite the layers over the new texture
+        let texture = self.source_texture();
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let source = layers::Source {
+            view: &view,
+            format: texture.format(),
+            size: texture.size(),
+        };
+        self.context.layers.resize(&self.context.device, &source);
+        if !self.context.layers.is_empty() {
+            self.connect_layers();
+        }
 
         // Resize the pixel buffer
         self.pixels
             .resize_with(pixels_buffer_size, Default::default);
+        *self.dirty.get_mut().unwrap() = DirtyRegions::new(width, height);
+        *self.redraw.get_mut() = true;
 
         Ok(())
     }
 
+    /// Recreate the backing texture and the renderers that depend on it, keeping the palette.
+    ///
+    /// Returns the size of the pixel buffer in bytes.
+    fn recreate_backing_texture(&mut self, width: u32, height: u32) -> Result<usize, TextureError> {
+        let backing = self.create_backing_texture(
+            &self.context.device,
+            &self.context.queue,
+            &self.context.pipeline_cache,
+            width,
+            height,
+        )?;
+
+        Ok(self.set_backing_texture(backing))
+    }
+
+    /// Create a backing texture and the renderers that depend on it on `device`, with the palette
+    /// colors of the current palette renderer.
+    fn create_backing_texture(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipeline_cache: &PipelineCache,
+        width: u32,
+        height: u32,
+    ) -> Result<builder::BackingTexture, TextureError> {
+        let mut backing = builder::create_backing_texture(
+            device,
+            queue,
+            pipeline_cache,
+            // Backing texture values
+            width,
+            height,
+            self.context.texture_format,
+            // Render texture values
+            &self.surface_size,
+            self.render_texture_format,
+            self.context.scaling_renderer.clear_color,
+            self.blend_state,
+            self.context.scaling_renderer.options,
+        )?;
+
+        // Keep the palette colors
+        if let (Some(new), Some(old)) = (
+            &mut backing.palette_renderer,
+            &self.context.palette_renderer,
+        ) {
+            new.set_colors(queue, old.colors());
+        }
+
+        Ok(backing)
+    }
+
+    /// Replace the backing texture and the renderers that depend on it.
+    ///
+    /// Returns the size of the pixel buffer in bytes.
+    fn set_backing_texture(&mut self, backing: builder::BackingTexture) -> usize {
+        let builder::BackingTexture {
+            scaling_matrix_inverse,
+            texture_extent,
+            texture,
+            palette_renderer,
+            scaling_renderer,
+            pixels_buffer_size,
+        } = backing;
+
+        self.scaling_matrix_inverse = scaling_matrix_inverse;
+        self.context.texture_extent = texture_extent;
+        self.context.texture = texture;
+        self.context.scaling_renderer = scaling_renderer;
+        self.context.palette_renderer = palette_renderer;
+        #[cfg(feature = "crt")]
+        self.update_crt();
+
+        pixels_buffer_size
+    }
+
     /// Resize the surface upon which the pixel buffer texture is rendered.
     ///
     /// This does not resize the pixel buffer. Use [`Pixels::resize_buffer`] to change the size of
@@ -403,32 +1020,59 @@ impl<'win> Pixels<'win> {
     /// - [`TextureError::TextureHeight`] when `height` is 0 or greater than GPU texture limits.
     pub fn resize_surface(&mut self, width: u32, height: u32) -> Result<(), TextureError> {
         check_texture_size(&self.context.device, width, height)?;
+        self.set_surface_size(width, height);
+        self.reconfigure_surface();
+
+        Ok(())
+    }
 
+    /// Update the surface size and everything that depends on it, except for the window surface
+    /// itself.
+    fn set_surface_size(&mut self, width: u32, height: u32) {
         // Update SurfaceTexture dimensions
         self.surface_size.width = width;
         self.surface_size.height = height;
 
-        // Update ScalingMatrix for mouse transformation
+        // Recreate the headless render target
+        if let RenderTarget::Headless(texture) = &mut self.context.target {
+            *texture = builder::create_render_target(
+                &self.context.device,
+                &self.surface_size,
+                self.surface_texture_format,
+            );
+        }
+
+        // Update state for all render passes
+        self.update_scaling_matrix();
+        self.context
+            .passes
+            .resize(&self.context.device, &self.context.queue, width, height);
+    }
+
+    /// Update the scaling renderer and the inverse matrix for mouse transformation.
+    ///
+    /// Call this when the surface size or any scaling option changes.
+    fn update_scaling_matrix(&mut self) {
+        let width = self.surface_size.width;
+        let height = self.surface_size.height;
+
         self.scaling_matrix_inverse = renderers::ScalingMatrix::new(
             (
                 self.context.texture_extent.width as f32,
                 self.context.texture_extent.height as f32,
             ),
             (width as f32, height as f32),
-            self.context.scaling_renderer.scaling_mode,
+            &self.context.scaling_renderer.options,
         )
         .transform
         .inversed();
 
-        // Reconfigure the surface
-        self.reconfigure_surface();
-
-        // Update state for all render passes
         self.context
             .scaling_renderer
             .resize(&self.context.queue, width, height);
-
-        Ok(())
+        #[cfg(feature = "crt")]
+        self.update_crt();
+        *self.redraw.get_mut() = true;
     }
 
     /// Enable or disable Vsync.
@@ -464,56 +1108,79 @@ impl<'win> Pixels<'win> {
         self.reconfigure_surface();
     }
 
-    /// Draw this pixel buffer to the configured [`SurfaceTexture`].
+    /// Add a post-processing pass to the end of the chain run by [`Pixels::render`].
     ///
-    /// # Errors
+    /// The scaling renderer draws to an intermediate texture owned by `Pixels`, then each pass
+    /// samples the output of the one before it. The last pass draws to the surface. The
+    /// intermediate textures are resized with the surface, and [`RenderPass::resize`] is called on
+    /// every pass when that happens. It is also called on `pass` before this method returns.
     ///
-    /// Returns an error when [`wgpu::Surface::get_current_texture`] fails.
+    /// Use [`PixelsContext::render_passes`] to run the chain from [`Pixels::render_with`].
     ///
     /// # Example
     ///
     /// ```no_run
-    /// # use pixels::Pixels;
+    /// # use pixels::{PassContext, Pixels, RenderPass};
     /// # let window = pixels_mocks::Window;
     /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, &window);
-    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// struct Invert {
+    ///     // Pipeline, sampler, bind group, ...
+    /// }
     ///
-    /// // Clear the pixel buffer
-    /// let frame = pixels.frame_mut();
-    /// for pixel in frame.chunks_exact_mut(4) {
-    ///     pixel[0] = 0x00; // R
-    ///     pixel[1] = 0x00; // G
-    ///     pixel[2] = 0x00; // B
-    ///     pixel[3] = 0xff; // A
+    /// impl RenderPass for Invert {
+    ///     fn render(
+    ///         &self,
+    ///         encoder: &mut pixels::wgpu::CommandEncoder,
+    ///         output: &pixels::wgpu::TextureView,
+    ///         context: &PassContext<'_>,
+    ///     ) {
+    ///         // Sample `context.input` and draw to `output`
+    ///     }
     /// }
     ///
-    /// // Draw it to the `SurfaceTexture`
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// pixels.add_pass(Box::new(Invert {}));
     /// pixels.render()?;
     /// # Ok::<(), pixels::Error>(())
     /// ```
-    pub fn render(&self) -> Result<(), Error> {
-        self.render_with(|encoder, render_target, context| {
-            context.scaling_renderer.render(encoder, render_target);
-
-            Ok(())
-        })
+    pub fn add_pass(&mut self, pass: Box<dyn RenderPass>) {
+        self.context
+            .passes
+            .push(&self.context.device, &self.context.queue, pass);
+        *self.redraw.get_mut() = true;
     }
 
-    /// Draw this pixel buffer to the configured [`SurfaceTexture`] using a custom user-provided
-    /// render function.
+    /// Remove all post-processing passes, returning them in order.
     ///
-    /// Provides access to a [`wgpu::CommandEncoder`], a [`wgpu::TextureView`] from the surface
-    /// which you can use to render to the screen, and a [`PixelsContext`] with all of the internal
-    /// `wgpu` context.
+    /// The scaling renderer draws directly to the surface again, and the intermediate textures are
+    /// released.
+    pub fn clear_passes(&mut self) -> Vec<Box<dyn RenderPass>> {
+        *self.redraw.get_mut() = true;
+
+        self.context
+            .passes
+            .clear(&self.context.device, &self.context.queue)
+    }
+
+    /// The number of post-processing passes added with [`Pixels::add_pass`].
+    pub fn pass_count(&self) -> usize {
+        self.context.passes.len()
+    }
+
+    /// Add a layer above the pixel buffer and all other layers.
     ///
-    /// The render function must return a `Result`. This allows fallible render functions to be
-    /// handled gracefully. The boxed `Error` will be made available in the [`Error::UserDefined`]
-    /// variant returned by `render_with()`.
+    /// The layer has its own `width` by `height` pixel buffer in the given `texture_format`,
+    /// which is cleared to transparent. A layer with the same name is replaced, keeping its place
+    /// in the drawing order. Layers are composited over the pixel buffer on the GPU before
+    /// scaling, so the layers are cropped to the size of the pixel buffer.
     ///
     /// # Errors
     ///
-    /// Returns an error when either [`wgpu::Surface::get_current_texture`] or the provided render
-    /// function fails.
+    /// - [`TextureError::TextureWidth`] when `width` is 0 or greater than GPU texture limits.
+    /// - [`TextureError::TextureHeight`] when `height` is 0 or greater than GPU texture limits.
+    /// - [`TextureError::TextureFormat`] when the format is compressed, or is not sampled as
+    ///   floating point values, e.g. `R8Uint`.
+    /// - [`TextureError::TextureSize`] when the pixel data does not fit in the address space.
     ///
     /// # Example
     ///
@@ -521,18 +1188,281 @@ impl<'win> Pixels<'win> {
     /// # use pixels::Pixels;
     /// # let window = pixels_mocks::Window;
     /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, &window);
+    /// use pixels::{wgpu::TextureFormat, BlendMode};
+    ///
     /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
     ///
-    /// // Clear the pixel buffer
-    /// let frame = pixels.frame_mut();
-    /// for pixel in frame.chunks_exact_mut(4) {
-    ///     pixel[0] = 0x00; // R
-    ///     pixel[1] = 0x00; // G
-    ///     pixel[2] = 0x00; // B
-    ///     pixel[3] = 0xff; // A
-    /// }
+    /// // A large background that scrolls, and a translucent HUD
+    /// pixels.add_layer("background", 1024, 240, TextureFormat::Rgba8UnormSrgb)?;
+    /// let hud = pixels.add_layer("hud", 320, 16, TextureFormat::Rgba8UnormSrgb)?;
+    /// hud.set_opacity(0.75);
     ///
-    /// // Draw it to the `SurfaceTexture`
+    /// // Later, once per frame
+    /// let background = pixels.layer_mut("background").unwrap();
+    /// background.set_offset(-16, 0);
+    /// background.set_blend_mode(BlendMode::Normal);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn add_layer(
+        &mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        texture_format: wgpu::TextureFormat,
+    ) -> Result<&mut Layer, TextureError> {
+        let texture = self.source_texture();
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let source = layers::Source {
+            view: &view,
+            format: texture.format(),
+            size: texture.size(),
+        };
+        let connect = self.context.layers.is_empty();
+        self.context.layers.insert(
+            &self.context.device,
+            &self.context.pipeline_cache,
+            &source,
+            name,
+            width,
+            height,
+            texture_format,
+        )?;
+        if connect {
+            self.connect_layers();
+        }
+        *self.redraw.get_mut() = true;
+
+        Ok(self.context.layers.get_mut(name).unwrap())
+    }
+
+    /// Remove the layer with the given name, returning it.
+    pub fn remove_layer(&mut self, name: &str) -> Option<Layer> {
+        let layer = self.context.layers.remove(name)?;
+        if self.context.layers.is_empty() {
+            self.connect_layers();
+        }
+        *self.redraw.get_mut() = true;
+
+        Some(layer)
+    }
+
+    /// Get the layer with the given name.
+    pub fn layer(&self, name: &str) -> Option<&Layer> {
+        self.context.layers.get(name)
+    }
+
+    /// Get the layer with the given name for drawing or changing its options.
+    ///
+    /// Accessing a layer this way marks the frame for redrawing, see [`Pixels::needs_redraw`].
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut Layer> {
+        let layer = self.context.layers.get_mut(name)?;
+        *self.redraw.get_mut() = true;
+
+        Some(layer)
+    }
+
+    /// Iterate over the layers in drawing order, from the bottom to the top.
+    pub fn layers(&self) -> impl Iterator<Item = &Layer> + '_ {
+        self.context.layers.iter()
+    }
+
+    /// The texture that the scaling renderer samples when there are no layers.
+    fn source_texture(&self) -> &wgpu::Texture {
+        self.context
+            .palette_renderer
+            .as_ref()
+            .map_or(&self.context.texture, PaletteRenderer::output_texture)
+    }
+
+    /// Point the scaling renderer at the composited layers, or at the pixel buffer when there are
+    /// no layers.
+    fn connect_layers(&mut self) {
+        let view = self
+            .source_texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let input = self.context.layers.output_view().unwrap_or(&view);
+        self.context
+            .scaling_renderer
+            .set_input(&self.context.device, input);
+    }
+
+    /// Enable, adjust, or disable the CRT display simulation.
+    ///
+    /// The [`CrtRenderer`] runs after all passes added with [`Pixels::add_pass`]. Changing the
+    /// options while it is enabled only updates a uniform buffer, so it is cheap to do every frame.
+    /// Pass `None` to disable it.
+    ///
+    /// [`Pixels::window_pos_to_pixel`] follows the curvature, so the mouse maps to the pixel that is
+    /// displayed under it.
+    ///
+    /// ```no_run
+    /// # use pixels::{CrtOptions, Pixels};
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1280, 960, &window);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// let mut options = CrtOptions::default();
+    /// options.curvature = 0.1;
+    /// pixels.set_crt(Some(options));
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    #[cfg(feature = "crt")]
+    pub fn set_crt(&mut self, options: Option<CrtOptions>) {
+        let device = &self.context.device;
+        let queue = &self.context.queue;
+        let pipeline_cache = &self.context.pipeline_cache;
+        let passes = &mut self.context.passes;
+        match (passes.crt_mut(), options) {
+            (Some(crt), Some(options)) => crt.set_options(queue, options),
+            (None, None) => return,
+            (_, options) => {
+                let crt = options.map(|options| {
+                    CrtRenderer::new(device, pipeline_cache, self.render_texture_format, options)
+                });
+                passes.set_crt(device, queue, crt);
+                self.update_crt();
+            }
+        }
+        *self.redraw.get_mut() = true;
+    }
+
+    /// Get the CRT display simulation options, or `None` when it is disabled.
+    #[cfg(feature = "crt")]
+    pub fn crt(&self) -> Option<&CrtOptions> {
+        self.context.passes.crt().map(CrtRenderer::options)
+    }
+
+    /// Update the placement of the scaled pixel buffer for the CRT renderer.
+    #[cfg(feature = "crt")]
+    fn update_crt(&mut self) {
+        let Some(crt) = self.context.passes.crt_mut() else {
+            return;
+        };
+
+        let texture_size = (
+            self.context.texture_extent.width as f32,
+            self.context.texture_extent.height as f32,
+        );
+        let surface_size = (
+            self.surface_size.width as f32,
+            self.surface_size.height as f32,
+        );
+        let matrix = renderers::ScalingMatrix::new(
+            texture_size,
+            surface_size,
+            &self.context.scaling_renderer.options,
+        );
+        crt.update(
+            &self.context.queue,
+            &matrix,
+            texture_size,
+            surface_size,
+            self.context.scaling_renderer.clear_color,
+        );
+    }
+
+    /// Draw this pixel buffer to the configured [`SurfaceTexture`].
+    ///
+    /// Any post-processing passes added with [`Pixels::add_pass`] are run after scaling.
+    ///
+    /// Headless pixel buffers are drawn to their internal texture instead. See
+    /// [`PixelsBuilder::new_headless`].
+    ///
+    /// # Surface errors
+    ///
+    /// Errors from [`wgpu::Surface::get_current_texture`] are handled as follows:
+    ///
+    /// - [`wgpu::SurfaceError::Timeout`]: the frame is skipped, and `Ok(())` is returned. The
+    ///   changes are drawn by the next call.
+    /// - [`wgpu::SurfaceError::Outdated`] and [`wgpu::SurfaceError::Lost`]: the surface is
+    ///   reconfigured and the frame is acquired again. The frame is skipped when that fails too,
+    ///   e.g. while the window is minimized.
+    /// - [`wgpu::SurfaceError::OutOfMemory`]: returned as [`Error::Surface`]. This is fatal, and
+    ///   the application should exit.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Surface`] when the GPU is out of memory, see above.
+    /// - [`Error::DeviceLost`] when the device was lost. Call [`Pixels::recreate_device`] to
+    ///   continue rendering.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, &window);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // Clear the pixel buffer
+    /// let frame = pixels.frame_mut();
+    /// for pixel in frame.chunks_exact_mut(4) {
+    ///     pixel[0] = 0x00; // R
+    ///     pixel[1] = 0x00; // G
+    ///     pixel[2] = 0x00; // B
+    ///     pixel[3] = 0xff; // A
+    /// }
+    ///
+    /// // Draw it to the `SurfaceTexture`
+    /// match pixels.render() {
+    ///     Ok(()) => (),
+    ///     Err(pixels::Error::DeviceLost) => pixels.recreate_device()?,
+    ///     Err(err) => return Err(err),
+    /// }
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn render(&self) -> Result<(), Error> {
+        self.present().map(drop)
+    }
+
+    /// Draw this pixel buffer with all passes, see [`Pixels::render`].
+    ///
+    /// Returns `false` when the frame was skipped.
+    fn present(&self) -> Result<bool, Error> {
+        self.present_with(|encoder, render_target, context| {
+            context.render_passes(encoder, render_target);
+
+            Ok(())
+        })
+    }
+
+    /// Draw this pixel buffer to the configured [`SurfaceTexture`] using a custom user-provided
+    /// render function.
+    ///
+    /// Provides access to a [`wgpu::CommandEncoder`], a [`wgpu::TextureView`] from the surface
+    /// which you can use to render to the screen, and a [`PixelsContext`] with all of the internal
+    /// `wgpu` context.
+    ///
+    /// The render function must return a `Result`. This allows fallible render functions to be
+    /// handled gracefully. The boxed `Error` will be made available in the [`Error::UserDefined`]
+    /// variant returned by `render_with()`.
+    ///
+    /// Surface errors are handled the same way as in [`Pixels::render`]. The render function is
+    /// not called when the frame is skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the GPU is out of memory, when the device was lost, or when the
+    /// provided render function fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, &window);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // Clear the pixel buffer
+    /// let frame = pixels.frame_mut();
+    /// for pixel in frame.chunks_exact_mut(4) {
+    ///     pixel[0] = 0x00; // R
+    ///     pixel[1] = 0x00; // G
+    ///     pixel[2] = 0x00; // B
+    ///     pixel[3] = 0xff; // A
+    /// }
+    ///
+    /// // Draw it to the `SurfaceTexture`
     /// pixels.render_with(|encoder, render_target, context| {
     ///     context.scaling_renderer.render(encoder, render_target);
     ///     // etc...
@@ -548,59 +1478,561 @@ impl<'win> Pixels<'win> {
             &PixelsContext,
         ) -> Result<(), DynError>,
     {
-        let frame = self.context.surface.get_current_texture().or_else(|_| {
-            // Reconfigure the surface and retry immediately on any error.
-            // See https://github.com/parasyte/pixels/issues/121
-            // See https://github.com/parasyte/pixels/issues/346
-            self.reconfigure_surface();
-            self.context.surface.get_current_texture()
-        })?;
-        let mut encoder =
-            self.context
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("pixels_command_encoder"),
-                });
+        self.present_with(render_function).map(drop)
+    }
+
+    /// Draw this pixel buffer with a render function, see [`Pixels::render_with`].
+    ///
+    /// Returns `false` when the frame was skipped.
+    fn present_with<F>(&self, render_function: F) -> Result<bool, Error>
+    where
+        F: FnOnce(
+            &mut wgpu::CommandEncoder,
+            &wgpu::TextureView,
+            &PixelsContext,
+        ) -> Result<(), DynError>,
+    {
+        if self.is_device_lost() {
+            return Err(Error::DeviceLost);
+        }
+
+        match &self.context.target {
+            RenderTarget::Surface(surface) => {
+                let Some(frame) = self.acquire_frame(surface)? else {
+                    // Skip this frame; the next call tries again
+                    return Ok(false);
+                };
+                self.render_to_texture(&frame.texture, render_function)?;
+                // Present the frame before reporting capture errors
+                #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+                let captured = self.record_frame(&frame.texture);
+                frame.present();
+                self.redraw.store(false, Ordering::Relaxed);
+                #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+                captured?;
+            }
+            RenderTarget::Headless(texture) => {
+                self.render_to_texture(texture, render_function)?;
+                self.redraw.store(false, Ordering::Relaxed);
+                #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+                self.record_frame(texture)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Acquire the next surface frame, following the policy described in [`Pixels::render`].
+    ///
+    /// Returns `None` when the frame should be skipped.
+    fn acquire_frame(
+        &self,
+        surface: &wgpu::Surface<'win>,
+    ) -> Result<Option<wgpu::SurfaceTexture>, Error> {
+        let frame = match surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Timeout) => return Ok(None),
+            Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                // Reconfigure the surface and retry immediately.
+                // See https://github.com/parasyte/pixels/issues/121
+                // See https://github.com/parasyte/pixels/issues/346
+                self.reconfigure_surface();
+                match surface.get_current_texture() {
+                    Ok(frame) => frame,
+                    // E.g. minimized windows stay outdated until they are restored
+                    Err(
+                        wgpu::SurfaceError::Timeout
+                        | wgpu::SurfaceError::Outdated
+                        | wgpu::SurfaceError::Lost,
+                    ) => return Ok(None),
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Some(frame))
+    }
+
+    /// Draw this pixel buffer to a texture view owned by the caller, e.g. to embed it as a widget
+    /// in another `wgpu` application.
+    ///
+    /// The pixel buffer is uploaded, and the scaling renderer and all post-processing passes are
+    /// recorded into the given `encoder`. The caller is responsible for submitting the encoder to
+    /// the queue. The window surface is neither acquired nor presented, and the frame is not
+    /// recorded. Use a pixel buffer that shares the device of the application, see
+    /// [`PixelsBuilder::with_device`].
+    ///
+    /// The `render_target` must have the [`Pixels::render_texture_format`] and the size given by
+    /// `target_size` in physical pixels. The pixel buffer is scaled to the target size with the
+    /// current scaling options, but the surface size is left unchanged: [`Pixels::render`] keeps
+    /// drawing at the surface size, and [`Pixels::window_pos_to_pixel`] keeps mapping positions
+    /// relative to the surface.
+    ///
+    /// Post-processing passes run at the surface size, so the target must have the surface size
+    /// while any pass or the CRT renderer is installed. Resize the surface with
+    /// [`Pixels::resize_surface`] to match the target.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidTexture`] when the target size is 0 or greater than GPU texture limits.
+    /// - [`Error::PassTargetSize`] when passes are installed, and the target size is not the
+    ///   surface size.
+    /// - [`Error::DeviceLost`] when the device was lost, see [`Pixels::recreate_device`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use pixels::{wgpu, Pixels, PixelsBuilder};
+    /// use std::sync::Arc;
+    ///
+    /// fn create_widget(
+    ///     instance: Arc<wgpu::Instance>,
+    ///     adapter: Arc<wgpu::Adapter>,
+    ///     device: Arc<wgpu::Device>,
+    ///     queue: Arc<wgpu::Queue>,
+    /// ) -> Result<Pixels<'static>, pixels::Error> {
+    ///     PixelsBuilder::new_headless(320, 240, 640, 480)
+    ///         .with_device(instance, adapter, device, queue)
+    ///         .build()
+    /// }
+    ///
+    /// // In the render loop of the application
+    /// fn draw_widget(
+    ///     pixels: &mut Pixels,
+    ///     widget_view: &wgpu::TextureView,
+    /// ) -> Result<(), pixels::Error> {
+    ///     let mut encoder = pixels.device().create_command_encoder(&Default::default());
+    ///     pixels.render_to(&mut encoder, widget_view, (640, 480))?;
+    ///     // Draw the rest of the application with the same encoder
+    ///     pixels.queue().submit(Some(encoder.finish()));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn render_to(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &wgpu::TextureView,
+        target_size: (u32, u32),
+    ) -> Result<(), Error> {
+        if self.is_device_lost() {
+            return Err(Error::DeviceLost);
+        }
+
+        let (width, height) = target_size;
+        check_texture_size(&self.context.device, width, height)?;
+        let surface_size = (self.surface_size.width, self.surface_size.height);
+        if !self.context.passes.is_empty() && target_size != surface_size {
+            return Err(Error::PassTargetSize(surface_size.0, surface_size.1));
+        }
+        self.context
+            .scaling_renderer
+            .resize_target(&self.context.queue, width, height);
+
+        self.prepare(encoder);
+        if self.context.passes.is_empty() {
+            self.context
+                .scaling_renderer
+                .render_target(encoder, render_target);
+        } else {
+            self.context.render_passes(encoder, render_target);
+        }
+        *self.redraw.get_mut() = false;
+
+        Ok(())
+    }
+
+    /// Draw this pixel buffer to the configured [`SurfaceTexture`], but only when something
+    /// changed since the last render.
+    ///
+    /// Returns `true` when a frame was rendered, or `false` when nothing changed and rendering
+    /// was skipped. See [`Pixels::needs_redraw`] for the changes that are tracked. Surface errors
+    /// are handled the same way as in [`Pixels::render`], and a skipped frame also returns
+    /// `false`, so that the next call tries again.
+    ///
+    /// This saves GPU and CPU time in applications that are idle most of the time. Use
+    /// [`Pixels::render`] when the window contents are lost and must be redrawn regardless, e.g.
+    /// when the window system asks for a redraw after the window was uncovered.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Surface`] when the GPU is out of memory.
+    /// - [`Error::DeviceLost`] when the device was lost. Call [`Pixels::recreate_device`] to
+    ///   continue rendering.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, &window);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// // The first frame is always rendered
+    /// assert!(pixels.render_if_dirty()?);
+    ///
+    /// // Nothing changed, so nothing is rendered
+    /// assert!(!pixels.render_if_dirty()?);
+    ///
+    /// pixels.frame_mut()[0] = 0xff;
+    /// assert!(pixels.render_if_dirty()?);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn render_if_dirty(&self) -> Result<bool, Error> {
+        if !self.needs_redraw() {
+            return Ok(false);
+        }
+
+        self.present()
+    }
+
+    /// Check whether anything changed since the last successful render.
+    ///
+    /// This is `true` after the pixel buffer was accessed with [`Pixels::frame_mut`] or marked
+    /// with [`Pixels::mark_dirty`], after either the pixel buffer or the surface was resized, and
+    /// after changing any option that affects rendering, like the scaling mode or clear color.
+    /// Rendering with [`Pixels::render`] or [`Pixels::render_with`] resets it.
+    ///
+    /// Event loops can use this to decide whether to request a redraw.
+    pub fn needs_redraw(&self) -> bool {
+        self.redraw.load(Ordering::Relaxed)
+    }
+
+    /// Upload the pixel buffer and call the render function with a view of the given texture.
+    fn render_to_texture<F>(&self, texture: &wgpu::Texture, render_function: F) -> Result<(), Error>
+    where
+        F: FnOnce(
+            &mut wgpu::CommandEncoder,
+            &wgpu::TextureView,
+            &PixelsContext,
+        ) -> Result<(), DynError>,
+    {
+        let mut encoder =
+            self.context
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("pixels_command_encoder"),
+                });
+        self.prepare(&mut encoder);
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Call the user's render function.
+        (render_function)(&mut encoder, &view, &self.context)?;
+
+        self.context.queue.submit(Some(encoder.finish()));
+        Ok(())
+    }
+
+    /// Upload the pixel buffer, and prepare the texture that the scaling renderer samples.
+    fn prepare(&self, encoder: &mut wgpu::CommandEncoder) {
+        // Upload the changed regions of the pixel buffer to the texture
+        let bytes_per_row =
+            (self.context.texture_extent.width as f32 * self.context.texture_format_size) as u32;
+        let bytes_per_pixel = self.context.texture_format_size as u64;
+        let regions = self.dirty.lock().unwrap().take();
+        for rect in regions {
+            self.context.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &self.context.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: rect.x,
+                        y: rect.y,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &self.pixels,
+                wgpu::ImageDataLayout {
+                    offset: rect.y as u64 * bytes_per_row as u64 + rect.x as u64 * bytes_per_pixel,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(rect.height),
+                },
+                wgpu::Extent3d {
+                    width: rect.width,
+                    height: rect.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        // Resolve indexed colors before the scaling renderer samples them
+        if let Some(palette_renderer) = &self.context.palette_renderer {
+            palette_renderer.render(encoder);
+        }
+
+        // Composite the layers over the pixel buffer
+        self.context.layers.render(encoder, &self.context.queue);
+    }
+
+    /// Render the pixel buffer and copy the result back to CPU memory.
+    ///
+    /// The image has the same size as the surface, and contains exactly what [`Pixels::render`]
+    /// draws: the pixel buffer after scaling, including the clear color border. Pixels are
+    /// converted from the render target format (e.g. `Bgra8UnormSrgb`) to `RGBA`.
+    ///
+    /// Headless pixel buffers render to their internal texture, which is then read back. Pixel
+    /// buffers with a window surface render to an offscreen texture of the same size instead,
+    /// since surface frames cannot be read; the surface is not touched.
+    ///
+    /// This method blocks the current thread, making it unusable on Web targets. Use
+    /// [`Pixels::read_rendered_frame_async`] for a non-blocking alternative.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when rendering fails, when the buffer cannot be mapped, or when the render
+    /// target format is not supported. Supported formats are `Rgba8Unorm`, `Bgra8Unorm` (and their
+    /// sRGB variants), `Rgb10a2Unorm`, and `Rgba16Float`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::PixelsBuilder;
+    /// let mut pixels = PixelsBuilder::new_headless(320, 240, 640, 480).build()?;
+    ///
+    /// let image = pixels.read_rendered_frame()?;
+    /// assert_eq!((image.width(), image.height()), (640, 480));
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_rendered_frame(&self) -> Result<RgbaImage, Error> {
+        pollster::block_on(self.read_rendered_frame_async())
+    }
+
+    /// Asynchronously render the pixel buffer and copy the result back to CPU memory.
+    ///
+    /// See [`Pixels::read_rendered_frame`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when rendering fails, when the buffer cannot be mapped, or when the render
+    /// target format is not supported.
+    pub async fn read_rendered_frame_async(&self) -> Result<RgbaImage, Error> {
+        self.read_rendered_frame_with_async(|encoder, render_target, context| {
+            context.render_passes(encoder, render_target);
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Render the pixel buffer with a custom render function and copy the result back to CPU
+    /// memory.
+    ///
+    /// This captures the output of the same render function passed to [`Pixels::render_with`],
+    /// including any custom render passes. See [`Pixels::read_rendered_frame`] for more
+    /// information.
+    ///
+    /// This method blocks the current thread, making it unusable on Web targets. Use
+    /// [`Pixels::read_rendered_frame_with_async`] for a non-blocking alternative.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when rendering fails, when the buffer cannot be mapped, or when the render
+    /// target format is not supported.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_rendered_frame_with<F>(&self, render_function: F) -> Result<RgbaImage, Error>
+    where
+        F: FnOnce(
+            &mut wgpu::CommandEncoder,
+            &wgpu::TextureView,
+            &PixelsContext,
+        ) -> Result<(), DynError>,
+    {
+        pollster::block_on(self.read_rendered_frame_with_async(render_function))
+    }
+
+    /// Asynchronously render the pixel buffer with a custom render function and copy the result
+    /// back to CPU memory.
+    ///
+    /// See [`Pixels::read_rendered_frame_with`] for more information.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when rendering fails, when the buffer cannot be mapped, or when the render
+    /// target format is not supported.
+    pub async fn read_rendered_frame_with_async<F>(
+        &self,
+        render_function: F,
+    ) -> Result<RgbaImage, Error>
+    where
+        F: FnOnce(
+            &mut wgpu::CommandEncoder,
+            &wgpu::TextureView,
+            &PixelsContext,
+        ) -> Result<(), DynError>,
+    {
+        if self.is_device_lost() {
+            return Err(Error::DeviceLost);
+        }
 
-        // Update the pixel buffer texture view
-        let bytes_per_row =
-            (self.context.texture_extent.width as f32 * self.context.texture_format_size) as u32;
-        self.context.queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &self.context.texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
-                aspect: wgpu::TextureAspect::All,
-            },
+        let device = &self.context.device;
+        let queue = &self.context.queue;
+
+        match &self.context.target {
+            RenderTarget::Headless(texture) => {
+                self.render_to_texture(texture, render_function)?;
+
+                readback::read_texture(device, queue, texture).await
+            }
+            RenderTarget::Surface(_) => {
+                let texture = self.create_readback_target();
+                self.render_to_texture(&texture, render_function)?;
+
+                readback::read_texture(device, queue, &texture).await
+            }
+        }
+    }
+
+    /// Create a texture with the size and format of the surface, which can be read back.
+    fn create_readback_target(&self) -> wgpu::Texture {
+        self.context
+            .device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("pixels_readback_render_target"),
+                size: wgpu::Extent3d {
+                    width: self.surface_size.width,
+                    height: self.surface_size.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.render_texture_format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            })
+    }
+
+    /// Convert the pixel buffer to `RGBA` at its own resolution, without rendering.
+    ///
+    /// Colors are converted the same way the GPU converts them when rendering, so they match
+    /// [`Pixels::read_rendered_frame`]: e.g. a linear `Rgba8Unorm` pixel buffer rendered to an
+    /// sRGB surface is sRGB encoded, and indexed pixel buffers are resolved with their palette.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the texture format of the pixel buffer is not supported. Supported
+    /// formats are those supported by [`Pixels::read_rendered_frame`], and `R8Uint` for indexed
+    /// pixel buffers.
+    pub fn frame_to_rgba(&self) -> Result<RgbaImage, Error> {
+        readback::convert_frame(
             &self.pixels,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(bytes_per_row),
-                rows_per_image: Some(self.context.texture_extent.height),
-            },
-            self.context.texture_extent,
-        );
+            self.context.texture_extent.width,
+            self.context.texture_extent.height,
+            self.context.texture_format,
+            self.palette(),
+            self.render_texture_format,
+        )
+    }
 
-        let view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+    /// Save a screenshot to a PNG file.
+    ///
+    /// With [`Resolution::Buffer`], the pixel buffer is saved at its own resolution, as returned
+    /// by [`Pixels::frame_to_rgba`]. With [`Resolution::Surface`], the pixel buffer is rendered
+    /// with the scaling renderer and saved at the surface resolution, as returned by
+    /// [`Pixels::read_rendered_frame`]. Use [`Pixels::save_screenshot_with`] to include custom
+    /// render passes.
+    ///
+    /// Colors are saved as sRGB, with the texture format byte order and encoding taken into
+    /// account.
+    ///
+    /// This method requires the `screenshot` cargo feature.
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// use pixels::Resolution;
+    ///
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, &window);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// pixels.save_screenshot("screenshot.png", Resolution::Buffer)?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the frame cannot be read back or converted, or when the file cannot
+    /// be written.
+    #[cfg(all(feature = "screenshot", not(target_arch = "wasm32")))]
+    pub fn save_screenshot<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        resolution: Resolution,
+    ) -> Result<(), Error> {
+        let image = match resolution {
+            Resolution::Buffer => self.frame_to_rgba()?,
+            Resolution::Surface => self.read_rendered_frame()?,
+        };
 
-        // Call the user's render function.
-        (render_function)(&mut encoder, &view, &self.context)?;
+        screenshot::write_png(path.as_ref(), &image)
+    }
 
-        self.context.queue.submit(Some(encoder.finish()));
-        frame.present();
-        Ok(())
+    /// Save a screenshot at the surface resolution to a PNG file, rendered with a custom render
+    /// function.
+    ///
+    /// This captures the output of the same render function passed to [`Pixels::render_with`],
+    /// including any custom render passes. See [`Pixels::save_screenshot`] for more information.
+    ///
+    /// This method requires the `screenshot` cargo feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when rendering fails, when the frame cannot be read back, or when the file
+    /// cannot be written.
+    #[cfg(all(feature = "screenshot", not(target_arch = "wasm32")))]
+    pub fn save_screenshot_with<P, F>(&self, path: P, render_function: F) -> Result<(), Error>
+    where
+        P: AsRef<std::path::Path>,
+        F: FnOnce(
+            &mut wgpu::CommandEncoder,
+            &wgpu::TextureView,
+            &PixelsContext,
+        ) -> Result<(), DynError>,
+    {
+        let image = self.read_rendered_frame_with(render_function)?;
+
+        screenshot::write_png(path.as_ref(), &image)
     }
 
     /// Reconfigure the surface.
     ///
-    /// Call this when the surface or presentation mode needs to be changed.
+    /// Call this when the surface or presentation mode needs to be changed. Does nothing for
+    /// headless pixel buffers.
     pub(crate) fn reconfigure_surface(&self) {
-        self.context.surface.configure(
+        let RenderTarget::Surface(surface) = &self.context.target else {
+            return;
+        };
+        self.redraw.store(true, Ordering::Relaxed);
+
+        // Surface resolution recordings read back the surface texture when possible
+        #[allow(unused_mut)]
+        let mut usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
+        #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+        if self
+            .recorder
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|recorder| {
+                recorder.resolution() == Resolution::Surface
+                    && self
+                        .context
+                        .surface_capabilities
+                        .usages
+                        .contains(wgpu::TextureUsages::COPY_SRC)
+            })
+        {
+            usage |= wgpu::TextureUsages::COPY_SRC;
+        }
+
+        surface.configure(
             &self.context.device,
             &wgpu::SurfaceConfiguration {
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                usage,
                 format: self.surface_texture_format,
                 width: self.surface_size.width,
                 height: self.surface_size.height,
@@ -614,10 +2046,66 @@ impl<'win> Pixels<'win> {
 
     /// Get a mutable byte slice for the pixel buffer. The buffer is _not_ cleared for you; it will
     /// retain the previous frame's contents until you clear it yourself.
+    ///
+    /// The whole pixel buffer is uploaded to the GPU on the next render. Use
+    /// [`Pixels::frame_mut_region`] when only part of the pixel buffer changes.
     pub fn frame_mut(&mut self) -> &mut [u8] {
+        self.dirty.get_mut().unwrap().mark_all();
+        *self.redraw.get_mut() = true;
+
+        &mut self.pixels
+    }
+
+    /// Get a mutable byte slice for the pixel buffer, marking only a region as changed.
+    ///
+    /// The region is given in pixels as `(x, y, width, height)` and is clipped to the pixel
+    /// buffer. Only the regions marked since the last render are uploaded to the GPU, so this can
+    /// save a lot of bandwidth when small parts of a large pixel buffer change between frames.
+    /// Regions are merged into a few uploads, and nothing is uploaded when no region was marked.
+    ///
+    /// The returned slice is the whole pixel buffer, as with [`Pixels::frame_mut`]. Changes made
+    /// outside of the region are not uploaded until they are part of a marked region.
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1920, 1080, &window);
+    /// let mut pixels = Pixels::new(1920, 1080, surface_texture)?;
+    ///
+    /// // Draw a white 8x8 brush at (100, 50)
+    /// let frame = pixels.frame_mut_region(100, 50, 8, 8);
+    /// for y in 50..58 {
+    ///     let start = (y * 1920 + 100) * 4;
+    ///     frame[start..start + 8 * 4].fill(0xff);
+    /// }
+    ///
+    /// // Only the 8x8 region is uploaded
+    /// pixels.render()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn frame_mut_region(&mut self, x: u32, y: u32, width: u32, height: u32) -> &mut [u8] {
+        self.mark_dirty(x, y, width, height);
+
         &mut self.pixels
     }
 
+    /// Mark a region of the pixel buffer as changed, so it is uploaded to the GPU on the next
+    /// render.
+    ///
+    /// The region is given in pixels as `(x, y, width, height)` and is clipped to the pixel
+    /// buffer. See [`Pixels::frame_mut_region`] for more information.
+    pub fn mark_dirty(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        let dirty = self.dirty.get_mut().unwrap();
+
+        // Compressed texture formats can only be uploaded in whole blocks
+        if self.context.texture_format.block_dimensions() == (1, 1) {
+            dirty.mark(Rect::new(x, y, width, height));
+        } else {
+            dirty.mark_all();
+        }
+        *self.redraw.get_mut() = true;
+    }
+
     /// Get an immutable byte slice for the pixel buffer.
     ///
     /// This may be useful for operations that must sample the buffer, such as blending pixel
@@ -626,6 +2114,212 @@ impl<'win> Pixels<'win> {
         &self.pixels
     }
 
+    /// Get a typed, mutable view of the pixel buffer.
+    ///
+    /// The view knows the size and texture format of the pixel buffer, so pixels can be addressed
+    /// by coordinates. The whole pixel buffer is uploaded to the GPU on the next render, as with
+    /// [`Pixels::frame_mut`].
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(320, 240, &window);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    /// let mut frame = pixels.frame_view_mut();
+    ///
+    /// frame.fill(&[0x00, 0x00, 0x00, 0xff]);
+    /// frame.set_pixel_rgba(160, 120, [0xff, 0xff, 0xff, 0xff]);
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics when the texture format is compressed.
+    pub fn frame_view_mut(&mut self) -> FrameMut<'_> {
+        let (width, height) = (
+            self.context.texture_extent.width,
+            self.context.texture_extent.height,
+        );
+        let format = self.context.texture_format;
+
+        FrameMut::new(self.frame_mut(), width, height, format)
+    }
+
+    /// Get a typed, mutable view of a rectangle in the pixel buffer.
+    ///
+    /// Only the rectangle is marked as changed, so only the rectangle is uploaded to the GPU on
+    /// the next render. See [`Pixels::frame_mut_region`].
+    ///
+    /// # Panics
+    ///
+    /// Panics when the rectangle is not within the pixel buffer, or when the texture format is
+    /// compressed.
+    #[track_caller]
+    pub fn frame_view_mut_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> FrameMut<'_> {
+        let (frame_width, frame_height) = (
+            self.context.texture_extent.width,
+            self.context.texture_extent.height,
+        );
+        let format = self.context.texture_format;
+        let frame = self.frame_mut_region(x, y, width, height);
+
+        FrameMut::new(frame, frame_width, frame_height, format).into_view_mut(x, y, width, height)
+    }
+
+    /// Get a typed, immutable view of the pixel buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the texture format is compressed.
+    pub fn frame_view(&self) -> Frame<'_> {
+        Frame::new(
+            &self.pixels,
+            self.context.texture_extent.width,
+            self.context.texture_extent.height,
+            self.context.texture_format,
+        )
+    }
+
+    /// Load an image into the pixel buffer, replacing its contents.
+    ///
+    /// The image is read from a file path or decoded from a byte slice, and scaled to the pixel
+    /// buffer size as described by `fit`. See the [`image`] module for the supported formats.
+    ///
+    /// This method requires the `image` cargo feature.
+    ///
+    /// ```no_run
+    /// # use pixels::Pixels;
+    /// use pixels::image::Fit;
+    ///
+    /// # let window = pixels_mocks::Window;
+    /// # let surface_texture = pixels::SurfaceTexture::new(1024, 768, &window);
+    /// let mut pixels = Pixels::new(320, 240, surface_texture)?;
+    ///
+    /// pixels.load_frame_from("title.png", Fit::Letterbox)?;
+    /// pixels.load_frame_from(include_bytes!("../img/pixels.png"), Fit::Stretch)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the image cannot be read or decoded, or when the texture format is
+    /// not one of the formats supported by the [`draw`] module.
+    #[cfg(feature = "image")]
+    pub fn load_frame_from<'a>(
+        &mut self,
+        source: impl Into<image::ImageSource<'a>>,
+        fit: image::Fit,
+    ) -> Result<(), image::ImageError> {
+        use wgpu::TextureFormat::*;
+
+        let format = self.context.texture_format;
+        if !matches!(
+            format,
+            Rgba8Unorm | Rgba8UnormSrgb | Bgra8Unorm | Bgra8UnormSrgb
+        ) {
+            return Err(image::ImageError::UnsupportedTextureFormat(format));
+        }
+
+        let image = source.into().load()?;
+        image::copy_to_frame(&image, &mut self.frame_view_mut(), fit);
+
+        Ok(())
+    }
+
+    /// Start recording every rendered frame.
+    ///
+    /// Frames are captured by [`Pixels::render`] and [`Pixels::render_with`] at the resolution of
+    /// the recorder, and encoded on a background thread. Surface resolution frames include custom
+    /// render passes when the surface texture can be read back; otherwise they are rendered again
+    /// with the scaling renderer. Any previous recorder is replaced and returned.
+    ///
+    /// This method requires the `record` cargo feature. See the [`record`] module for an example.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when frames cannot be captured in the texture format of the pixel buffer,
+    /// or of the render target for surface resolution recordings. The recorder is not started.
+    #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+    pub fn start_recording(
+        &mut self,
+        recorder: record::Recorder,
+    ) -> Result<Option<record::Recorder>, Error> {
+        let format = match recorder.resolution() {
+            Resolution::Buffer if self.context.palette_renderer.is_some() => None,
+            Resolution::Buffer => Some(self.context.texture_format),
+            Resolution::Surface => Some(self.render_texture_format),
+        };
+        if let Some(format) = format.filter(|&format| !readback::is_supported_format(format)) {
+            return Err(Error::UnsupportedFormat(format));
+        }
+
+        let mut previous = self.recorder.lock().unwrap().replace(recorder);
+        if let Some(previous) = &mut previous {
+            previous.receive_readbacks(&self.context.device, true);
+        }
+        self.reconfigure_surface();
+
+        Ok(previous)
+    }
+
+    /// Stop recording, returning the recorder.
+    ///
+    /// This waits for the frames that are still being copied from the GPU. Call
+    /// [`record::Recorder::finish`] to finish encoding the recording.
+    ///
+    /// This method requires the `record` cargo feature.
+    #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+    pub fn stop_recording(&mut self) -> Option<record::Recorder> {
+        let mut recorder = self.recorder.lock().unwrap().take();
+        if let Some(recorder) = &mut recorder {
+            recorder.receive_readbacks(&self.context.device, true);
+            self.reconfigure_surface();
+        }
+
+        recorder
+    }
+
+    /// Capture a rendered frame when recording.
+    ///
+    /// Surface resolution frames are copied from the GPU without waiting, and given to the
+    /// recorder when they are read back on a later frame.
+    #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+    fn record_frame(&self, texture: &wgpu::Texture) -> Result<(), Error> {
+        let mut recorder = self.recorder.lock().unwrap();
+        let Some(recorder) = recorder.as_mut() else {
+            return Ok(());
+        };
+        let (device, queue) = (&self.context.device, &self.context.queue);
+        recorder.receive_readbacks(device, false);
+        let Some(timestamp) = recorder.reserve_frame() else {
+            return Ok(());
+        };
+
+        match recorder.resolution() {
+            Resolution::Buffer => recorder.send(self.frame_to_rgba()?, timestamp),
+            Resolution::Surface if texture.usage().contains(wgpu::TextureUsages::COPY_SRC) => {
+                recorder.capture_texture(device, queue, texture, timestamp)?;
+            }
+            Resolution::Surface => {
+                let texture = self.create_readback_target();
+                self.render_to_texture(&texture, |encoder, render_target, context| {
+                    context.render_passes(encoder, render_target);
+
+                    Ok(())
+                })?;
+                recorder.capture_texture(device, queue, &texture, timestamp)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Calculate the pixel location from a physical location on the window,
     /// dealing with window resizing, scaling, and margins. Takes a physical
     /// position (x, y) within the window, and returns a pixel position (x, y).
@@ -663,19 +2357,26 @@ impl<'win> Pixels<'win> {
         let pixels_width = self.context.texture_extent.width as f32;
         let pixels_height = self.context.texture_extent.height as f32;
 
+        // Follow the CRT curvature to the position that is displayed there
+        #[cfg(feature = "crt")]
+        let physical_position = self
+            .context
+            .passes
+            .crt()
+            .map_or(physical_position, |crt| crt.distort(physical_position));
+
+        // Convert the physical position to normalized device coordinates
         let pos = ultraviolet::Vec4::new(
-            (physical_position.0 / physical_width - 0.5) * pixels_width,
-            (physical_position.1 / physical_height - 0.5) * pixels_height,
+            physical_position.0 / physical_width * 2.0 - 1.0,
+            1.0 - physical_position.1 / physical_height * 2.0,
             0.0,
             1.0,
         );
 
+        // Undo the scaling transform, then convert to texture coordinates
         let pos = self.scaling_matrix_inverse * pos;
-        let offset_width = pixels_width.min(physical_width) / 2.0;
-        let offset_height = pixels_height.min(physical_height) / 2.0;
-
-        let pixel_x = (pos.x / pos.w + offset_width).floor() as isize;
-        let pixel_y = (pos.y / pos.w + offset_height).floor() as isize;
+        let pixel_x = ((pos.x / pos.w + 1.0) / 2.0 * pixels_width).floor() as isize;
+        let pixel_y = ((1.0 - pos.y / pos.w) / 2.0 * pixels_height).floor() as isize;
 
         if pixel_x < 0
             || pixel_x >= self.context.texture_extent.width as isize
@@ -732,6 +2433,250 @@ impl<'win> Pixels<'win> {
         &self.context.texture
     }
 
+    /// Provides access to the internal render target [`wgpu::Texture`] of a headless pixel buffer.
+    ///
+    /// This is the texture that [`Pixels::render`] and [`Pixels::render_with`] draw to when there
+    /// is no window surface. Returns `None` when rendering to a window surface.
+    pub fn headless_texture(&self) -> Option<&wgpu::Texture> {
+        match &self.context.target {
+            RenderTarget::Surface(_) => None,
+            RenderTarget::Headless(texture) => Some(texture),
+        }
+    }
+
+    /// Returns `true` when this pixel buffer was built with [`PixelsBuilder::new_headless`].
+    pub fn is_headless(&self) -> bool {
+        matches!(self.context.target, RenderTarget::Headless(_))
+    }
+
+    /// Get a [`PixelsDevice`] for creating more pixel buffers on the same device.
+    ///
+    /// The new pixel buffers share the device, shader modules, and pipelines with this one.
+    ///
+    /// ```no_run
+    /// # use pixels::{Pixels, SurfaceTexture};
+    /// # let window = pixels_mocks::Window;
+    /// # let viewer_window = pixels_mocks::Window;
+    /// let pixels = Pixels::new(320, 240, SurfaceTexture::new(640, 480, &window))?;
+    /// let viewer = pixels
+    ///     .pixels_device()
+    ///     .builder(128, 128, SurfaceTexture::new(256, 256, &viewer_window))
+    ///     .build()?;
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn pixels_device(&self) -> PixelsDevice {
+        PixelsDevice {
+            device_lost: Arc::clone(&self.context.device_lost),
+            instance: Arc::clone(&self.context.instance),
+            adapter: Arc::clone(&self.context.adapter),
+            device: Arc::clone(&self.context.device),
+            queue: Arc::clone(&self.context.queue),
+            pipeline_cache: Arc::clone(&self.context.pipeline_cache),
+            features: self.context.features,
+            limits: self.context.limits.clone(),
+        }
+    }
+
+    /// Returns `true` when the GPU device was lost, e.g. after a driver reset or update.
+    ///
+    /// Rendering returns [`Error::DeviceLost`] until [`Pixels::recreate_device`] is called.
+    pub fn is_device_lost(&self) -> bool {
+        self.context.device_lost.is_lost()
+    }
+
+    /// Set a callback that is called when the GPU device is lost.
+    ///
+    /// The callback is called with the reason and a description from the driver, on whichever
+    /// thread notices the loss. It is a good place to wake up the event loop, so that
+    /// [`Pixels::recreate_device`] can be called. The callback is shared by every pixel buffer on
+    /// the same [`PixelsDevice`], and is kept by [`Pixels::recreate_device`].
+    pub fn set_device_lost_callback<F>(&self, callback: F)
+    where
+        F: Fn(wgpu::DeviceLostReason, String) + Send + Sync + 'static,
+    {
+        self.context.device_lost.set_callback(Arc::new(callback));
+    }
+
+    /// Report that the GPU device was lost.
+    ///
+    /// Pixels only sets a device lost callback on devices that it requested itself. A device
+    /// given to [`PixelsBuilder::with_device`] or [`PixelsDevice::from_wgpu`] belongs to the
+    /// application, which reports the loss with this method or with
+    /// [`PixelsDevice::notify_device_lost`], e.g. after its own device lost callback woke up the
+    /// event loop. Every pixel buffer on the same [`PixelsDevice`] is marked as lost, and the
+    /// callback set with [`Pixels::set_device_lost_callback`] is called.
+    ///
+    /// Reasons that are not a loss, like [`wgpu::DeviceLostReason::Dropped`], are ignored.
+    pub fn notify_device_lost(&self, reason: wgpu::DeviceLostReason, message: String) {
+        self.context.device_lost.notify(reason, message);
+    }
+
+    /// Replace a lost GPU device, and recreate every GPU resource of this pixel buffer on it.
+    ///
+    /// A new device is requested from the same adapter with the same features and limits, or from
+    /// a new adapter when that fails. The textures, the scaling renderer, the layers, and the CRT
+    /// renderer are recreated, and the contents of the pixel buffer, the layers, and the palette
+    /// are uploaded again. The surface is reconfigured for the new device.
+    ///
+    /// Custom passes added with [`Pixels::add_pass`] own resources of the lost device, so they are
+    /// removed and must be added again. So must the scaling shader set with
+    /// `Pixels::set_scaling_shader` when the `hot-reload` feature is enabled.
+    ///
+    /// Pixel buffers that shared the lost device each get their own device. To keep sharing a
+    /// device, recreate it once with [`PixelsDevice::recreate`], and move every pixel buffer to it
+    /// with [`Pixels::recreate_device_with`].
+    ///
+    /// This method blocks the current thread, making it unusable on Web targets. Use
+    /// [`Pixels::recreate_device_async`] for a non-blocking alternative.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::ExternalDevice`] when the device was given to [`PixelsBuilder::with_device`] or
+    ///   [`PixelsDevice::from_wgpu`]. Create a new device, and use
+    ///   [`Pixels::recreate_device_with`] instead.
+    /// - [`Error::AdapterNotFound`] or [`Error::DeviceNotFound`] when no device can be created.
+    /// - [`Error::SurfaceNotSupported`] when the new adapter cannot present to the surface.
+    /// - [`Error::InvalidTexture`] when the new device does not support the texture size.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn recreate_device(&mut self) -> Result<(), Error> {
+        pollster::block_on(self.recreate_device_async())
+    }
+
+    /// Replace a lost GPU device without blocking the current thread.
+    ///
+    /// See [`Pixels::recreate_device`].
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::ExternalDevice`] when the device was given to [`PixelsBuilder::with_device`] or
+    ///   [`PixelsDevice::from_wgpu`].
+    /// - [`Error::AdapterNotFound`] or [`Error::DeviceNotFound`] when no device can be created.
+    /// - [`Error::SurfaceNotSupported`] when the new adapter cannot present to the surface.
+    /// - [`Error::InvalidTexture`] when the new device does not support the texture size.
+    pub async fn recreate_device_async(&mut self) -> Result<(), Error> {
+        let surface = match &self.context.target {
+            RenderTarget::Surface(surface) => Some(surface),
+            RenderTarget::Headless(_) => None,
+        };
+        let device = self.pixels_device().recreate_for(surface).await?;
+
+        self.recreate_device_with(&device)
+    }
+
+    /// Move this pixel buffer to another GPU device after its device was lost, and recreate every
+    /// GPU resource on it, see [`Pixels::recreate_device`].
+    ///
+    /// Use this to move all pixel buffers that shared the lost device to the same new device, from
+    /// [`PixelsDevice::recreate`]. It is also the way to recover pixel buffers on a device of the
+    /// application: create a new device, and wrap it with [`PixelsDevice::from_wgpu`].
+    ///
+    /// A window surface is kept, so the `device` must use the same instance as this pixel buffer.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::SurfaceNotSupported`] when the adapter cannot present to the surface, or when
+    ///   the instance is different.
+    /// - [`Error::InvalidTexture`] when the new device does not support the texture size.
+    ///
+    /// The pixel buffer keeps using its previous device on errors.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use pixels::{Pixels, SurfaceTexture};
+    /// # let window = pixels_mocks::Window;
+    /// # let viewer_window = pixels_mocks::Window;
+    /// # let mut pixels = Pixels::new(320, 240, SurfaceTexture::new(640, 480, &window))?;
+    /// # let viewer_texture = SurfaceTexture::new(256, 256, &viewer_window);
+    /// # let mut viewer = pixels.pixels_device().builder(128, 128, viewer_texture).build()?;
+    /// if pixels.is_device_lost() {
+    ///     let gpu = pixels.pixels_device().recreate()?;
+    ///     pixels.recreate_device_with(&gpu)?;
+    ///     viewer.recreate_device_with(&gpu)?;
+    /// }
+    /// # Ok::<(), pixels::Error>(())
+    /// ```
+    pub fn recreate_device_with(&mut self, device: &PixelsDevice) -> Result<(), Error> {
+        // Create every GPU resource on the new device before replacing anything, so that this
+        // pixel buffer is unchanged on errors
+        let surface_capabilities = match &self.context.target {
+            RenderTarget::Surface(surface) => {
+                if !Arc::ptr_eq(&device.instance, &self.context.instance)
+                    || !device.adapter.is_surface_supported(surface)
+                {
+                    return Err(Error::SurfaceNotSupported);
+                }
+                Some(surface.get_capabilities(&device.adapter))
+            }
+            RenderTarget::Headless(_) => None,
+        };
+        // The headless render target and the pass targets have the surface size
+        check_texture_size(
+            &device.device,
+            self.surface_size.width,
+            self.surface_size.height,
+        )?;
+
+        let extent = self.context.texture_extent;
+        let backing = self.create_backing_texture(
+            &device.device,
+            &device.queue,
+            &device.pipeline_cache,
+            extent.width,
+            extent.height,
+        )?;
+
+        let texture = backing
+            .palette_renderer
+            .as_ref()
+            .map_or(&backing.texture, PaletteRenderer::output_texture);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let source = layers::Source {
+            view: &view,
+            format: texture.format(),
+            size: texture.size(),
+        };
+        let layers =
+            self.context
+                .layers
+                .recreate(&device.device, &device.pipeline_cache, &source)?;
+        let passes =
+            self.context
+                .passes
+                .recreate(&device.device, &device.queue, &device.pipeline_cache);
+
+        // Keep the pixel buffer
+        #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+        if let Some(recorder) = self.recorder.get_mut().unwrap() {
+            recorder.discard_readbacks();
+        }
+        if let Some(surface_capabilities) = surface_capabilities {
+            self.context.surface_capabilities = surface_capabilities;
+        }
+        self.context.device_lost = Arc::clone(&device.device_lost);
+        self.context.instance = Arc::clone(&device.instance);
+        self.context.adapter = Arc::clone(&device.adapter);
+        self.context.device = Arc::clone(&device.device);
+        self.context.queue = Arc::clone(&device.queue);
+        self.context.pipeline_cache = Arc::clone(&device.pipeline_cache);
+        self.context.features = device.features;
+        self.context.limits = device.limits.clone();
+        self.context.passes = passes;
+        self.set_backing_texture(backing);
+        self.context.layers.replace(layers);
+        self.dirty.get_mut().unwrap().mark_all();
+        if !self.context.layers.is_empty() {
+            self.connect_layers();
+        }
+
+        // The headless render target and the pass targets are recreated with the surface size
+        let (width, height) = (self.surface_size.width, self.surface_size.height);
+        self.set_surface_size(width, height);
+        self.reconfigure_surface();
+
+        Ok(())
+    }
+
     /// Provides access to the internal [`PixelsContext`].
     pub fn context(&self) -> &PixelsContext<'win> {
         &self.context
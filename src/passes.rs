@@ -0,0 +1,362 @@
+#[cfg(feature = "crt")]
+use crate::renderers::CrtRenderer;
+use crate::renderers::PipelineCache;
+use crate::ScalingRenderer;
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// A post-processing pass in the chain managed by [`Pixels`](crate::Pixels).
+///
+/// Passes are added with [`Pixels::add_pass`](crate::Pixels::add_pass) and run in order after the
+/// scaling renderer. Each pass samples the output of the previous pass (or of the scaling renderer
+/// for the first pass) and draws to its output view. The last pass draws to the surface.
+///
+/// Pixels owns the intermediate textures between passes. They have the same size as the surface
+/// and use the render texture format, see
+/// [`PixelsBuilder::render_texture_format`](crate::PixelsBuilder::render_texture_format).
+///
+/// Passes must be `Send` and `Sync` on native targets, like [`Pixels`](crate::Pixels) itself.
+pub trait RenderPass: wgpu::WasmNotSendSync {
+    /// Called when the pass is added to the chain, and again whenever its input texture is
+    /// recreated, e.g. when the surface is resized.
+    ///
+    /// This is the place to create bind groups for the `input` view and to resize any textures
+    /// owned by the pass. The `width` and `height` are the surface size in physical pixels.
+    ///
+    /// The default implementation does nothing.
+    fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        input: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) {
+        let _ = (device, queue, input, width, height);
+    }
+
+    /// Recompile shaders that were modified on disk, see [`Pixels::reload_shaders`].
+    ///
+    /// Returns `true` when any shader was recompiled. Use a
+    /// [`ShaderWatcher`](crate::shader::ShaderWatcher) for each shader file, and keep the previous
+    /// pipelines when an error is returned.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// [`Pixels::reload_shaders`]: crate::Pixels::reload_shaders
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    fn reload_shaders(
+        &mut self,
+        device: &wgpu::Device,
+    ) -> Result<bool, crate::shader::ShaderError> {
+        let _ = device;
+        Ok(false)
+    }
+
+    /// Draw the pass to the `output` view.
+    ///
+    /// The `output` is either an intermediate texture or the surface, and must be drawn entirely;
+    /// its previous contents are undefined.
+    fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        context: &PassContext<'_>,
+    );
+}
+
+/// The per-frame state given to each [`RenderPass`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct PassContext<'a> {
+    /// The `Device` allows creating GPU resources.
+    pub device: &'a wgpu::Device,
+
+    /// The `Queue` provides access to the GPU command queue, e.g. to update uniform buffers.
+    pub queue: &'a wgpu::Queue,
+
+    /// The output of the previous pass, or of the scaling renderer for the first pass.
+    ///
+    /// This is the same view that was given to [`RenderPass::resize`].
+    pub input: &'a wgpu::TextureView,
+
+    /// The texture format of the input and of the intermediate outputs.
+    pub format: wgpu::TextureFormat,
+
+    /// The clipping rectangle of the scaled pixel buffer on the surface, see
+    /// [`ScalingRenderer::clip_rect`].
+    pub clip_rect: (u32, u32, u32, u32),
+
+    /// The surface size in physical pixels.
+    pub size: (u32, u32),
+
+    /// The time since the first frame was rendered with this chain.
+    pub time: Duration,
+
+    /// The time since the previous frame was rendered, or zero for the first frame.
+    pub delta_time: Duration,
+}
+
+/// The post-processing passes and the intermediate textures between them.
+pub(crate) struct PassChain {
+    passes: Vec<Box<dyn RenderPass>>,
+    // Runs after all other passes
+    #[cfg(feature = "crt")]
+    crt: Option<CrtRenderer>,
+    // Ping-pong targets; stage `i` reads `targets[i % 2]`
+    targets: Vec<wgpu::TextureView>,
+    format: wgpu::TextureFormat,
+    size: (u32, u32),
+    // The time of the first and of the previous frame
+    frame_times: Mutex<Option<(Instant, Instant)>>,
+}
+
+impl PassChain {
+    pub(crate) fn new(format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        Self {
+            passes: Vec::new(),
+            #[cfg(feature = "crt")]
+            crt: None,
+            targets: Vec::new(),
+            format,
+            size: (width, height),
+            frame_times: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.passes.len()
+    }
+
+    /// Check whether the scaling renderer draws directly to the render target.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.stage_count() == 0
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pass: Box<dyn RenderPass>,
+    ) {
+        self.passes.push(pass);
+        self.connect(device, queue, self.passes.len() - 1);
+    }
+
+    pub(crate) fn clear(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Vec<Box<dyn RenderPass>> {
+        let passes = std::mem::take(&mut self.passes);
+        self.connect(device, queue, 0);
+
+        passes
+    }
+
+    #[cfg(feature = "crt")]
+    pub(crate) fn crt(&self) -> Option<&CrtRenderer> {
+        self.crt.as_ref()
+    }
+
+    #[cfg(feature = "crt")]
+    pub(crate) fn crt_mut(&mut self) -> Option<&mut CrtRenderer> {
+        self.crt.as_mut()
+    }
+
+    #[cfg(feature = "crt")]
+    pub(crate) fn set_crt(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        crt: Option<CrtRenderer>,
+    ) {
+        self.crt = crt;
+        self.connect(device, queue, self.passes.len());
+    }
+
+    /// Recompile the shaders of every pass, collecting the results.
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    pub(crate) fn reload_shaders(
+        &mut self,
+        device: &wgpu::Device,
+    ) -> Vec<Result<bool, crate::shader::ShaderError>> {
+        self.passes
+            .iter_mut()
+            .map(|pass| pass.reload_shaders(device))
+            .collect()
+    }
+
+    /// Recreate the intermediate targets at the new surface size and notify every pass.
+    pub(crate) fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+    ) {
+        self.size = (width, height);
+        self.targets.clear();
+        self.connect(device, queue, 0);
+    }
+
+    /// Create a copy of the chain on a new device after the previous device was lost.
+    ///
+    /// Custom passes own resources of the lost device, so they are left out. The CRT renderer is
+    /// recreated with its options.
+    pub(crate) fn recreate(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipeline_cache: &PipelineCache,
+    ) -> Self {
+        #[cfg(not(feature = "crt"))]
+        let _ = pipeline_cache;
+
+        let mut chain = Self {
+            passes: Vec::new(),
+            #[cfg(feature = "crt")]
+            crt: self
+                .crt
+                .as_ref()
+                .map(|crt| CrtRenderer::new(device, pipeline_cache, self.format, *crt.options())),
+            targets: Vec::new(),
+            format: self.format,
+            size: self.size,
+            frame_times: Mutex::new(*self.frame_times.lock().unwrap()),
+        };
+        chain.connect(device, queue, 0);
+
+        chain
+    }
+
+    /// The number of stages, including the CRT renderer.
+    fn stage_count(&self) -> usize {
+        #[cfg(feature = "crt")]
+        let crt = usize::from(self.crt.is_some());
+        #[cfg(not(feature = "crt"))]
+        let crt = 0;
+
+        self.passes.len() + crt
+    }
+
+    /// Create or release intermediate targets to match the number of stages, then give each stage
+    /// from `first` onward its input.
+    fn connect(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, first: usize) {
+        let count = self.stage_count();
+        self.targets.truncate(count.min(2));
+        while self.targets.len() < count.min(2) {
+            self.targets
+                .push(create_target(device, self.format, self.size));
+        }
+        if count == 0 {
+            *self.frame_times.get_mut().unwrap() = None;
+        }
+
+        let (width, height) = self.size;
+        let targets = &self.targets;
+        #[cfg(feature = "crt")]
+        let crt = self.crt.as_mut().map(|crt| crt as &mut dyn RenderPass);
+        #[cfg(not(feature = "crt"))]
+        let crt = None;
+        let stages = self.passes.iter_mut().map(|pass| &mut **pass).chain(crt);
+        for (i, stage) in stages.enumerate().skip(first) {
+            stage.resize(device, queue, &targets[i % 2], width, height);
+        }
+    }
+
+    /// Draw the pixel buffer with the scaling renderer, then run every pass in order.
+    ///
+    /// The last pass (or the scaling renderer, when there are no passes) draws to `render_target`.
+    pub(crate) fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &wgpu::TextureView,
+        scaling_renderer: &ScalingRenderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        let count = self.stage_count();
+        if count == 0 {
+            scaling_renderer.render(encoder, render_target);
+            return;
+        }
+
+        let now = Instant::now();
+        let (time, delta_time) = {
+            let mut frame_times = self.frame_times.lock().unwrap();
+            let (first, previous) = frame_times.get_or_insert((now, now));
+            let times = (now - *first, now - *previous);
+            *previous = now;
+
+            times
+        };
+
+        scaling_renderer.render(encoder, &self.targets[0]);
+
+        #[cfg(feature = "crt")]
+        let crt = self.crt.as_ref().map(|crt| crt as &dyn RenderPass);
+        #[cfg(not(feature = "crt"))]
+        let crt = None;
+        let stages = self.passes.iter().map(|pass| &**pass).chain(crt);
+        for (i, stage) in stages.enumerate() {
+            let context = PassContext {
+                device,
+                queue,
+                input: &self.targets[i % 2],
+                format: self.format,
+                clip_rect: scaling_renderer.clip_rect(),
+                size: self.size,
+                time,
+                delta_time,
+            };
+            let output = if i == count - 1 {
+                render_target
+            } else {
+                &self.targets[(i + 1) % 2]
+            };
+
+            stage.render(encoder, output, &context);
+        }
+    }
+}
+
+impl std::fmt::Debug for PassChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("PassChain");
+        debug.field("passes", &self.passes.len());
+        #[cfg(feature = "crt")]
+        debug.field("crt", &self.crt);
+        debug
+            .field("format", &self.format)
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+fn create_target(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    (width, height): (u32, u32),
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("pixels_pass_target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
@@ -0,0 +1,441 @@
+use crate::{Error, Frame};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// An owned image with 4 unsigned bytes per pixel in `RGBA` order.
+///
+/// This is returned by [`Pixels::read_rendered_frame`](crate::Pixels::read_rendered_frame) and
+/// friends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Create an image from raw `RGBA` pixel data.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the length of `pixels` is not `width * height * 4`.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize * 4);
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// The image width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The image height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get an immutable byte slice for the pixel data.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Get a mutable byte slice for the pixel data.
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    /// Get a typed view of the image in the `Rgba8UnormSrgb` texture format.
+    ///
+    /// This allows drawing the image into the pixel buffer with `pixels::draw::blit`.
+    pub fn as_frame(&self) -> Frame<'_> {
+        Frame::new(
+            &self.pixels,
+            self.width,
+            self.height,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+
+    /// Consume the image, returning the pixel data.
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+}
+
+/// Copy a texture to CPU memory and convert it to `RGBA`.
+///
+/// The texture must have been created with [`wgpu::TextureUsages::COPY_SRC`].
+pub(crate) async fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<RgbaImage, Error> {
+    let mut readback = Readback::new(device, queue, texture, None)?;
+
+    // Web targets resolve the mapping on their own; native targets must wait for the GPU.
+    #[cfg(not(target_arch = "wasm32"))]
+    device.poll(wgpu::Maintain::Wait);
+
+    (&mut readback.mapping).await?;
+
+    Ok(readback.to_rgba())
+}
+
+/// A copy of a texture in a staging buffer, which can be read once the buffer is mapped.
+///
+/// The mapping completes when the device is polled, so frames can be copied without waiting
+/// for the GPU and read on a later frame.
+pub(crate) struct Readback {
+    buffer: wgpu::Buffer,
+    mapping: MapFuture,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+}
+
+impl Readback {
+    /// Copy `texture` to a staging buffer and start mapping it.
+    ///
+    /// The `staging` buffer of an earlier readback is reused when it has the right size.
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        staging: Option<wgpu::Buffer>,
+    ) -> Result<Self, Error> {
+        let format = texture.format();
+        if !is_supported_format(format) {
+            return Err(Error::UnsupportedFormat(format));
+        }
+
+        let width = texture.width();
+        let height = texture.height();
+        let bytes_per_pixel = format.block_copy_size(None).unwrap_or(4);
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (width * bytes_per_pixel).div_ceil(align) * align;
+        let size = padded_bytes_per_row as u64 * height as u64;
+
+        let buffer = match staging.filter(|buffer| buffer.size() == size) {
+            Some(buffer) => buffer,
+            None => device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("pixels_readback_buffer"),
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+        };
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("pixels_readback_command_encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let mapping = MapFuture::default();
+        let state = mapping.state.clone();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let mut state = state.lock().unwrap();
+                state.result = Some(result);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            });
+
+        Ok(Self {
+            buffer,
+            mapping,
+            format,
+            width,
+            height,
+            padded_bytes_per_row,
+        })
+    }
+
+    /// Whether mapping the staging buffer has finished, successfully or not.
+    #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+    pub(crate) fn is_ready(&self) -> bool {
+        self.mapping.state.lock().unwrap().result.is_some()
+    }
+
+    /// Read the mapped staging buffer, and return the buffer for reuse.
+    ///
+    /// Must only be called when [`Readback::is_ready`] returns `true`.
+    #[cfg(all(feature = "record", not(target_arch = "wasm32")))]
+    pub(crate) fn finish(self) -> (Result<RgbaImage, Error>, wgpu::Buffer) {
+        let mapped = self.mapping.state.lock().unwrap().result.take();
+        let image = match mapped {
+            Some(Ok(())) => Ok(self.to_rgba()),
+            Some(Err(err)) => Err(err.into()),
+            None => unreachable!("The staging buffer is not mapped yet"),
+        };
+
+        (image, self.buffer)
+    }
+
+    /// Convert the mapped staging buffer to `RGBA`, and unmap it.
+    fn to_rgba(&self) -> RgbaImage {
+        let (width, height) = (self.width, self.height);
+        let bytes_per_pixel = self.format.block_copy_size(None).unwrap_or(4) as usize;
+        let unpadded_bytes_per_row = width as usize * bytes_per_pixel;
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        {
+            let data = self.buffer.slice(..).get_mapped_range();
+            for row in data.chunks_exact(self.padded_bytes_per_row as usize) {
+                let row = &row[..unpadded_bytes_per_row];
+                for texel in row.chunks_exact(bytes_per_pixel) {
+                    pixels.extend_from_slice(&convert_texel(self.format, texel));
+                }
+            }
+        }
+        self.buffer.unmap();
+
+        RgbaImage::new(width, height, pixels)
+    }
+}
+
+/// Convert the contents of a pixel buffer to `RGBA`, with the colors it has when rendered to a
+/// texture in the target format.
+///
+/// Indexed pixel buffers are converted with their palette.
+pub(crate) fn convert_frame(
+    frame: &[u8],
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    palette: Option<&[[u8; 4]; 256]>,
+    target_format: wgpu::TextureFormat,
+) -> Result<RgbaImage, Error> {
+    use wgpu::TextureFormat::*;
+
+    if let Some(palette) = palette {
+        // Palette colors are sRGB encoded
+        let colors = frame.iter().map(|&index| palette[index as usize]);
+        let pixels = convert_texels(colors.map(|color| (color, Rgba8UnormSrgb)), target_format);
+
+        return Ok(RgbaImage::new(width, height, pixels));
+    }
+    if !is_supported_format(format) {
+        return Err(Error::UnsupportedFormat(format));
+    }
+
+    let bytes_per_pixel = format.block_copy_size(None).unwrap_or(4) as usize;
+    let pixels = match format {
+        Rgba8Unorm | Rgba8UnormSrgb | Bgra8Unorm | Bgra8UnormSrgb => {
+            let colors = frame
+                .chunks_exact(bytes_per_pixel)
+                .map(|texel| (convert_texel(format, texel), format));
+            convert_texels(colors, target_format)
+        }
+        _ => frame
+            .chunks_exact(bytes_per_pixel)
+            .flat_map(|texel| encode_linear(decode_linear(format, texel), target_format))
+            .collect(),
+    };
+
+    Ok(RgbaImage::new(width, height, pixels))
+}
+
+/// Convert 8-bit `RGBA` colors in the given formats to colors in the target format.
+///
+/// Colors are copied unchanged when both formats have the same encoding.
+fn convert_texels(
+    colors: impl Iterator<Item = ([u8; 4], wgpu::TextureFormat)>,
+    target_format: wgpu::TextureFormat,
+) -> Vec<u8> {
+    colors
+        .flat_map(|(color, format)| {
+            if format.is_srgb() == encodes_srgb(target_format) {
+                return color;
+            }
+
+            // The color is already in `RGBA` order
+            let format = if format.is_srgb() {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+            } else {
+                wgpu::TextureFormat::Rgba8Unorm
+            };
+            encode_linear(decode_linear(format, &color), target_format)
+        })
+        .collect()
+}
+
+/// Whether rendering to the format stores sRGB encoded colors in `RGBA` readback images.
+fn encodes_srgb(format: wgpu::TextureFormat) -> bool {
+    format.is_srgb() || format == wgpu::TextureFormat::Rgba16Float
+}
+
+/// Decode a texel to linear color values, as sampled by a shader.
+fn decode_linear(format: wgpu::TextureFormat, texel: &[u8]) -> [f32; 4] {
+    use wgpu::TextureFormat::*;
+
+    match format {
+        Rgba16Float => {
+            let channel = |i: usize| f16_to_f32(u16::from_le_bytes([texel[i], texel[i + 1]]));
+
+            [channel(0), channel(2), channel(4), channel(6)]
+        }
+        Rgb10a2Unorm => {
+            let bits = u32::from_le_bytes([texel[0], texel[1], texel[2], texel[3]]);
+            let channel = |shift: u32, max: u32| ((bits >> shift) & max) as f32 / max as f32;
+
+            [
+                channel(0, 0x3ff),
+                channel(10, 0x3ff),
+                channel(20, 0x3ff),
+                channel(30, 0x3),
+            ]
+        }
+        _ => {
+            let [r, g, b, a] = convert_texel(format, texel).map(|value| value as f32 / 255.0);
+            if format.is_srgb() {
+                [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a]
+            } else {
+                [r, g, b, a]
+            }
+        }
+    }
+}
+
+/// Encode linear color values as they are stored by rendering to the format, and read back.
+fn encode_linear([r, g, b, a]: [f32; 4], format: wgpu::TextureFormat) -> [u8; 4] {
+    let unorm = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    if encodes_srgb(format) {
+        [
+            linear_to_srgb(r),
+            linear_to_srgb(g),
+            linear_to_srgb(b),
+            unorm(a),
+        ]
+    } else {
+        [unorm(r), unorm(g), unorm(b), unorm(a)]
+    }
+}
+
+pub(crate) fn is_supported_format(format: wgpu::TextureFormat) -> bool {
+    use wgpu::TextureFormat::*;
+
+    matches!(
+        format,
+        Rgba8Unorm | Rgba8UnormSrgb | Bgra8Unorm | Bgra8UnormSrgb | Rgb10a2Unorm | Rgba16Float
+    )
+}
+
+/// Convert a single texel to `RGBA`. The format must be accepted by `is_supported_format`.
+fn convert_texel(format: wgpu::TextureFormat, texel: &[u8]) -> [u8; 4] {
+    use wgpu::TextureFormat::*;
+
+    match format {
+        Rgba8Unorm | Rgba8UnormSrgb => [texel[0], texel[1], texel[2], texel[3]],
+        Bgra8Unorm | Bgra8UnormSrgb => [texel[2], texel[1], texel[0], texel[3]],
+        Rgb10a2Unorm => {
+            let bits = u32::from_le_bytes([texel[0], texel[1], texel[2], texel[3]]);
+            let channel = |shift: u32, max: u32| {
+                (((bits >> shift) & max) as f32 / max as f32 * 255.0).round() as u8
+            };
+
+            [
+                channel(0, 0x3ff),
+                channel(10, 0x3ff),
+                channel(20, 0x3ff),
+                channel(30, 0x3),
+            ]
+        }
+        Rgba16Float => {
+            // Float formats are linear, so the color channels are sRGB encoded.
+            let channel = |i: usize| f16_to_f32(u16::from_le_bytes([texel[i], texel[i + 1]]));
+
+            [
+                linear_to_srgb(channel(0)),
+                linear_to_srgb(channel(2)),
+                linear_to_srgb(channel(4)),
+                (channel(6).clamp(0.0, 1.0) * 255.0).round() as u8,
+            ]
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+
+    match exponent {
+        0 => sign * mantissa * 2.0_f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2.0_f32.powi(exponent - 15),
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let value = if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    };
+    let encoded = if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+
+    (encoded * 255.0).round() as u8
+}
+
+/// Resolves when the `wgpu::BufferSlice::map_async` callback has been called.
+#[derive(Default)]
+struct MapFuture {
+    state: Arc<Mutex<MapState>>,
+}
+
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+impl Future for MapFuture {
+    type Output = Result<(), wgpu::BufferAsyncError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
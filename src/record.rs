@@ -0,0 +1,713 @@
+//! Recording rendered frames to animated images and video.
+//!
+//! A [`Recorder`] captures frames with timestamps, and encodes them on a background thread so the
+//! render loop is not stalled. Frames can be encoded as an animated GIF (with palette
+//! quantization), an animated PNG, or a raw YUV4MPEG2 stream that can be piped into tools like
+//! `ffmpeg`.
+//!
+//! Start recording with [`Pixels::start_recording`](crate::Pixels::start_recording), and every
+//! frame presented by [`Pixels::render`](crate::Pixels::render) or
+//! [`Pixels::render_with`](crate::Pixels::render_with) is captured until the recorder is taken
+//! back with [`Pixels::stop_recording`](crate::Pixels::stop_recording). Frames can also be given to
+//! a recorder directly with [`Recorder::capture`].
+//!
+//! Rendered frames are copied from the GPU without waiting for it, and reach the recorder a few
+//! frames later. When the GPU copies or the encoder fall behind, frames are dropped instead of
+//! stalling the render loop or queueing without bounds; see [`Recorder::dropped_frames`].
+//!
+//! This module requires the `record` cargo feature, and is not available on Web targets.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use pixels::record::Recorder;
+//! use pixels::{Pixels, Resolution};
+//!
+//! # let window = pixels_mocks::Window;
+//! # let surface_texture = pixels::SurfaceTexture::new(1024, 768, &window);
+//! let mut pixels = Pixels::new(320, 240, surface_texture)?;
+//!
+//! let recorder = Recorder::create("bug-report.gif", Resolution::Buffer)?.max_frame_rate(30);
+//! pixels.start_recording(recorder)?;
+//!
+//! for _ in 0..60 {
+//!     // Update the frame...
+//!     pixels.render()?;
+//! }
+//!
+//! if let Some(recorder) = pixels.stop_recording() {
+//!     recorder.finish()?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::readback::Readback;
+use crate::{Resolution, RgbaImage};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// The frame rate used when no maximum frame rate is set.
+///
+/// This is the frame rate of YUV4MPEG2 streams, and the delay of the last frame of animated
+/// images when only a single frame was captured.
+const DEFAULT_FRAME_RATE: u32 = 60;
+
+/// The number of frames waiting for the encoder before new frames are dropped.
+const MAX_QUEUED_FRAMES: usize = 8;
+
+/// The number of rendered frames being copied from the GPU before new frames are dropped.
+const MAX_PENDING_READBACKS: usize = 3;
+
+/// All the ways in which recording can fail.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum RecordError {
+    /// Writing the recording failed.
+    #[error("Unable to write the recording.")]
+    Io(#[from] std::io::Error),
+    /// Encoding the GIF failed.
+    #[error("Unable to encode the GIF.")]
+    Gif(#[from] gif::EncodingError),
+    /// Encoding a PNG frame failed.
+    #[error("Unable to encode the PNG.")]
+    Png(#[from] png::EncodingError),
+    /// The recording format cannot be guessed from the file extension.
+    #[error("Unknown recording file extension.")]
+    UnknownFormat,
+    /// A frame has a different size than the first frame.
+    #[error("Frame size changed from {0}x{1} to {2}x{3} while recording.")]
+    SizeChanged(u32, u32, u32, u32),
+    /// The frames are too large for the format.
+    #[error("Frame size {0}x{1} is too large for the recording format.")]
+    TooLarge(u32, u32),
+    /// The encoder thread panicked.
+    #[error("The encoder thread panicked.")]
+    EncoderPanicked,
+}
+
+/// The formats that frames can be recorded to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RecordFormat {
+    /// Animated GIF. Colors are quantized to a palette for each frame, and pixels with zero alpha
+    /// are transparent. Frame delays are rounded to hundredths of a second.
+    Gif,
+    /// Animated PNG, with lossless colors and alpha. Frames are compressed as they are captured,
+    /// but kept in memory until the recording is finished.
+    Apng,
+    /// Uncompressed YUV4MPEG2 video with 4:2:0 chroma subsampling, at a constant frame rate. Frames
+    /// are repeated or dropped to follow the capture timestamps, and alpha is ignored.
+    Y4m,
+}
+
+impl RecordFormat {
+    /// Guess the format from a file extension, e.g. `gif`, `png`, `apng`, or `y4m`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "gif" => Some(Self::Gif),
+            "png" | "apng" => Some(Self::Apng),
+            "y4m" => Some(Self::Y4m),
+            _ => None,
+        }
+    }
+}
+
+/// Records frames on a background thread.
+///
+/// See the [module documentation](self) for more information.
+pub struct Recorder {
+    // The background thread is started by the first capture, when the frame rate is final
+    pending: Option<(Box<dyn Write + Send>, RecordFormat)>,
+    encoder: Option<Encoder>,
+    resolution: Resolution,
+    start: Instant,
+    frame_rate: Option<u32>,
+    last_capture: Option<Duration>,
+    dropped: u64,
+    // Rendered frames being copied from the GPU, and staging buffers for reuse
+    readbacks: VecDeque<(Readback, Duration)>,
+    staging: Vec<wgpu::Buffer>,
+}
+
+/// The channel to the background thread, and its handle.
+struct Encoder {
+    sender: SyncSender<(RgbaImage, Duration)>,
+    thread: JoinHandle<Result<(), RecordError>>,
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder")
+            .field("resolution", &self.resolution)
+            .field("frame_rate", &self.frame_rate)
+            .field("last_capture", &self.last_capture)
+            .field("dropped", &self.dropped)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Recorder {
+    /// Create a recorder that encodes frames to a writer.
+    ///
+    /// Frames are captured at the given resolution when the recorder is used with
+    /// [`Pixels::start_recording`](crate::Pixels::start_recording). Capture timestamps start
+    /// when the recorder is created.
+    pub fn new<W>(writer: W, format: RecordFormat, resolution: Resolution) -> Self
+    where
+        W: Write + Send + 'static,
+    {
+        Self {
+            pending: Some((Box::new(writer), format)),
+            encoder: None,
+            resolution,
+            start: Instant::now(),
+            frame_rate: None,
+            last_capture: None,
+            dropped: 0,
+            readbacks: VecDeque::new(),
+            staging: Vec::new(),
+        }
+    }
+
+    /// Create a recorder that encodes frames to a file.
+    ///
+    /// The format is guessed from the file extension with [`RecordFormat::from_extension`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the file extension is not recognized, or when the file cannot be
+    /// created.
+    pub fn create<P: AsRef<Path>>(path: P, resolution: Resolution) -> Result<Self, RecordError> {
+        let path = path.as_ref();
+        let format = path
+            .extension()
+            .and_then(|extension| RecordFormat::from_extension(&extension.to_string_lossy()))
+            .ok_or(RecordError::UnknownFormat)?;
+        let file = BufWriter::new(File::create(path)?);
+
+        Ok(Self::new(file, format, resolution))
+    }
+
+    /// Limit the number of frames captured per second.
+    ///
+    /// Frames that are captured sooner than `1 / fps` seconds after the last captured frame are
+    /// skipped. This also sets the frame rate of YUV4MPEG2 streams, which is 60 by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `fps` is 0, or when frames were already captured.
+    #[track_caller]
+    pub fn max_frame_rate(mut self, fps: u32) -> Self {
+        assert!(fps > 0, "Frame rate must be greater than 0");
+        assert!(
+            self.last_capture.is_none(),
+            "Frame rate must be set before capturing frames"
+        );
+        self.frame_rate = Some(fps);
+
+        self
+    }
+
+    /// The resolution at which frames are captured by [`Pixels`](crate::Pixels).
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Capture a frame, timestamped with the time since the recorder was created.
+    ///
+    /// The frame is skipped when it comes too soon after the last captured frame; see
+    /// [`Recorder::max_frame_rate`]. All frames must have the same size. When the encoder falls
+    /// behind, the frame is dropped instead of queued; see [`Recorder::dropped_frames`].
+    ///
+    /// Encoding errors are not reported here. They are returned by [`Recorder::finish`], and no
+    /// more frames are encoded after an error.
+    pub fn capture(&mut self, image: RgbaImage) {
+        self.capture_at(image, self.start.elapsed());
+    }
+
+    /// Capture a frame with an explicit timestamp, e.g. the simulation time of a game with a
+    /// fixed time step.
+    ///
+    /// Timestamps are relative to the start of the recording, and must not decrease. See
+    /// [`Recorder::capture`] for more information.
+    pub fn capture_at(&mut self, image: RgbaImage, timestamp: Duration) {
+        if !self.wants_frame_at(timestamp) {
+            return;
+        }
+        self.last_capture = Some(timestamp);
+        self.send(image, timestamp);
+    }
+
+    /// The number of frames that were dropped because the encoder or the GPU copies fell behind.
+    ///
+    /// Frames skipped because of [`Recorder::max_frame_rate`] are not counted.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Send a frame to the background thread, starting it for the first frame.
+    pub(crate) fn send(&mut self, image: RgbaImage, timestamp: Duration) {
+        if let Some((writer, format)) = self.pending.take() {
+            let frame_rate = self.frame_rate.unwrap_or(DEFAULT_FRAME_RATE);
+            let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED_FRAMES);
+            let thread = std::thread::Builder::new()
+                .name("pixels-recorder".into())
+                .spawn(move || encode(writer, format, frame_rate, receiver))
+                .expect("Unable to spawn the recorder thread");
+
+            self.encoder = Some(Encoder { sender, thread });
+        }
+
+        if let Some(encoder) = &self.encoder {
+            match encoder.sender.try_send((image, timestamp)) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => self.dropped += 1,
+                // The encoder stopped with an error, which `finish` returns
+                Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+    }
+
+    /// Reserve a timestamp for a rendered frame, or `None` when the frame is skipped.
+    ///
+    /// Frames are skipped because of the frame rate limit, or dropped when
+    /// [`MAX_PENDING_READBACKS`] surface resolution frames are still being copied from the GPU.
+    pub(crate) fn reserve_frame(&mut self) -> Option<Duration> {
+        let timestamp = self.start.elapsed();
+        if !self.wants_frame_at(timestamp) {
+            return None;
+        }
+        self.last_capture = Some(timestamp);
+        if self.resolution == Resolution::Surface && self.readbacks.len() >= MAX_PENDING_READBACKS {
+            self.dropped += 1;
+            return None;
+        }
+
+        Some(timestamp)
+    }
+
+    /// Start copying a rendered texture from the GPU, to be captured with the timestamp from
+    /// [`Recorder::reserve_frame`] once it is read back.
+    pub(crate) fn capture_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        timestamp: Duration,
+    ) -> Result<(), crate::Error> {
+        let readback = Readback::new(device, queue, texture, self.staging.pop())?;
+        self.readbacks.push_back((readback, timestamp));
+
+        Ok(())
+    }
+
+    /// Capture the frames that have been read back from the GPU, in order.
+    ///
+    /// This polls the device without blocking, unless `wait` is `true`. Waiting reads back every
+    /// frame and releases the staging buffers, e.g. when recording stops.
+    pub(crate) fn receive_readbacks(&mut self, device: &wgpu::Device, wait: bool) {
+        if !self.readbacks.is_empty() {
+            device.poll(if wait {
+                wgpu::Maintain::Wait
+            } else {
+                wgpu::Maintain::Poll
+            });
+        }
+
+        while self
+            .readbacks
+            .front()
+            .is_some_and(|(readback, _)| readback.is_ready())
+        {
+            let (readback, timestamp) = self.readbacks.pop_front().unwrap();
+            let (image, buffer) = readback.finish();
+            self.staging.push(buffer);
+            match image {
+                Ok(image) => self.send(image, timestamp),
+                Err(_) => self.dropped += 1,
+            }
+        }
+
+        if wait {
+            self.staging.clear();
+        }
+    }
+
+    /// Drop the frames being copied from the GPU, and the staging buffers, e.g. when the device
+    /// was lost.
+    pub(crate) fn discard_readbacks(&mut self) {
+        self.dropped += self.readbacks.len() as u64;
+        self.readbacks.clear();
+        self.staging.clear();
+    }
+
+    /// Whether a frame captured now would be recorded, or skipped because of the frame rate
+    /// limit.
+    pub fn wants_frame(&self) -> bool {
+        self.wants_frame_at(self.start.elapsed())
+    }
+
+    fn wants_frame_at(&self, timestamp: Duration) -> bool {
+        let min_interval = self
+            .frame_rate
+            .map_or(Duration::ZERO, |fps| Duration::from_secs(1) / fps);
+
+        self.last_capture
+            .map_or(true, |last| timestamp.saturating_sub(last) >= min_interval)
+    }
+
+    /// Stop recording, and wait for the background thread to encode the remaining frames.
+    ///
+    /// Nothing is written when no frames were captured.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error that occurred while encoding or writing frames.
+    pub fn finish(self) -> Result<(), RecordError> {
+        if let Some((mut writer, _)) = self.pending {
+            writer.flush()?;
+        }
+
+        match self.encoder {
+            Some(Encoder { sender, thread }) => {
+                drop(sender);
+
+                thread.join().unwrap_or(Err(RecordError::EncoderPanicked))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// An encoder for one of the [`RecordFormat`]s.
+trait FrameEncoder {
+    /// Encode a frame that is shown for the given duration.
+    fn frame(&mut self, image: &RgbaImage, delay: Duration) -> Result<(), RecordError>;
+
+    /// Finish encoding and flush the writer.
+    fn finish(self: Box<Self>) -> Result<(), RecordError>;
+}
+
+/// Encode frames from the channel until it is closed. This runs on the background thread.
+fn encode(
+    writer: Box<dyn Write + Send>,
+    format: RecordFormat,
+    frame_rate: u32,
+    receiver: Receiver<(RgbaImage, Duration)>,
+) -> Result<(), RecordError> {
+    let mut encoder: Box<dyn FrameEncoder> = match format {
+        RecordFormat::Gif => Box::new(GifEncoder::new(writer)),
+        RecordFormat::Apng => Box::new(ApngEncoder::new(writer)),
+        RecordFormat::Y4m => Box::new(Y4mEncoder::new(writer, frame_rate)),
+    };
+
+    // Each frame is held back until the next one arrives, which decides how long it is shown
+    let mut pending: Option<(RgbaImage, Duration)> = None;
+    let mut delay = Duration::from_secs(1) / frame_rate;
+    for (image, timestamp) in receiver {
+        if let Some((first, _)) = &pending {
+            let (width, height) = (first.width(), first.height());
+            if (image.width(), image.height()) != (width, height) {
+                return Err(RecordError::SizeChanged(
+                    width,
+                    height,
+                    image.width(),
+                    image.height(),
+                ));
+            }
+        }
+
+        if let Some((previous, previous_timestamp)) = pending.replace((image, timestamp)) {
+            delay = timestamp.saturating_sub(previous_timestamp);
+            encoder.frame(&previous, delay)?;
+        }
+    }
+    if let Some((last, _)) = pending {
+        encoder.frame(&last, delay)?;
+    }
+
+    encoder.finish()
+}
+
+/// Encodes animated GIFs with a quantized palette for each frame.
+struct GifEncoder<W: Write> {
+    writer: Option<W>,
+    encoder: Option<gif::Encoder<W>>,
+}
+
+impl<W: Write> GifEncoder<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer: Some(writer),
+            encoder: None,
+        }
+    }
+}
+
+impl<W: Write> FrameEncoder for GifEncoder<W> {
+    fn frame(&mut self, image: &RgbaImage, delay: Duration) -> Result<(), RecordError> {
+        let too_large = || RecordError::TooLarge(image.width(), image.height());
+        let width = u16::try_from(image.width()).map_err(|_| too_large())?;
+        let height = u16::try_from(image.height()).map_err(|_| too_large())?;
+
+        let encoder = match (&mut self.encoder, self.writer.take()) {
+            (Some(encoder), _) => encoder,
+            (None, Some(writer)) => {
+                let mut encoder = gif::Encoder::new(writer, width, height, &[])?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                self.encoder.insert(encoder)
+            }
+            (None, None) => unreachable!(),
+        };
+
+        // Speed 10 is the default quality of the NeuQuant quantizer
+        let mut pixels = image.pixels().to_vec();
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+        frame.dispose = gif::DisposalMethod::Background;
+        // Many viewers show delays below 2 hundredths of a second much slower
+        let centiseconds = (delay.as_millis() as f64 / 10.0).round();
+        frame.delay = centiseconds.clamp(2.0, u16::MAX as f64) as u16;
+        encoder.write_frame(&frame)?;
+
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), RecordError> {
+        let mut writer = match (self.encoder, self.writer) {
+            (Some(encoder), _) => encoder.into_inner()?,
+            (None, Some(writer)) => writer,
+            (None, None) => unreachable!(),
+        };
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Encodes animated PNGs.
+///
+/// The number of frames is stored before the first frame, so compressed frames are kept until
+/// the recording is finished.
+struct ApngEncoder<W: Write> {
+    writer: W,
+    size: (u32, u32),
+    frames: Vec<(Vec<u8>, Duration)>,
+}
+
+impl<W: Write> ApngEncoder<W> {
+    fn new(writer: W) -> Self {
+        Self {
+            writer,
+            size: (0, 0),
+            frames: Vec::new(),
+        }
+    }
+
+    fn write_chunk(&mut self, kind: &[u8; 4], data: &[u8]) -> Result<(), RecordError> {
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(kind);
+        crc.update(data);
+
+        self.writer.write_all(&(data.len() as u32).to_be_bytes())?;
+        self.writer.write_all(kind)?;
+        self.writer.write_all(data)?;
+        self.writer.write_all(&crc.finalize().to_be_bytes())?;
+
+        Ok(())
+    }
+}
+
+impl<W: Write> FrameEncoder for ApngEncoder<W> {
+    fn frame(&mut self, image: &RgbaImage, delay: Duration) -> Result<(), RecordError> {
+        self.size = (image.width(), image.height());
+
+        // Encode a single PNG and keep the compressed image data
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, image.width(), image.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(image.pixels())?;
+        writer.finish()?;
+
+        let mut data = Vec::new();
+        let mut chunks = &png[8..];
+        while chunks.len() >= 12 {
+            let length = u32::from_be_bytes([chunks[0], chunks[1], chunks[2], chunks[3]]) as usize;
+            if &chunks[4..8] == b"IDAT" {
+                data.extend_from_slice(&chunks[8..8 + length]);
+            }
+            chunks = &chunks[12 + length..];
+        }
+        self.frames.push((data, delay));
+
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), RecordError> {
+        if self.frames.is_empty() {
+            self.writer.flush()?;
+            return Ok(());
+        }
+
+        let (width, height) = self.size;
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        // 8 bits per channel, RGBA, default compression and filtering, not interlaced
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut animation = Vec::with_capacity(8);
+        animation.extend_from_slice(&(self.frames.len() as u32).to_be_bytes());
+        // Loop forever
+        animation.extend_from_slice(&0_u32.to_be_bytes());
+
+        self.writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+        self.write_chunk(b"IHDR", &header)?;
+        self.write_chunk(b"sRGB", &[0])?;
+        self.write_chunk(b"acTL", &animation)?;
+
+        let frames = std::mem::take(&mut self.frames);
+        let mut sequence = 0_u32;
+        for (i, (data, delay)) in frames.iter().enumerate() {
+            let milliseconds = delay.as_millis().min(u16::MAX as u128) as u16;
+            let mut control = Vec::with_capacity(26);
+            control.extend_from_slice(&sequence.to_be_bytes());
+            control.extend_from_slice(&width.to_be_bytes());
+            control.extend_from_slice(&height.to_be_bytes());
+            // Offset
+            control.extend_from_slice(&[0; 8]);
+            control.extend_from_slice(&milliseconds.to_be_bytes());
+            control.extend_from_slice(&1000_u16.to_be_bytes());
+            // No disposal, and replace the previous frame
+            control.extend_from_slice(&[0, 0]);
+            self.write_chunk(b"fcTL", &control)?;
+            sequence += 1;
+
+            if i == 0 {
+                self.write_chunk(b"IDAT", data)?;
+            } else {
+                let mut frame_data = Vec::with_capacity(4 + data.len());
+                frame_data.extend_from_slice(&sequence.to_be_bytes());
+                frame_data.extend_from_slice(data);
+                self.write_chunk(b"fdAT", &frame_data)?;
+                sequence += 1;
+            }
+        }
+
+        self.write_chunk(b"IEND", &[])?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Encodes uncompressed YUV4MPEG2 video at a constant frame rate.
+struct Y4mEncoder<W: Write> {
+    writer: W,
+    frame_rate: u32,
+    header_written: bool,
+    // The total duration of the encoded frames, and the number of frames written to cover it
+    elapsed: Duration,
+    written: u64,
+}
+
+impl<W: Write> Y4mEncoder<W> {
+    fn new(writer: W, frame_rate: u32) -> Self {
+        Self {
+            writer,
+            frame_rate,
+            header_written: false,
+            elapsed: Duration::ZERO,
+            written: 0,
+        }
+    }
+}
+
+impl<W: Write> FrameEncoder for Y4mEncoder<W> {
+    fn frame(&mut self, image: &RgbaImage, delay: Duration) -> Result<(), RecordError> {
+        let (width, height) = (image.width(), image.height());
+        if !self.header_written {
+            writeln!(
+                self.writer,
+                "YUV4MPEG2 W{width} H{height} F{}:1 Ip A1:1 C420jpeg",
+                self.frame_rate,
+            )?;
+            self.header_written = true;
+        }
+
+        // Repeat or drop the frame to keep up with the timestamps
+        self.elapsed += delay;
+        let total = (self.elapsed.as_secs_f64() * self.frame_rate as f64).round() as u64;
+        let repeat = total.saturating_sub(self.written);
+        if repeat == 0 {
+            return Ok(());
+        }
+        self.written = total;
+
+        let planes = rgba_to_yuv420(image);
+        for _ in 0..repeat {
+            self.writer.write_all(b"FRAME\n")?;
+            self.writer.write_all(&planes)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), RecordError> {
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Convert an image to planar YUV with BT.601 limited range colors, and chroma averaged over
+/// 2x2 blocks.
+fn rgba_to_yuv420(image: &RgbaImage) -> Vec<u8> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+    let pixel = |x: usize, y: usize| {
+        let offset = (y * width + x) * 4;
+        let rgb = &image.pixels()[offset..offset + 3];
+
+        [rgb[0] as i32, rgb[1] as i32, rgb[2] as i32]
+    };
+
+    let mut planes = Vec::with_capacity(width * height + chroma_width * chroma_height * 2);
+    for y in 0..height {
+        for x in 0..width {
+            let [r, g, b] = pixel(x, y);
+            planes.push((((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8);
+        }
+    }
+
+    let mut u_plane = Vec::with_capacity(chroma_width * chroma_height);
+    let mut v_plane = Vec::with_capacity(chroma_width * chroma_height);
+    for y in (0..height).step_by(2) {
+        for x in (0..width).step_by(2) {
+            let mut sum = [0; 3];
+            let mut count = 0;
+            for (x, y) in [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)] {
+                if x < width && y < height {
+                    let rgb = pixel(x, y);
+                    sum = [sum[0] + rgb[0], sum[1] + rgb[1], sum[2] + rgb[2]];
+                    count += 1;
+                }
+            }
+            let [r, g, b] = sum.map(|channel| (channel + count / 2) / count);
+
+            u_plane.push((((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8);
+            v_plane.push((((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8);
+        }
+    }
+    planes.extend(u_plane);
+    planes.extend(v_plane);
+
+    planes
+}
@@ -1,4 +1,9 @@
-use crate::{ScalingMode, SurfaceSize};
+use crate::layers::CompositorPipelines;
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+use crate::shader::{ShaderError, ShaderWatcher};
+use crate::{Anchor, ScalingFilter, ScalingMode, SurfaceSize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
 use ultraviolet::Mat4;
 use wgpu::util::DeviceExt;
 
@@ -6,35 +11,66 @@ use wgpu::util::DeviceExt;
 #[derive(Debug)]
 pub struct ScalingRenderer {
     vertex_buffer: wgpu::Buffer,
-    uniform_buffer: wgpu::Buffer,
-    bind_group_nearest: wgpu::BindGroup,
-    bind_group_linear: wgpu::BindGroup,
-    render_pipeline: wgpu::RenderPipeline,
-    render_pipeline_fill: wgpu::RenderPipeline,
+    sampler_nearest: wgpu::Sampler,
+    sampler_linear: wgpu::Sampler,
+    // Draws to the surface and to the intermediate textures of the post-processing passes
+    surface: ScalingTarget,
+    // Draws to the texture views given to `Pixels::render_to`
+    target: ScalingTarget,
+    // Shared with every renderer on the device that has the same format and blend state
+    pipelines: Arc<ScalingPipelines>,
+    // Replaces the default pipelines when a scaling filter is selected
+    render_pipeline_filter: Option<Arc<wgpu::RenderPipeline>>,
+    // Replaces all other pipelines when a custom shader is loaded from a file
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    custom_shader: Option<(ShaderWatcher, wgpu::RenderPipeline)>,
     pub(crate) clear_color: wgpu::Color,
     width: f32,
     height: f32,
+    pub(crate) options: ScalingOptions,
+}
+
+/// The uniform buffer and bind groups of the scaling renderer for one render target size.
+#[derive(Debug)]
+struct ScalingTarget {
+    size: (u32, u32),
+    uniform_buffer: wgpu::Buffer,
+    bind_group_nearest: wgpu::BindGroup,
+    bind_group_linear: wgpu::BindGroup,
     clip_rect: (u32, u32, u32, u32),
+    viewport: (u32, u32, u32, u32),
+    full_viewport: bool,
+    smooth: bool,
+}
+
+/// Options that control how the pixel buffer is scaled and positioned on the surface.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ScalingOptions {
     pub(crate) scaling_mode: ScalingMode,
+    pub(crate) filter: ScalingFilter,
+    // The width of a single pixel relative to its height
+    pub(crate) pixel_aspect_ratio: f32,
+    pub(crate) anchor: Anchor,
+    // Offset from the anchor position in physical pixels
+    pub(crate) anchor_offset: (i32, i32),
+    // The area of the surface to scale into, or `None` for the whole surface
+    pub(crate) viewport: Option<(u32, u32, u32, u32)>,
 }
 
 impl ScalingRenderer {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         device: &wgpu::Device,
+        pipeline_cache: &PipelineCache,
         texture_view: &wgpu::TextureView,
         texture_size: &wgpu::Extent3d,
         surface_size: &SurfaceSize,
         render_texture_format: wgpu::TextureFormat,
         clear_color: wgpu::Color,
         blend_state: wgpu::BlendState,
-        scaling_mode: ScalingMode,
+        options: ScalingOptions,
     ) -> Self {
-        let shader = wgpu::include_wgsl!("../shaders/scale.wgsl");
-        let module = device.create_shader_module(shader);
-
-        let shader_fill = wgpu::include_wgsl!("../shaders/scale_fill.wgsl");
-        let module_fill = device.create_shader_module(shader_fill);
+        let pipelines = pipeline_cache.scaling(device, render_texture_format, blend_state);
 
         // Create a texture sampler with nearest neighbor
         let sampler_nearest = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -81,175 +117,87 @@ impl ScalingRenderer {
             contents: vertex_data_slice,
             usage: wgpu::BufferUsages::VERTEX,
         });
-        let vertex_buffer_layout = wgpu::VertexBufferLayout {
-            array_stride: (vertex_data_slice.len() / vertex_data.len()) as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &[wgpu::VertexAttribute {
-                format: wgpu::VertexFormat::Float32x2,
-                offset: 0,
-                shader_location: 0,
-            }],
-        };
-
-        // Create uniform buffer
-        let matrix = ScalingMatrix::new(
-            (texture_size.width as f32, texture_size.height as f32),
-            (surface_size.width as f32, surface_size.height as f32),
-            scaling_mode,
-        );
-        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("pixels_scaling_renderer_matrix_uniform_buffer"),
-            contents: matrix.uniform_buffer.as_slice(),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        // Create bind group
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("pixels_scaling_renderer_bind_group_layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: wgpu::BufferSize::new(matrix.uniform_buffer.len() as u64),
-                    },
-                    count: None,
-                },
-            ],
-        });
-        let bind_group_nearest = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("pixels_scaling_renderer_bind_group_nearest"),
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler_nearest),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: uniform_buffer.as_entire_binding(),
-                },
-            ],
-        });
-        let bind_group_linear = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("pixels_scaling_renderer_bind_group_linear"),
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler_linear),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: uniform_buffer.as_entire_binding(),
-                },
-            ],
-        });
 
-        // Create pipeline
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("pixels_scaling_renderer_pipeline_layout"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
-        });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("pixels_scaling_renderer_pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &module,
-                entry_point: "vs_main",
-                buffers: std::slice::from_ref(&vertex_buffer_layout),
-            },
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            fragment: Some(wgpu::FragmentState {
-                module: &module,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: render_texture_format,
-                    blend: Some(blend_state),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview: None,
-        });
-        let render_pipeline_fill = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("pixels_scaling_renderer_pipeline_fill"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &module_fill,
-                entry_point: "vs_main",
-                buffers: &[vertex_buffer_layout],
-            },
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            fragment: Some(wgpu::FragmentState {
-                module: &module_fill,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: render_texture_format,
-                    blend: Some(blend_state),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview: None,
-        });
+        let render_pipeline_filter = pipelines.filter(device, options.filter);
 
-        // Create clipping rectangle
-        let clip_rect = matrix.clip_rect();
+        let width = texture_size.width as f32;
+        let height = texture_size.height as f32;
+        let size = (surface_size.width, surface_size.height);
+        let matrix = ScalingMatrix::new((width, height), (size.0 as f32, size.1 as f32), &options);
+        let samplers = (&sampler_nearest, &sampler_linear);
+        let surface = ScalingTarget::new(
+            device,
+            &pipelines.bind_group_layout,
+            texture_view,
+            samplers,
+            size,
+            &matrix,
+            clear_color,
+        );
+        let target = ScalingTarget::new(
+            device,
+            &pipelines.bind_group_layout,
+            texture_view,
+            samplers,
+            size,
+            &matrix,
+            clear_color,
+        );
 
         Self {
             vertex_buffer,
-            uniform_buffer,
-            bind_group_nearest,
-            bind_group_linear,
-            render_pipeline,
-            render_pipeline_fill,
+            sampler_nearest,
+            sampler_linear,
+            surface,
+            target,
+            pipelines,
+            render_pipeline_filter,
+            #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+            custom_shader: None,
             clear_color,
-            width: texture_size.width as f32,
-            height: texture_size.height as f32,
-            clip_rect,
-            scaling_mode,
+            width,
+            height,
+            options,
         }
     }
 
     /// Draw the pixel buffer to the render target.
+    ///
+    /// The render target is cleared with the clear color first. When a viewport is set with
+    /// [`Pixels::set_viewport`](crate::Pixels::set_viewport), only the viewport is cleared and
+    /// drawn to; the rest of the render target is left untouched.
     pub fn render(&self, encoder: &mut wgpu::CommandEncoder, render_target: &wgpu::TextureView) {
+        self.draw(encoder, render_target, &self.surface);
+    }
+
+    /// Draw the pixel buffer to a render target with the size given to
+    /// [`ScalingRenderer::resize_target`].
+    pub(crate) fn render_target(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &wgpu::TextureView,
+    ) {
+        self.draw(encoder, render_target, &self.target);
+    }
+
+    fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &wgpu::TextureView,
+        target: &ScalingTarget,
+    ) {
+        let load = if target.full_viewport {
+            wgpu::LoadOp::Clear(self.clear_color)
+        } else {
+            wgpu::LoadOp::Load
+        };
         let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("pixels_scaling_renderer_render_pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                 view: render_target,
                 resolve_target: None,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(self.clear_color),
+                    load,
                     store: wgpu::StoreOp::Store,
                 },
             })],
@@ -257,22 +205,46 @@ impl ScalingRenderer {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
-        let pipeline = match self.scaling_mode {
-            ScalingMode::PixelPerfect => &self.render_pipeline,
-            ScalingMode::Fill => &self.render_pipeline_fill,
-        };
-        rpass.set_pipeline(pipeline);
-        let bind_group = match self.scaling_mode {
-            ScalingMode::PixelPerfect => &self.bind_group_nearest,
-            ScalingMode::Fill => &self.bind_group_linear,
+        #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+        let custom_pipeline = self.custom_shader.as_ref().map(|(_, pipeline)| pipeline);
+        #[cfg(not(all(feature = "hot-reload", not(target_arch = "wasm32"))))]
+        let custom_pipeline = None;
+
+        let (pipeline, bind_group) = if let Some(pipeline) = custom_pipeline {
+            let bind_group = if target.smooth {
+                &target.bind_group_linear
+            } else {
+                &target.bind_group_nearest
+            };
+            (pipeline, bind_group)
+        } else if let Some(pipeline) = &self.render_pipeline_filter {
+            (&**pipeline, &target.bind_group_linear)
+        } else if target.smooth {
+            (
+                &self.pipelines.render_pipeline_fill,
+                &target.bind_group_linear,
+            )
+        } else {
+            (&self.pipelines.render_pipeline, &target.bind_group_nearest)
         };
         rpass.set_bind_group(0, bind_group, &[]);
         rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        if !target.full_viewport {
+            rpass.set_pipeline(&self.pipelines.render_pipeline_clear);
+            rpass.set_scissor_rect(
+                target.viewport.0,
+                target.viewport.1,
+                target.viewport.2,
+                target.viewport.3,
+            );
+            rpass.draw(0..3, 0..1);
+        }
+        rpass.set_pipeline(pipeline);
         rpass.set_scissor_rect(
-            self.clip_rect.0,
-            self.clip_rect.1,
-            self.clip_rect.2,
-            self.clip_rect.3,
+            target.clip_rect.0,
+            target.clip_rect.1,
+            target.clip_rect.2,
+            target.clip_rect.3,
         );
         rpass.draw(0..3, 0..1);
     }
@@ -281,97 +253,1272 @@ impl ScalingRenderer {
     ///
     /// This rectangle defines the inner bounds of the surface texture, without the border.
     pub fn clip_rect(&self) -> (u32, u32, u32, u32) {
-        self.clip_rect
+        self.surface.clip_rect
     }
 
-    pub(crate) fn resize(&mut self, queue: &wgpu::Queue, width: u32, height: u32) {
-        let matrix = ScalingMatrix::new(
-            (self.width, self.height),
-            (width as f32, height as f32),
-            self.scaling_mode,
-        );
-        queue.write_buffer(&self.uniform_buffer, 0, &matrix.uniform_buffer);
+    pub(crate) fn set_filter(&mut self, device: &wgpu::Device, filter: ScalingFilter) {
+        if filter == self.options.filter {
+            return;
+        }
 
-        self.clip_rect = matrix.clip_rect();
+        self.options.filter = filter;
+        self.render_pipeline_filter = self.pipelines.filter(device, filter);
     }
-}
-
-#[derive(Debug)]
-pub(crate) struct ScalingMatrix {
-    pub(crate) transform: Mat4,
-    clip_rect: (u32, u32, u32, u32),
-    uniform_buffer: Vec<u8>,
-}
 
-impl ScalingMatrix {
-    // texture_size is the dimensions of the drawing texture
-    // screen_size is the dimensions of the surface being drawn to
-    pub(crate) fn new(
-        texture_size: (f32, f32),
-        screen_size: (f32, f32),
-        scaling_mode: ScalingMode,
-    ) -> Self {
-        let (texture_width, texture_height) = texture_size;
-        let (screen_width, screen_height) = screen_size;
+    /// Sample a different texture with the same size, e.g. the composited layers.
+    pub(crate) fn set_input(&mut self, device: &wgpu::Device, texture_view: &wgpu::TextureView) {
+        let samplers = (&self.sampler_nearest, &self.sampler_linear);
+        for target in [&mut self.surface, &mut self.target] {
+            target.set_input(
+                device,
+                &self.pipelines.bind_group_layout,
+                texture_view,
+                samplers,
+            );
+        }
+    }
 
-        let (scaled_width, scaled_height) = match scaling_mode {
-            ScalingMode::PixelPerfect => {
-                // Scale up to nearest integer multiple of screen size
-                let width_ratio = (screen_width / texture_width).max(1.0);
-                let height_ratio = (screen_height / texture_height).max(1.0);
-                let scale = width_ratio.min(height_ratio).floor().max(1.0);
-                (texture_width * scale, texture_height * scale)
-            }
-            ScalingMode::Fill => {
-                // Scale up or down while preserving aspect ratio
-                let width_ratio = screen_width / texture_width;
-                let height_ratio = screen_height / texture_height;
-                let scale = width_ratio.min(height_ratio);
-                (texture_width * scale, texture_height * scale)
+    /// Replace the scaling shader with a WGSL file, or restore the default shaders with `None`.
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    pub(crate) fn set_shader(
+        &mut self,
+        device: &wgpu::Device,
+        path: Option<&std::path::Path>,
+    ) -> Result<(), ShaderError> {
+        self.custom_shader = match path {
+            Some(path) => {
+                let watcher = ShaderWatcher::new(device, path)?;
+                let pipeline = watcher.build(device, |module| {
+                    create_pipeline(
+                        device,
+                        "pixels_scaling_renderer_pipeline_custom",
+                        &self.pipelines.pipeline_layout,
+                        module,
+                        "fs_main",
+                        self.pipelines.format,
+                        Some(self.pipelines.blend_state),
+                    )
+                })?;
+                Some((watcher, pipeline))
             }
+            None => None,
         };
 
-        // Create a transformation matrix
-        let sw = scaled_width / screen_width;
-        let sh = scaled_height / screen_height;
-        let tx = (screen_width / 2.0).fract() / screen_width;
-        let ty = (screen_height / 2.0).fract() / screen_height;
-        #[rustfmt::skip]
-        let transform: [f32; 16] = [
-            sw,  0.0, 0.0, 0.0,
-            0.0, sh,  0.0, 0.0,
-            0.0, 0.0, 1.0, 0.0,
-            tx,  ty,  0.0, 1.0,
-        ];
-
-        // Create a clipping rectangle
-        let clip_rect = {
-            let scaled_width = scaled_width.min(screen_width);
-            let scaled_height = scaled_height.min(screen_height);
-            let x = ((screen_width - scaled_width) / 2.0) as u32;
-            let y = ((screen_height - scaled_height) / 2.0) as u32;
+        Ok(())
+    }
 
-            (x, y, scaled_width as u32, scaled_height as u32)
+    /// Recompile the custom scaling shader if its file was modified.
+    ///
+    /// The previous pipeline is kept when the shader fails to compile.
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    pub(crate) fn reload_shader(&mut self, device: &wgpu::Device) -> Result<bool, ShaderError> {
+        let Some((watcher, pipeline)) = &mut self.custom_shader else {
+            return Ok(false);
         };
+        if !watcher.poll(device)? {
+            return Ok(false);
+        }
 
-        let mat = Mat4::from(transform);
+        *pipeline = watcher.build(device, |module| {
+            create_pipeline(
+                device,
+                "pixels_scaling_renderer_pipeline_custom",
+                &self.pipelines.pipeline_layout,
+                module,
+                "fs_main",
+                self.pipelines.format,
+                Some(self.pipelines.blend_state),
+            )
+        })?;
 
-        // Compute the constant buffer
-        let mut uniform_buffer = Vec::new();
-        uniform_buffer.extend_from_slice(mat.as_byte_slice());
-        uniform_buffer.extend_from_slice(&texture_width.to_le_bytes());
-        uniform_buffer.extend_from_slice(&texture_height.to_le_bytes());
-        uniform_buffer.extend_from_slice(&(1.0 / texture_width).to_le_bytes());
-        uniform_buffer.extend_from_slice(&(1.0 / texture_height).to_le_bytes());
+        Ok(true)
+    }
+
+    /// Update the scaling matrix for a new surface size, or for changed options.
+    ///
+    /// The matrix for [`ScalingRenderer::render_target`] is updated with the options as well.
+    pub(crate) fn resize(&mut self, queue: &wgpu::Queue, width: u32, height: u32) {
+        let texture_size = (self.width, self.height);
+        let target_size = self.target.size;
+        self.surface.resize(
+            queue,
+            texture_size,
+            &self.options,
+            self.clear_color,
+            (width, height),
+        );
+        self.target.resize(
+            queue,
+            texture_size,
+            &self.options,
+            self.clear_color,
+            target_size,
+        );
+    }
+
+    /// Update the scaling matrix for [`ScalingRenderer::render_target`] when the size of the
+    /// render target changed.
+    pub(crate) fn resize_target(&mut self, queue: &wgpu::Queue, width: u32, height: u32) {
+        if self.target.size == (width, height) {
+            return;
+        }
+
+        self.target.resize(
+            queue,
+            (self.width, self.height),
+            &self.options,
+            self.clear_color,
+            (width, height),
+        );
+    }
+
+    /// Get the viewport for the scaling renderer.
+    ///
+    /// This rectangle defines the area of the surface texture that is cleared and drawn to. It
+    /// covers the whole surface texture unless a viewport is set.
+    pub fn viewport(&self) -> (u32, u32, u32, u32) {
+        self.surface.viewport
+    }
+}
+
+impl ScalingTarget {
+    fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        texture_view: &wgpu::TextureView,
+        (sampler_nearest, sampler_linear): (&wgpu::Sampler, &wgpu::Sampler),
+        size: (u32, u32),
+        matrix: &ScalingMatrix,
+        clear_color: wgpu::Color,
+    ) -> Self {
+        // Create uniform buffer
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("pixels_scaling_renderer_matrix_uniform_buffer"),
+            contents: &uniform_data(matrix, clear_color),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Create bind group
+        let bind_group_nearest = create_bind_group(
+            device,
+            "pixels_scaling_renderer_bind_group_nearest",
+            layout,
+            texture_view,
+            sampler_nearest,
+            &uniform_buffer,
+        );
+        let bind_group_linear = create_bind_group(
+            device,
+            "pixels_scaling_renderer_bind_group_linear",
+            layout,
+            texture_view,
+            sampler_linear,
+            &uniform_buffer,
+        );
 
         Self {
-            transform: mat,
-            clip_rect,
+            size,
             uniform_buffer,
+            bind_group_nearest,
+            bind_group_linear,
+            clip_rect: matrix.clip_rect(),
+            viewport: matrix.viewport,
+            full_viewport: matrix.full_viewport,
+            smooth: matrix.smooth,
         }
     }
 
-    pub(crate) fn clip_rect(&self) -> (u32, u32, u32, u32) {
-        self.clip_rect
+    fn set_input(
+        &mut self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        texture_view: &wgpu::TextureView,
+        (sampler_nearest, sampler_linear): (&wgpu::Sampler, &wgpu::Sampler),
+    ) {
+        self.bind_group_nearest = create_bind_group(
+            device,
+            "pixels_scaling_renderer_bind_group_nearest",
+            layout,
+            texture_view,
+            sampler_nearest,
+            &self.uniform_buffer,
+        );
+        self.bind_group_linear = create_bind_group(
+            device,
+            "pixels_scaling_renderer_bind_group_linear",
+            layout,
+            texture_view,
+            sampler_linear,
+            &self.uniform_buffer,
+        );
+    }
+
+    fn resize(
+        &mut self,
+        queue: &wgpu::Queue,
+        (texture_width, texture_height): (f32, f32),
+        options: &ScalingOptions,
+        clear_color: wgpu::Color,
+        (width, height): (u32, u32),
+    ) {
+        let matrix = ScalingMatrix::new(
+            (texture_width, texture_height),
+            (width as f32, height as f32),
+            options,
+        );
+        queue.write_buffer(&self.uniform_buffer, 0, &uniform_data(&matrix, clear_color));
+
+        self.size = (width, height);
+        self.clip_rect = matrix.clip_rect();
+        self.viewport = matrix.viewport;
+        self.full_viewport = matrix.full_viewport;
+        self.smooth = matrix.smooth;
+    }
+}
+
+/// Shader modules and pipelines shared by every renderer on a device.
+///
+/// Pipelines only depend on the texture format they draw to (and the blend state for the
+/// [`ScalingRenderer`]), so pixel buffers that agree on those use the same pipelines. Everything
+/// is created on first use.
+#[derive(Debug, Default)]
+pub(crate) struct PipelineCache {
+    modules: OnceLock<Arc<ScalingModules>>,
+    scaling: Mutex<HashMap<(wgpu::TextureFormat, wgpu::BlendState), Arc<ScalingPipelines>>>,
+    // The palette renderer always draws to the same format
+    palette: OnceLock<Arc<PalettePipeline>>,
+    #[cfg(feature = "crt")]
+    crt_module: OnceLock<wgpu::ShaderModule>,
+    #[cfg(feature = "crt")]
+    crt: Mutex<HashMap<wgpu::TextureFormat, Arc<CrtPipeline>>>,
+    compositor_module: OnceLock<wgpu::ShaderModule>,
+    compositor: Mutex<HashMap<wgpu::TextureFormat, Arc<CompositorPipelines>>>,
+}
+
+impl PipelineCache {
+    /// Get the scaling pipelines for a render texture format and blend state.
+    pub(crate) fn scaling(
+        &self,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        blend_state: wgpu::BlendState,
+    ) -> Arc<ScalingPipelines> {
+        let modules = self
+            .modules
+            .get_or_init(|| Arc::new(ScalingModules::new(device)));
+
+        let mut scaling = self.scaling.lock().unwrap();
+        let pipelines = scaling.entry((format, blend_state)).or_insert_with(|| {
+            Arc::new(ScalingPipelines::new(
+                device,
+                Arc::clone(modules),
+                format,
+                blend_state,
+            ))
+        });
+
+        Arc::clone(pipelines)
+    }
+
+    /// Get the pipeline of the palette renderer.
+    pub(crate) fn palette(&self, device: &wgpu::Device) -> Arc<PalettePipeline> {
+        let pipeline = self
+            .palette
+            .get_or_init(|| Arc::new(PalettePipeline::new(device)));
+
+        Arc::clone(pipeline)
+    }
+
+    /// Get the pipeline of the CRT renderer for a render texture format.
+    #[cfg(feature = "crt")]
+    pub(crate) fn crt(
+        &self,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+    ) -> Arc<CrtPipeline> {
+        let module = self.crt_module.get_or_init(|| {
+            device.create_shader_module(wgpu::include_wgsl!("../shaders/crt.wgsl"))
+        });
+
+        let mut crt = self.crt.lock().unwrap();
+        let pipeline = crt
+            .entry(format)
+            .or_insert_with(|| Arc::new(CrtPipeline::new(device, module, format)));
+
+        Arc::clone(pipeline)
+    }
+
+    /// Get the pipelines of the layer compositor for an output texture format.
+    pub(crate) fn compositor(
+        &self,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+    ) -> Arc<CompositorPipelines> {
+        let module = self.compositor_module.get_or_init(|| {
+            device.create_shader_module(wgpu::include_wgsl!("../shaders/composite.wgsl"))
+        });
+
+        let mut compositor = self.compositor.lock().unwrap();
+        let pipelines = compositor
+            .entry(format)
+            .or_insert_with(|| Arc::new(CompositorPipelines::new(device, module, format)));
+
+        Arc::clone(pipelines)
+    }
+}
+
+/// The shader modules used by the scaling renderer.
+#[derive(Debug)]
+struct ScalingModules {
+    scale: wgpu::ShaderModule,
+    fill: wgpu::ShaderModule,
+    clear: wgpu::ShaderModule,
+    filter: wgpu::ShaderModule,
+}
+
+impl ScalingModules {
+    fn new(device: &wgpu::Device) -> Self {
+        Self {
+            scale: device.create_shader_module(wgpu::include_wgsl!("../shaders/scale.wgsl")),
+            fill: device.create_shader_module(wgpu::include_wgsl!("../shaders/scale_fill.wgsl")),
+            clear: device.create_shader_module(wgpu::include_wgsl!("../shaders/clear.wgsl")),
+            filter: device.create_shader_module(wgpu::include_wgsl!("../shaders/filter.wgsl")),
+        }
+    }
+}
+
+/// The layouts and pipelines of the scaling renderer for one render texture format and blend
+/// state.
+#[derive(Debug)]
+pub(crate) struct ScalingPipelines {
+    modules: Arc<ScalingModules>,
+    format: wgpu::TextureFormat,
+    blend_state: wgpu::BlendState,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    render_pipeline_fill: wgpu::RenderPipeline,
+    render_pipeline_clear: wgpu::RenderPipeline,
+    // Scaling filter pipelines are only created when a filter is selected
+    filters: Mutex<HashMap<ScalingFilter, Arc<wgpu::RenderPipeline>>>,
+}
+
+impl ScalingPipelines {
+    fn new(
+        device: &wgpu::Device,
+        modules: Arc<ScalingModules>,
+        format: wgpu::TextureFormat,
+        blend_state: wgpu::BlendState,
+    ) -> Self {
+        // The scaling matrix, the texture size and its inverse, and the clear color
+        let uniform_size = std::mem::size_of::<[f32; 16 + 4 + 4]>() as u64;
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_scaling_renderer_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(uniform_size),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("pixels_scaling_renderer_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = create_pipeline(
+            device,
+            "pixels_scaling_renderer_pipeline",
+            &pipeline_layout,
+            &modules.scale,
+            "fs_main",
+            format,
+            Some(blend_state),
+        );
+        let render_pipeline_fill = create_pipeline(
+            device,
+            "pixels_scaling_renderer_pipeline_fill",
+            &pipeline_layout,
+            &modules.fill,
+            "fs_main",
+            format,
+            Some(blend_state),
+        );
+
+        // The clear pipeline fills the viewport when it does not cover the whole surface
+        let render_pipeline_clear = create_pipeline(
+            device,
+            "pixels_scaling_renderer_pipeline_clear",
+            &pipeline_layout,
+            &modules.clear,
+            "fs_main",
+            format,
+            None,
+        );
+
+        Self {
+            modules,
+            format,
+            blend_state,
+            bind_group_layout,
+            pipeline_layout,
+            render_pipeline,
+            render_pipeline_fill,
+            render_pipeline_clear,
+            filters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the pipeline for a scaling filter, or `None` for the default pipelines.
+    fn filter(
+        &self,
+        device: &wgpu::Device,
+        filter: ScalingFilter,
+    ) -> Option<Arc<wgpu::RenderPipeline>> {
+        let entry_point = filter.entry_point()?;
+        let mut filters = self.filters.lock().unwrap();
+        let pipeline = filters.entry(filter).or_insert_with(|| {
+            Arc::new(create_pipeline(
+                device,
+                "pixels_scaling_renderer_pipeline_filter",
+                &self.pipeline_layout,
+                &self.modules.filter,
+                entry_point,
+                self.format,
+                Some(self.blend_state),
+            ))
+        });
+
+        Some(Arc::clone(pipeline))
+    }
+}
+
+/// Create a pipeline with the same interface as the default scaling shader, using the given
+/// fragment shader entry point.
+fn create_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    pipeline_layout: &wgpu::PipelineLayout,
+    module: &wgpu::ShaderModule,
+    entry_point: &str,
+    render_texture_format: wgpu::TextureFormat,
+    blend: Option<wgpu::BlendState>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module,
+            entry_point: "vs_main",
+            buffers: &[wgpu::VertexBufferLayout {
+                array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &[wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x2,
+                    offset: 0,
+                    shader_location: 0,
+                }],
+            }],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module,
+            entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format: render_texture_format,
+                blend,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+    })
+}
+
+/// Create a bind group for the scaling renderer that samples `texture_view` with `sampler`.
+fn create_bind_group(
+    device: &wgpu::Device,
+    label: &str,
+    layout: &wgpu::BindGroupLayout,
+    texture_view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+    uniform_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some(label),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+        ],
+    })
+}
+
+/// Build the contents of the uniform buffer: the scaling matrix followed by the clear color.
+fn uniform_data(matrix: &ScalingMatrix, clear_color: wgpu::Color) -> Vec<u8> {
+    let mut data = matrix.uniform_buffer.clone();
+    for channel in [clear_color.r, clear_color.g, clear_color.b, clear_color.a] {
+        data.extend_from_slice(&(channel as f32).to_le_bytes());
+    }
+
+    data
+}
+
+#[derive(Debug)]
+pub(crate) struct ScalingMatrix {
+    pub(crate) transform: Mat4,
+    clip_rect: (u32, u32, u32, u32),
+    uniform_buffer: Vec<u8>,
+    // Whether the scale is fractional, requiring the anti-aliased "fill" pipeline
+    smooth: bool,
+    // The viewport clipped to the screen, and whether it covers the whole screen
+    viewport: (u32, u32, u32, u32),
+    full_viewport: bool,
+    // The scaled buffer on the screen before clipping: x, y, width, height
+    #[cfg_attr(not(feature = "crt"), allow(dead_code))]
+    pub(crate) screen: (f32, f32, f32, f32),
+}
+
+impl ScalingMatrix {
+    // texture_size is the dimensions of the drawing texture
+    // screen_size is the dimensions of the surface being drawn to
+    pub(crate) fn new(
+        texture_size: (f32, f32),
+        screen_size: (f32, f32),
+        options: &ScalingOptions,
+    ) -> Self {
+        let (texture_width, texture_height) = texture_size;
+        let (screen_width, screen_height) = screen_size;
+
+        // The area of the screen to scale into
+        let (view_x, view_y, view_width, view_height) = options.viewport.map_or(
+            (0.0, 0.0, screen_width, screen_height),
+            |(x, y, width, height)| (x as f32, y as f32, width as f32, height as f32),
+        );
+
+        // The texture width after stretching by the pixel aspect ratio
+        let aspect_width = texture_width * options.pixel_aspect_ratio;
+
+        // Scale ratios that exactly fit the viewport on each axis
+        let width_ratio = view_width / aspect_width;
+        let height_ratio = view_height / texture_height;
+
+        let (scaled_width, scaled_height, smooth) = match options.scaling_mode {
+            ScalingMode::PixelPerfect => {
+                // Scale up to nearest integer multiple of screen size
+                // The integer scale applies vertically; the width is stretched by the aspect ratio
+                let scale = width_ratio.min(height_ratio).floor().max(1.0);
+                (aspect_width * scale, texture_height * scale, false)
+            }
+            ScalingMode::Fill => {
+                // Scale up or down while preserving aspect ratio
+                let scale = width_ratio.min(height_ratio);
+                (aspect_width * scale, texture_height * scale, true)
+            }
+            ScalingMode::Stretch => {
+                // Scale each axis independently to fill the viewport
+                (view_width, view_height, true)
+            }
+            ScalingMode::FitIntegerOrDown => {
+                // Scale up to nearest integer multiple, or down to fit while preserving aspect ratio
+                let scale = width_ratio.min(height_ratio);
+                if scale >= 1.0 {
+                    let scale = scale.floor();
+                    (aspect_width * scale, texture_height * scale, false)
+                } else {
+                    (aspect_width * scale, texture_height * scale, true)
+                }
+            }
+            ScalingMode::Fixed(scale) => {
+                // Scale by exactly the given integer multiple
+                let scale = scale.max(1) as f32;
+                (aspect_width * scale, texture_height * scale, false)
+            }
+            ScalingMode::Cover => {
+                // Scale up or down to cover the screen while preserving aspect ratio
+                let scale = width_ratio.max(height_ratio);
+                (aspect_width * scale, texture_height * scale, true)
+            }
+        };
+
+        // Position the scaled texture in the viewport relative to the anchor
+        let (anchor_x, anchor_y) = options.anchor.factors();
+        let x = view_x + (view_width - scaled_width) * anchor_x + options.anchor_offset.0 as f32;
+        let y = view_y + (view_height - scaled_height) * anchor_y + options.anchor_offset.1 as f32;
+
+        // Clip the viewport to the screen
+        let view_left = view_x.min(screen_width);
+        let view_top = view_y.min(screen_height);
+        let view_right = (view_x + view_width).min(screen_width);
+        let view_bottom = (view_y + view_height).min(screen_height);
+        let viewport = (
+            view_left as u32,
+            view_top as u32,
+            (view_right - view_left) as u32,
+            (view_bottom - view_top) as u32,
+        );
+        let full_viewport = viewport == (0, 0, screen_width as u32, screen_height as u32);
+
+        // Create a transformation matrix
+        let sw = scaled_width / screen_width;
+        let sh = scaled_height / screen_height;
+        let tx = (x * 2.0 + scaled_width) / screen_width - 1.0
+            + (screen_width / 2.0).fract() / screen_width;
+        let ty = 1.0 - (y * 2.0 + scaled_height) / screen_height
+            + (screen_height / 2.0).fract() / screen_height;
+        #[rustfmt::skip]
+        let transform: [f32; 16] = [
+            sw,  0.0, 0.0, 0.0,
+            0.0, sh,  0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            tx,  ty,  0.0, 1.0,
+        ];
+
+        // Create a clipping rectangle from the visible part of the scaled texture
+        let clip_rect = {
+            let left = x.clamp(view_left, view_right);
+            let top = y.clamp(view_top, view_bottom);
+            let right = (x + scaled_width).clamp(view_left, view_right);
+            let bottom = (y + scaled_height).clamp(view_top, view_bottom);
+
+            (
+                left as u32,
+                top as u32,
+                (right - left) as u32,
+                (bottom - top) as u32,
+            )
+        };
+
+        let mat = Mat4::from(transform);
+
+        // Compute the constant buffer
+        let mut uniform_buffer = Vec::new();
+        uniform_buffer.extend_from_slice(mat.as_byte_slice());
+        uniform_buffer.extend_from_slice(&texture_width.to_le_bytes());
+        uniform_buffer.extend_from_slice(&texture_height.to_le_bytes());
+        uniform_buffer.extend_from_slice(&(1.0 / texture_width).to_le_bytes());
+        uniform_buffer.extend_from_slice(&(1.0 / texture_height).to_le_bytes());
+
+        Self {
+            transform: mat,
+            clip_rect,
+            uniform_buffer,
+            smooth,
+            viewport,
+            full_viewport,
+            screen: (x, y, scaled_width, scaled_height),
+        }
+    }
+
+    pub(crate) fn clip_rect(&self) -> (u32, u32, u32, u32) {
+        self.clip_rect
+    }
+}
+
+/// Converts an indexed color texture to `RGBA` by looking up each index in a palette.
+///
+/// The output texture has the same size as the indexed texture, and is the input for the
+/// [`ScalingRenderer`].
+#[derive(Debug)]
+pub(crate) struct PaletteRenderer {
+    palette_texture: wgpu::Texture,
+    output_texture: wgpu::Texture,
+    output_view: wgpu::TextureView,
+    bind_group: wgpu::BindGroup,
+    // Shared with every palette renderer on the device
+    pipeline: Arc<PalettePipeline>,
+    colors: [[u8; 4]; 256],
+}
+
+impl PaletteRenderer {
+    /// The texture format for the palette and the output texture.
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pipeline_cache: &PipelineCache,
+        index_view: &wgpu::TextureView,
+        texture_size: &wgpu::Extent3d,
+    ) -> Self {
+        let pipeline = pipeline_cache.palette(device);
+
+        // The default palette is a grayscale ramp
+        let mut colors = [[0; 4]; 256];
+        for (i, color) in colors.iter_mut().enumerate() {
+            *color = [i as u8, i as u8, i as u8, 0xff];
+        }
+        let palette_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("pixels_palette_renderer_palette_texture"),
+                size: wgpu::Extent3d {
+                    width: 256,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: Self::FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &colors.concat(),
+        );
+        let palette_view = palette_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Create the output texture
+        let output_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pixels_palette_renderer_output_texture"),
+            size: *texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let output_view = output_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Create bind group
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_palette_renderer_bind_group"),
+            layout: &pipeline.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(index_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&palette_view),
+                },
+            ],
+        });
+
+        Self {
+            palette_texture,
+            output_texture,
+            output_view,
+            bind_group,
+            pipeline,
+            colors,
+        }
+    }
+
+    /// The `RGBA` output texture view, for use as the input to the [`ScalingRenderer`].
+    pub(crate) fn output_view(&self) -> &wgpu::TextureView {
+        &self.output_view
+    }
+
+    /// The `RGBA` output texture.
+    pub(crate) fn output_texture(&self) -> &wgpu::Texture {
+        &self.output_texture
+    }
+
+    /// The current palette colors.
+    pub(crate) fn colors(&self) -> &[[u8; 4]; 256] {
+        &self.colors
+    }
+
+    /// Replace palette colors, starting at index 0.
+    pub(crate) fn set_colors(&mut self, queue: &wgpu::Queue, colors: &[[u8; 4]]) {
+        self.colors[..colors.len()].copy_from_slice(colors);
+        queue.write_texture(
+            self.palette_texture.as_image_copy(),
+            &self.colors.concat(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(256 * 4),
+                rows_per_image: Some(1),
+            },
+            self.palette_texture.size(),
+        );
+    }
+
+    /// Convert the indexed texture to `RGBA`.
+    pub(crate) fn render(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("pixels_palette_renderer_render_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.output_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.pipeline.render_pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+/// The layout and pipeline of the palette renderer.
+#[derive(Debug)]
+pub(crate) struct PalettePipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl PalettePipeline {
+    fn new(device: &wgpu::Device) -> Self {
+        let shader = wgpu::include_wgsl!("../shaders/palette.wgsl");
+        let module = device.create_shader_module(shader);
+
+        // Create bind group layout
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_palette_renderer_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Uint,
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        // Create pipeline
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("pixels_palette_renderer_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("pixels_palette_renderer_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: PaletteRenderer::FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Self {
+            bind_group_layout,
+            render_pipeline,
+        }
+    }
+}
+
+/// The phosphor mask simulated by the [`CrtRenderer`].
+#[cfg(feature = "crt")]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum CrtMask {
+    /// No mask.
+    None,
+    /// Vertical stripes of red, green, and blue phosphors, like a Trinitron.
+    #[default]
+    ApertureGrille,
+    /// Staggered triads of red, green, and blue phosphors.
+    ShadowMask,
+}
+
+/// Parameters for the [`CrtRenderer`].
+///
+/// Every effect is disabled by setting its parameter to `0.0`. The defaults give a subtle CRT
+/// look; set them with [`Pixels::set_crt`](crate::Pixels::set_crt).
+#[cfg(feature = "crt")]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct CrtOptions {
+    /// How much the gaps between pixel buffer rows are darkened, from `0.0` to `1.0`.
+    pub scanlines: f32,
+    /// The phosphor mask pattern.
+    pub mask: CrtMask,
+    /// How much the mask darkens the other color channels of each subpixel, from `0.0` to `1.0`.
+    pub mask_strength: f32,
+    /// The amount of barrel distortion. `0.0` is a flat screen.
+    pub curvature: f32,
+    /// How much the corners are darkened, from `0.0` to `1.0`.
+    pub vignette: f32,
+    /// How much bright pixels glow into their neighbors.
+    pub bloom: f32,
+}
+
+#[cfg(feature = "crt")]
+impl Default for CrtOptions {
+    fn default() -> Self {
+        Self {
+            scanlines: 0.4,
+            mask: CrtMask::default(),
+            mask_strength: 0.2,
+            curvature: 0.04,
+            vignette: 0.2,
+            bloom: 0.1,
+        }
+    }
+}
+
+/// A post-processing renderer that simulates a CRT display.
+///
+/// It draws scanlines, a phosphor mask, barrel curvature, a vignette, and bloom over the scaled
+/// pixel buffer. The renderer is enabled with [`Pixels::set_crt`](crate::Pixels::set_crt) and
+/// runs after all other passes added with [`Pixels::add_pass`](crate::Pixels::add_pass).
+/// [`Pixels::window_pos_to_pixel`](crate::Pixels::window_pos_to_pixel) accounts for the
+/// curvature.
+#[cfg(feature = "crt")]
+#[derive(Debug)]
+pub struct CrtRenderer {
+    uniform_buffer: wgpu::Buffer,
+    bind_group: Option<wgpu::BindGroup>,
+    sampler: wgpu::Sampler,
+    // Shared with every CRT renderer on the device that has the same format
+    pipeline: Arc<CrtPipeline>,
+    options: CrtOptions,
+    // The scaled buffer on the surface before clipping: x, y, width, height
+    screen: (f32, f32, f32, f32),
+    texture_size: (f32, f32),
+    surface_size: (f32, f32),
+    clear_color: wgpu::Color,
+}
+
+#[cfg(feature = "crt")]
+impl CrtRenderer {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        pipeline_cache: &PipelineCache,
+        render_texture_format: wgpu::TextureFormat,
+        options: CrtOptions,
+    ) -> Self {
+        let pipeline = pipeline_cache.crt(device, render_texture_format);
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("pixels_crt_renderer_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 1.0,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pixels_crt_renderer_uniform_buffer"),
+            size: Self::UNIFORM_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            uniform_buffer,
+            bind_group: None,
+            sampler,
+            pipeline,
+            options,
+            screen: (0.0, 0.0, 0.0, 0.0),
+            texture_size: (0.0, 0.0),
+            surface_size: (0.0, 0.0),
+            clear_color: wgpu::Color::BLACK,
+        }
+    }
+
+    /// The size of the uniform buffer in bytes.
+    const UNIFORM_SIZE: u64 = 80;
+
+    /// Get the current parameters.
+    pub fn options(&self) -> &CrtOptions {
+        &self.options
+    }
+
+    pub(crate) fn set_options(&mut self, queue: &wgpu::Queue, options: CrtOptions) {
+        self.options = options;
+        self.write_uniforms(queue);
+    }
+
+    /// Update the placement of the scaled pixel buffer.
+    pub(crate) fn update(
+        &mut self,
+        queue: &wgpu::Queue,
+        matrix: &ScalingMatrix,
+        texture_size: (f32, f32),
+        surface_size: (f32, f32),
+        clear_color: wgpu::Color,
+    ) {
+        self.screen = matrix.screen;
+        self.texture_size = texture_size;
+        self.surface_size = surface_size;
+        self.clear_color = clear_color;
+        self.write_uniforms(queue);
+    }
+
+    /// Map a physical position on the surface to the position that is displayed there after the
+    /// barrel distortion.
+    pub(crate) fn distort(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (screen_x, screen_y, width, height) = self.screen;
+        if self.options.curvature == 0.0 || width <= 0.0 || height <= 0.0 {
+            return (x, y);
+        }
+
+        // Positions outside of the scaled buffer are not distorted
+        let u = (x - screen_x) / width;
+        let v = (y - screen_y) / height;
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return (x, y);
+        }
+
+        // This must match `fs_main` in `crt.wgsl`
+        let (cx, cy) = (u * 2.0 - 1.0, v * 2.0 - 1.0);
+        let scale = 1.0 + self.options.curvature * (cx * cx + cy * cy);
+
+        (
+            screen_x + (cx * scale * 0.5 + 0.5) * width,
+            screen_y + (cy * scale * 0.5 + 0.5) * height,
+        )
+    }
+
+    fn write_uniforms(&self, queue: &wgpu::Queue) {
+        let (x, y, width, height) = self.screen;
+        let color = self.clear_color;
+        let options = &self.options;
+        let floats = [
+            x,
+            y,
+            width,
+            height,
+            self.texture_size.0,
+            self.texture_size.1,
+            self.surface_size.0,
+            self.surface_size.1,
+            color.r as f32,
+            color.g as f32,
+            color.b as f32,
+            color.a as f32,
+            options.scanlines,
+            options.mask_strength,
+            options.curvature,
+            options.vignette,
+            options.bloom,
+        ];
+        let mask: u32 = match options.mask {
+            CrtMask::None => 0,
+            CrtMask::ApertureGrille => 1,
+            CrtMask::ShadowMask => 2,
+        };
+
+        let mut data = Vec::with_capacity(Self::UNIFORM_SIZE as usize);
+        for value in floats {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&mask.to_le_bytes());
+        data.resize(Self::UNIFORM_SIZE as usize, 0);
+
+        queue.write_buffer(&self.uniform_buffer, 0, &data);
+    }
+}
+
+#[cfg(feature = "crt")]
+impl crate::RenderPass for CrtRenderer {
+    fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        input: &wgpu::TextureView,
+        _width: u32,
+        _height: u32,
+    ) {
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pixels_crt_renderer_bind_group"),
+            layout: &self.pipeline.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+            ],
+        }));
+    }
+
+    fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        _context: &crate::PassContext<'_>,
+    ) {
+        // The bind group is created when the renderer is added to the pass chain
+        let Some(bind_group) = &self.bind_group else {
+            return;
+        };
+
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("pixels_crt_renderer_render_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.clear_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.pipeline.render_pipeline);
+        rpass.set_bind_group(0, bind_group, &[]);
+        rpass.draw(0..3, 0..1);
+    }
+}
+
+/// The layout and pipeline of the CRT renderer for one render texture format.
+#[cfg(feature = "crt")]
+#[derive(Debug)]
+pub(crate) struct CrtPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+#[cfg(feature = "crt")]
+impl CrtPipeline {
+    fn new(
+        device: &wgpu::Device,
+        module: &wgpu::ShaderModule,
+        render_texture_format: wgpu::TextureFormat,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("pixels_crt_renderer_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(CrtRenderer::UNIFORM_SIZE),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("pixels_crt_renderer_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("pixels_crt_renderer_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: render_texture_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Self {
+            bind_group_layout,
+            render_pipeline,
+        }
     }
 }
@@ -0,0 +1,20 @@
+use crate::{Error, RgbaImage};
+
+/// Write an image to a PNG file, tagged as sRGB.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn write_png(path: &std::path::Path, image: &RgbaImage) -> Result<(), Error> {
+    use std::fs::File;
+    use std::io::BufWriter;
+
+    let file = BufWriter::new(File::create(path).map_err(png::EncodingError::from)?);
+    let mut encoder = png::Encoder::new(file, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image.pixels())?;
+    writer.finish()?;
+
+    Ok(())
+}
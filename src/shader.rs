@@ -0,0 +1,165 @@
+//! Hot reloading of WGSL shaders from disk during development.
+//!
+//! A [`ShaderWatcher`] compiles a shader from a file and recompiles it when the file is modified.
+//! Compile errors are returned with the diagnostic from `naga`, and the previous shader module is
+//! kept, so a typo in a shader does not bring down the application.
+//!
+//! The scaling renderer can use a watched shader with [`Pixels::set_scaling_shader`], and custom
+//! [`RenderPass`] implementations can reload theirs in [`RenderPass::reload_shaders`]. Call
+//! [`Pixels::reload_shaders`] once per frame to pick up changes.
+//!
+//! [`Pixels::set_scaling_shader`]: crate::Pixels::set_scaling_shader
+//! [`Pixels::reload_shaders`]: crate::Pixels::reload_shaders
+//! [`RenderPass`]: crate::RenderPass
+//! [`RenderPass::reload_shaders`]: crate::RenderPass::reload_shaders
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use thiserror::Error;
+
+/// All the ways in which loading a shader can fail.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ShaderError {
+    /// The shader file could not be read.
+    #[error("Unable to read the shader file `{0}`.")]
+    Io(PathBuf, #[source] std::io::Error),
+    /// The shader failed to compile, or a pipeline could not be created from it. Contains the
+    /// diagnostic reported by `wgpu` and `naga`.
+    #[error("Shader `{0}` failed to compile:\n{1}")]
+    Compile(PathBuf, String),
+}
+
+/// A WGSL shader module that is recompiled when its file is modified.
+///
+/// # Example
+///
+/// ```no_run
+/// use pixels::shader::{ShaderError, ShaderWatcher};
+/// use pixels::wgpu;
+///
+/// fn watch_noise(device: &wgpu::Device) -> Result<(), ShaderError> {
+///     let mut watcher = ShaderWatcher::new(device, "shaders/noise.wgsl")?;
+///     let mut pipeline = watcher.build(device, |module| {
+///         // Create a render pipeline with `module`
+/// #       let _ = module;
+///     })?;
+///
+///     // Later, once per frame
+///     match watcher.poll(device) {
+///         Ok(true) => match watcher.build(device, |module| { /* ... */ }) {
+///             Ok(new_pipeline) => pipeline = new_pipeline,
+///             Err(err) => eprintln!("{err}"),
+///         },
+///         Ok(false) => (),
+///         Err(err) => eprintln!("{err}"),
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ShaderWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    module: wgpu::ShaderModule,
+}
+
+impl ShaderWatcher {
+    /// Read and compile a WGSL shader file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the file cannot be read or the shader does not compile.
+    pub fn new<P: AsRef<Path>>(device: &wgpu::Device, path: P) -> Result<Self, ShaderError> {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified(&path)?;
+        let module = compile(device, &path)?;
+
+        Ok(Self {
+            path,
+            modified,
+            module,
+        })
+    }
+
+    /// The path of the shader file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The most recent shader module that compiled successfully.
+    pub fn module(&self) -> &wgpu::ShaderModule {
+        &self.module
+    }
+
+    /// Recompile the shader if its file was modified since it was last compiled.
+    ///
+    /// Returns `true` when a new module was compiled. Pipelines must be recreated with
+    /// [`ShaderWatcher::build`] to use it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the file cannot be read or the shader does not compile. The previous
+    /// module is kept, and the same modification is not reported again.
+    pub fn poll(&mut self, device: &wgpu::Device) -> Result<bool, ShaderError> {
+        let modified = modified(&self.path)?;
+        if modified == self.modified {
+            return Ok(false);
+        }
+
+        self.modified = modified;
+        self.module = compile(device, &self.path)?;
+
+        Ok(true)
+    }
+
+    /// Create GPU objects from the shader module, e.g. a render pipeline, and catch any validation
+    /// errors instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when `build` causes a validation error, e.g. when an entry point is
+    /// missing or the shader interface does not match the pipeline layout.
+    pub fn build<T, F>(&self, device: &wgpu::Device, build: F) -> Result<T, ShaderError>
+    where
+        F: FnOnce(&wgpu::ShaderModule) -> T,
+    {
+        catch(device, || build(&self.module))
+            .map_err(|diagnostic| ShaderError::Compile(self.path.clone(), diagnostic))
+    }
+}
+
+/// Read and compile a WGSL shader file.
+fn compile(device: &wgpu::Device, path: &Path) -> Result<wgpu::ShaderModule, ShaderError> {
+    let source =
+        std::fs::read_to_string(path).map_err(|err| ShaderError::Io(path.to_path_buf(), err))?;
+    let label = path.to_string_lossy();
+
+    catch(device, || {
+        device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&label),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        })
+    })
+    .map_err(|diagnostic| ShaderError::Compile(path.to_path_buf(), diagnostic))
+}
+
+/// Run `f` in a validation error scope, returning the error description.
+fn catch<T>(device: &wgpu::Device, f: impl FnOnce() -> T) -> Result<T, String> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = f();
+
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(err) => Err(err.to_string()),
+        None => Ok(value),
+    }
+}
+
+fn modified(path: &Path) -> Result<Option<SystemTime>, ShaderError> {
+    let metadata =
+        std::fs::metadata(path).map_err(|err| ShaderError::Io(path.to_path_buf(), err))?;
+
+    // Platforms without modification times only compile the shader once
+    Ok(metadata.modified().ok())
+}
@@ -0,0 +1,582 @@
+//! Bitmap font text rendering for the pixel buffer.
+//!
+//! Text is drawn directly into a [`FrameMut`], so it is rendered at the resolution of the pixel
+//! buffer and scales with it. A built-in 8x8 font covering ASCII and Latin-1 is available with
+//! [`BitmapFont::default`], and fonts can be loaded from BDF and PSF (version 1 and 2) files.
+//!
+//! Text is clipped to the frame, like the primitives in the [`draw`](crate::draw) module, and
+//! supports the same texture formats.
+//!
+//! This module requires the `text` cargo feature.
+//!
+//! # Example
+//!
+//! ```
+//! use pixels::text::{self, BitmapFont};
+//! use pixels::FrameMut;
+//!
+//! let mut data = vec![0; 64 * 48 * 4];
+//! let mut frame = FrameMut::new(&mut data, 64, 48, pixels::wgpu::TextureFormat::Rgba8UnormSrgb);
+//! let font = BitmapFont::default();
+//!
+//! text::draw_text(&mut frame, &font, 0, 0, "Score: 42", [0xff, 0xff, 0xff, 0xff]);
+//! text::draw_text_wrapped(
+//!     &mut frame,
+//!     &font,
+//!     0,
+//!     16,
+//!     64,
+//!     "Text is wrapped at word boundaries",
+//!     [0xff, 0xff, 0x00, 0xff],
+//! );
+//! ```
+
+use crate::draw::{encode, set_clipped};
+use crate::FrameMut;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// All the ways in which loading a font can fail.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum FontError {
+    /// The BDF font is malformed.
+    #[error("Invalid BDF font on line {line}: {reason}")]
+    Bdf {
+        /// The line number, starting at 1.
+        line: usize,
+        /// What is wrong with the line.
+        reason: &'static str,
+    },
+    /// The PSF font is malformed.
+    #[error("Invalid PSF font: {0}")]
+    Psf(&'static str),
+}
+
+/// A single character in a [`BitmapFont`].
+#[derive(Debug, Clone)]
+struct Glyph {
+    width: u32,
+    height: u32,
+    // Offset of the top-left corner from the pen position at the top of the line
+    x_offset: i32,
+    y_offset: i32,
+    // Horizontal distance to the next pen position
+    advance: i32,
+    // Row-major, one entry per pixel
+    bitmap: Vec<bool>,
+}
+
+impl Glyph {
+    /// Create a glyph from rows of bits, where the most significant bit of the first byte in each
+    /// row is the leftmost pixel.
+    fn from_rows(width: u32, height: u32, rows: &[u8], bytes_per_row: usize) -> Self {
+        let bitmap = rows
+            .chunks_exact(bytes_per_row)
+            .take(height as usize)
+            .flat_map(|row| (0..width as usize).map(move |x| row[x / 8] & (0x80 >> (x % 8)) != 0))
+            .collect();
+
+        Self {
+            width,
+            height,
+            x_offset: 0,
+            y_offset: 0,
+            advance: width as i32,
+            bitmap,
+        }
+    }
+}
+
+/// A font made of bitmaps, for drawing crisp text at the pixel buffer resolution.
+#[derive(Debug, Clone)]
+pub struct BitmapFont {
+    glyphs: HashMap<char, Glyph>,
+    line_height: u32,
+    // The advance for characters without a glyph
+    default_advance: i32,
+}
+
+impl Default for BitmapFont {
+    /// The built-in 8x8 font, covering printable ASCII and Latin-1 characters.
+    fn default() -> Self {
+        use font8x8::legacy::{BASIC_LEGACY, LATIN_LEGACY};
+
+        let basic = (0x20..0x7f).map(|code| (code, BASIC_LEGACY[code as usize]));
+        let latin = (0xa0..=0xff).map(|code| (code, LATIN_LEGACY[code as usize - 0xa0]));
+        let glyphs = basic
+            .chain(latin)
+            .map(|(code, rows)| {
+                // The least significant bit is the leftmost pixel in this font
+                let rows = rows.map(u8::reverse_bits);
+
+                (char::from(code as u8), Glyph::from_rows(8, 8, &rows, 1))
+            })
+            .collect();
+
+        Self {
+            glyphs,
+            line_height: 8,
+            default_advance: 8,
+        }
+    }
+}
+
+impl BitmapFont {
+    /// Load a font in the Glyph Bitmap Distribution Format (BDF).
+    ///
+    /// Glyph encodings are assumed to be Unicode code points, which is the case for ISO 10646 and
+    /// ISO 8859-1 fonts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the font is malformed.
+    pub fn from_bdf(data: &[u8]) -> Result<Self, FontError> {
+        BdfParser::default().parse(data)
+    }
+
+    /// Load a PC Screen Font, version 1 or 2 (PSF), as used by the Linux console.
+    ///
+    /// When the font has no Unicode table, glyph indices are used as code points.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the font is malformed.
+    pub fn from_psf(data: &[u8]) -> Result<Self, FontError> {
+        let error = FontError::Psf;
+        let u32_at = |offset: usize| {
+            data.get(offset..offset + 4)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+                .ok_or(error("Truncated header"))
+        };
+
+        let (width, height, glyph_count, glyph_size, glyph_offset, unicode) = match data {
+            [0x36, 0x04, mode, char_size, ..] => {
+                let glyph_count = if mode & 0x01 != 0 { 512 } else { 256 };
+                let unicode = mode & 0x06 != 0;
+
+                (
+                    8,
+                    *char_size as usize,
+                    glyph_count,
+                    *char_size as usize,
+                    4,
+                    unicode,
+                )
+            }
+            [0x72, 0xb5, 0x4a, 0x86, ..] => {
+                let header_size = u32_at(8)?;
+                let unicode = u32_at(12)? & 0x01 != 0;
+                let glyph_count = u32_at(16)?;
+                let glyph_size = u32_at(20)?;
+                let height = u32_at(24)?;
+                let width = u32_at(28)?;
+
+                (width, height, glyph_count, glyph_size, header_size, unicode)
+            }
+            _ => return Err(error("Unknown magic number")),
+        };
+        let bytes_per_row = width.div_ceil(8);
+        if width == 0 || height == 0 || glyph_size < bytes_per_row * height {
+            return Err(error("Invalid glyph size"));
+        }
+
+        let glyph_data = glyph_count
+            .checked_mul(glyph_size)
+            .and_then(|len| data.get(glyph_offset..glyph_offset.checked_add(len)?))
+            .ok_or(error("Truncated glyph data"))?;
+        let glyphs: Vec<_> = glyph_data
+            .chunks_exact(glyph_size)
+            .map(|rows| Glyph::from_rows(width as u32, height as u32, rows, bytes_per_row))
+            .collect();
+
+        let table = &data[glyph_offset + glyph_data.len()..];
+        let map = if !unicode {
+            glyphs
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, glyph)| Some((char::from_u32(index as u32)?, glyph)))
+                .collect()
+        } else if data[0] == 0x36 {
+            psf1_unicode_table(table, &glyphs)?
+        } else {
+            psf2_unicode_table(table, &glyphs)?
+        };
+
+        Ok(Self {
+            glyphs: map,
+            line_height: height as u32,
+            default_advance: width as i32,
+        })
+    }
+
+    /// The distance between the tops of two lines of text, in pixels.
+    pub fn line_height(&self) -> u32 {
+        self.line_height
+    }
+
+    /// Whether the font has a glyph for the character.
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.glyphs.contains_key(&c)
+    }
+
+    /// Measure the size of text in pixels, as drawn by [`draw_text`].
+    pub fn measure(&self, text: &str) -> (u32, u32) {
+        let width = text
+            .lines()
+            .map(|line| self.line_width(line))
+            .max()
+            .unwrap_or_default();
+        let lines = text.lines().count().max(1) as u32;
+
+        (width, lines * self.line_height)
+    }
+
+    /// Split text into lines that fit within `max_width` pixels.
+    ///
+    /// Lines are broken at whitespace where possible, and within words that are too long to fit
+    /// on a line of their own. Explicit line breaks are kept.
+    pub fn wrap<'a>(&self, text: &'a str, max_width: u32) -> Vec<&'a str> {
+        let mut lines = Vec::new();
+
+        for paragraph in text.lines() {
+            let mut rest = paragraph;
+            loop {
+                let (line, next) = self.break_line(rest, max_width);
+                lines.push(line);
+                match next {
+                    Some(next) => rest = next,
+                    None => break,
+                }
+            }
+        }
+
+        lines
+    }
+
+    /// Find the longest prefix of `text` that fits within `max_width`, and the remaining text.
+    fn break_line<'a>(&self, text: &'a str, max_width: u32) -> (&'a str, Option<&'a str>) {
+        let mut width = 0;
+        let mut last_space = None;
+
+        for (i, c) in text.char_indices() {
+            let advance = i64::from(self.advance(c));
+            if width + advance > i64::from(max_width) && i > 0 {
+                let end = match last_space {
+                    Some(space) if !text[..space].trim_end().is_empty() => space,
+                    _ => i,
+                };
+                let rest = text[end..].trim_start();
+
+                return (text[..end].trim_end(), (!rest.is_empty()).then_some(rest));
+            }
+            if c.is_whitespace() {
+                last_space = Some(i);
+            }
+            width += advance;
+        }
+
+        (text.trim_end(), None)
+    }
+
+    fn line_width(&self, line: &str) -> u32 {
+        let width: i64 = line.chars().map(|c| i64::from(self.advance(c))).sum();
+
+        width.clamp(0, u32::MAX.into()) as u32
+    }
+
+    fn advance(&self, c: char) -> i32 {
+        self.glyphs
+            .get(&c)
+            .map_or(self.default_advance, |glyph| glyph.advance)
+    }
+}
+
+/// Draw text with its top-left corner at `(x, y)`.
+///
+/// Newlines start a new line. Characters without a glyph in the font are skipped.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported by the [`draw`](crate::draw)
+/// module.
+pub fn draw_text(
+    frame: &mut FrameMut,
+    font: &BitmapFont,
+    x: i32,
+    y: i32,
+    text: &str,
+    color: [u8; 4],
+) {
+    for (i, line) in text.lines().enumerate() {
+        draw_line(frame, font, x, y, i, line, color);
+    }
+}
+
+/// Draw text with its top-left corner at `(x, y)`, wrapped to fit within `max_width` pixels.
+///
+/// See [`BitmapFont::wrap`]. Returns the height of the text in pixels.
+///
+/// # Panics
+///
+/// Panics when the texture format of the frame is not supported by the [`draw`](crate::draw)
+/// module.
+pub fn draw_text_wrapped(
+    frame: &mut FrameMut,
+    font: &BitmapFont,
+    x: i32,
+    y: i32,
+    max_width: u32,
+    text: &str,
+    color: [u8; 4],
+) -> u32 {
+    let lines = font.wrap(text, max_width);
+    for (i, line) in lines.iter().enumerate() {
+        draw_line(frame, font, x, y, i, line, color);
+    }
+
+    lines.len() as u32 * font.line_height
+}
+
+fn draw_line(
+    frame: &mut FrameMut,
+    font: &BitmapFont,
+    x: i32,
+    y: i32,
+    line_number: usize,
+    line: &str,
+    color: [u8; 4],
+) {
+    let pixel = encode(frame.format(), color);
+    let top = y as i64 + line_number as i64 * font.line_height as i64;
+    let mut pen = x as i64;
+
+    for c in line.chars() {
+        let Some(glyph) = font.glyphs.get(&c) else {
+            pen += font.default_advance as i64;
+            continue;
+        };
+        let left = pen + glyph.x_offset as i64;
+        let glyph_top = top + glyph.y_offset as i64;
+        let rows = glyph.bitmap.chunks_exact(glyph.width.max(1) as usize);
+        for (gy, row) in rows.take(glyph.height as usize).enumerate() {
+            for (gx, _) in row.iter().enumerate().filter(|(_, &set)| set) {
+                set_clipped(frame, left + gx as i64, glyph_top + gy as i64, &pixel);
+            }
+        }
+        pen += glyph.advance as i64;
+    }
+}
+
+/// Map glyphs to characters with a PSF1 Unicode table of 16-bit code points.
+fn psf1_unicode_table(table: &[u8], glyphs: &[Glyph]) -> Result<HashMap<char, Glyph>, FontError> {
+    let mut map = HashMap::new();
+    let mut entries = table
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+
+    for glyph in glyphs {
+        let mut in_sequence = false;
+        loop {
+            match entries.next() {
+                None => return Err(FontError::Psf("Truncated Unicode table")),
+                Some(0xffff) => break,
+                // Multi-character sequences are not supported
+                Some(0xfffe) => in_sequence = true,
+                Some(code) if !in_sequence => {
+                    if let Some(c) = char::from_u32(code as u32) {
+                        map.entry(c).or_insert_with(|| glyph.clone());
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    Ok(map)
+}
+
+/// Map glyphs to characters with a PSF2 Unicode table of UTF-8 strings.
+fn psf2_unicode_table(table: &[u8], glyphs: &[Glyph]) -> Result<HashMap<char, Glyph>, FontError> {
+    let mut map = HashMap::new();
+    let mut entries = table.split(|&byte| byte == 0xff);
+
+    for glyph in glyphs {
+        let entry = entries
+            .next()
+            .ok_or(FontError::Psf("Truncated Unicode table"))?;
+        // Multi-character sequences follow 0xfe, and are not supported
+        let singles = entry.split(|&byte| byte == 0xfe).next().unwrap_or_default();
+        let singles =
+            std::str::from_utf8(singles).map_err(|_| FontError::Psf("Invalid UTF-8 in table"))?;
+        for c in singles.chars() {
+            map.entry(c).or_insert_with(|| glyph.clone());
+        }
+    }
+
+    Ok(map)
+}
+
+/// The largest glyph size, offset, and advance accepted in BDF fonts.
+const MAX_BDF_GLYPH_SIZE: i32 = 1024;
+
+/// Check a BDF bounding box of `[width, height, x_offset, y_offset]`, returning the top of the
+/// box relative to the baseline.
+///
+/// Returns `None` when the size is negative, or when the size or offsets are too large.
+fn check_bbx([width, height, x_offset, y_offset]: [i32; 4]) -> Option<i32> {
+    let size = 0..=MAX_BDF_GLYPH_SIZE;
+    let offset = -MAX_BDF_GLYPH_SIZE..=MAX_BDF_GLYPH_SIZE;
+    let valid = size.contains(&width)
+        && size.contains(&height)
+        && offset.contains(&x_offset)
+        && offset.contains(&y_offset);
+
+    valid.then(|| -(y_offset + height))
+}
+
+/// Parser state for BDF fonts.
+#[derive(Default)]
+struct BdfParser {
+    line: usize,
+    ascent: Option<i32>,
+    descent: Option<i32>,
+    bounding_box: Option<[i32; 4]>,
+    glyphs: HashMap<char, Glyph>,
+}
+
+impl BdfParser {
+    fn parse(mut self, data: &[u8]) -> Result<BitmapFont, FontError> {
+        let text = std::str::from_utf8(data).map_err(|_| self.error("Invalid UTF-8"))?;
+        let mut lines = text.lines();
+
+        // The encoding, advance, and bounding box of the current glyph
+        let mut encoding = None;
+        let mut advance = None;
+        let mut bbx = None;
+
+        while let Some(line) = self.next_line(&mut lines) {
+            let line_number = self.line;
+            let error = |reason| FontError::Bdf {
+                line: line_number,
+                reason,
+            };
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+            let numbers: Vec<i32> = match keyword {
+                "FONTBOUNDINGBOX" | "FONT_ASCENT" | "FONT_DESCENT" | "ENCODING" | "DWIDTH"
+                | "BBX" => words
+                    .map(|word| word.parse().map_err(|_| error("Invalid number")))
+                    .collect::<Result<_, _>>()?,
+                _ => Vec::new(),
+            };
+            let first = numbers.first().copied();
+            let four = || {
+                <[i32; 4]>::try_from(numbers.as_slice()).map_err(|_| error("Expected 4 numbers"))
+            };
+
+            match keyword {
+                "FONTBOUNDINGBOX" => {
+                    let bounding_box = four()?;
+                    check_bbx(bounding_box).ok_or_else(|| error("Invalid FONTBOUNDINGBOX"))?;
+                    self.bounding_box = Some(bounding_box);
+                }
+                "FONT_ASCENT" => self.ascent = first,
+                "FONT_DESCENT" => self.descent = first,
+                "STARTCHAR" => {
+                    encoding = None;
+                    advance = None;
+                    bbx = None;
+                }
+                "ENCODING" => encoding = first,
+                "DWIDTH" => {
+                    let range = -MAX_BDF_GLYPH_SIZE..=MAX_BDF_GLYPH_SIZE;
+                    if first.is_some_and(|width| !range.contains(&width)) {
+                        return Err(error("Invalid DWIDTH"));
+                    }
+                    advance = first;
+                }
+                "BBX" => {
+                    let glyph_bbx = four()?;
+                    check_bbx(glyph_bbx).ok_or_else(|| error("Invalid BBX"))?;
+                    bbx = Some(glyph_bbx);
+                }
+                "BITMAP" => {
+                    let [width, height, x_offset, y_offset] = bbx
+                        .or(self.bounding_box)
+                        .ok_or_else(|| error("Missing BBX"))?;
+                    // Both bounding boxes were checked when parsed, so the bitmap size is bounded
+                    let top = check_bbx([width, height, x_offset, y_offset])
+                        .ok_or_else(|| error("Invalid BBX"))?;
+                    let bytes_per_row = (width as usize).div_ceil(8);
+
+                    let mut rows = Vec::with_capacity(bytes_per_row * height as usize);
+                    for _ in 0..height {
+                        let line = self
+                            .next_line(&mut lines)
+                            .ok_or_else(|| self.error("Truncated bitmap"))?;
+                        for i in 0..bytes_per_row {
+                            let byte = line
+                                .get(i * 2..i * 2 + 2)
+                                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                                .ok_or_else(|| self.error("Invalid bitmap row"))?;
+                            rows.push(byte);
+                        }
+                    }
+
+                    let mut glyph =
+                        Glyph::from_rows(width as u32, height as u32, &rows, bytes_per_row.max(1));
+                    glyph.x_offset = x_offset;
+                    // Offsets are relative to the baseline until the ascent is known
+                    glyph.y_offset = top;
+                    glyph.advance = advance.unwrap_or(width);
+
+                    let c = encoding
+                        .and_then(|code| u32::try_from(code).ok())
+                        .and_then(char::from_u32);
+                    if let Some(c) = c {
+                        self.glyphs.insert(c, glyph);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let [width, height, _, y_offset] = self
+            .bounding_box
+            .ok_or_else(|| self.error("Missing FONTBOUNDINGBOX"))?;
+        let line = self.line;
+        let invalid_metrics = || FontError::Bdf {
+            line,
+            reason: "Invalid FONT_ASCENT or FONT_DESCENT",
+        };
+        // The bounding box was checked when parsed, so these cannot overflow
+        let ascent = self.ascent.unwrap_or(height + y_offset);
+        let descent = self.descent.unwrap_or(-y_offset);
+        let line_height = ascent.checked_add(descent).ok_or_else(invalid_metrics)?;
+        for glyph in self.glyphs.values_mut() {
+            glyph.y_offset = glyph
+                .y_offset
+                .checked_add(ascent)
+                .ok_or_else(invalid_metrics)?;
+        }
+
+        Ok(BitmapFont {
+            glyphs: self.glyphs,
+            line_height: line_height.max(0) as u32,
+            default_advance: width,
+        })
+    }
+
+    fn next_line<'a>(&mut self, lines: &mut std::str::Lines<'a>) -> Option<&'a str> {
+        self.line += 1;
+        lines.next().map(str::trim)
+    }
+
+    fn error(&self, reason: &'static str) -> FontError {
+        FontError::Bdf {
+            line: self.line,
+            reason,
+        }
+    }
+}